@@ -0,0 +1,106 @@
+// 性能测试 - 不打开窗口，测量 `cat` 大文件时终端每一帧的耗时
+//
+// 用法: ay-dev-tool-rust --bench-cat <文件>
+//
+// 文件内容按UI读取SSH输出的节奏（每帧最多10次 × 4KB）送入终端面板，每帧执行完整的egui帧：
+// 解析、排版、绘制和三角化（不含GPU提交）。输出结束后再测量一组没有新数据的空闲帧。
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context as _, Result};
+use eframe::egui;
+
+use crate::config::AppSettings;
+use crate::ui::fonts::{apply_fonts, SystemFonts};
+use crate::ui::SimpleTerminalPanel;
+use crate::utils::logger::{self, LogLevel};
+
+/// 每帧送入的字节数，与 `SimpleTerminalPanel::receive_ssh_output` 一帧最多读取的数据量相同
+const BYTES_PER_FRAME: usize = 10 * 4096;
+/// 输出结束后测量的空闲帧数
+const IDLE_FRAMES: usize = 100;
+/// 模拟的窗口大小（与默认窗口相同）
+const SCREEN_SIZE: egui::Vec2 = egui::vec2(1200.0, 800.0);
+
+/// 📊 运行性能测试并把结果打印到标准输出
+pub fn run_cat_benchmark(path: &Path) -> Result<()> {
+    let file = std::fs::read(path).with_context(|| format!("无法读取文件 {}", path.display()))?;
+    // 远程PTY会把 \n 转换为 \r\n
+    let mut data = Vec::with_capacity(file.len() + file.len() / 32);
+    for &byte in &file {
+        if byte == b'\n' {
+            data.push(b'\r');
+        }
+        data.push(byte);
+    }
+
+    // 逐块的调试日志（控制台和日志文件）会淹没渲染本身的耗时
+    if let Ok(mut logger) = logger::get_logger().lock() {
+        logger.set_min_level(LogLevel::Warn);
+    }
+
+    let ctx = egui::Context::default();
+    let settings = AppSettings::default();
+    apply_fonts(&ctx, &settings, &SystemFonts::scan());
+    let mut terminal = SimpleTerminalPanel::new("bench".to_string(), format!("cat {}", path.display()));
+    terminal.apply_settings(&settings);
+    terminal.set_font_size(settings.font_size as f32);
+
+    // 第一帧加载字体、确定行列数，不计入结果
+    run_frame(&ctx, &mut terminal, &[]);
+
+    let started = Instant::now();
+    let busy: Vec<Duration> = data
+        .chunks(BYTES_PER_FRAME)
+        .map(|chunk| run_frame(&ctx, &mut terminal, chunk))
+        .collect();
+    let total = started.elapsed();
+    let idle: Vec<Duration> = (0..IDLE_FRAMES).map(|_| run_frame(&ctx, &mut terminal, &[])).collect();
+
+    println!("📊 cat {} ({:.1} MB)", path.display(), file.len() as f64 / 1e6);
+    println!(
+        "   总耗时 {:.2} s，{:.1} MB/s",
+        total.as_secs_f64(),
+        file.len() as f64 / 1e6 / total.as_secs_f64()
+    );
+    print_stats("输出中", &busy);
+    print_stats("空闲", &idle);
+    Ok(())
+}
+
+/// 执行一帧：送入数据，运行终端界面并三角化
+fn run_frame(ctx: &egui::Context, terminal: &mut SimpleTerminalPanel, chunk: &[u8]) -> Duration {
+    let started = Instant::now();
+    if !chunk.is_empty() {
+        terminal.process_ssh_data(chunk);
+    }
+    let input = egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, SCREEN_SIZE)),
+        ..Default::default()
+    };
+    let output = ctx.run(input, |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| terminal.show(ui));
+    });
+    ctx.tessellate(output.shapes, output.pixels_per_point);
+    started.elapsed()
+}
+
+/// 打印帧耗时的平均值、中位数、p99和最大值
+fn print_stats(label: &str, frames: &[Duration]) {
+    if frames.is_empty() {
+        return;
+    }
+    let mut millis: Vec<f64> = frames.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+    millis.sort_by(f64::total_cmp);
+    let percentile = |p: f64| millis[((millis.len() - 1) as f64 * p).round() as usize];
+    println!(
+        "   {} {} 帧：平均 {:.2} ms，p50 {:.2} ms，p99 {:.2} ms，最大 {:.2} ms",
+        label,
+        millis.len(),
+        millis.iter().sum::<f64>() / millis.len() as f64,
+        percentile(0.5),
+        percentile(0.99),
+        millis[millis.len() - 1]
+    );
+}
@@ -1,5 +1,6 @@
 // App模块 - 使用设计模式的应用实现
 
 // 导入基于Tab系统的应用
+pub mod bench;
 pub mod tab_app;
 pub use tab_app::TabAppFactory;
\ No newline at end of file
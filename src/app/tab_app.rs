@@ -33,14 +33,18 @@ impl TabObserver for TabBasedApp {
 }
 
 impl TabBasedApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         crate::app_log!(info, "App", "启动基于Tab系统的应用");
 
         // 加载配置
         let config = AppConfig::load().unwrap_or_default();
 
         // 创建Tab管理器
-        let tab_manager = TabManager::new(config);
+        let tab_manager = TabManager::new(config, &cc.egui_ctx);
+        tab_manager.apply_fonts(&cc.egui_ctx);
+        cc.egui_ctx.set_visuals(tab_manager.theme().visuals());
+        // Ctrl+= / Ctrl+- 调整终端字号，而不是缩放整个界面
+        cc.egui_ctx.options_mut(|options| options.zoom_with_keyboard = false);
 
         // 注册观察者（暂时注释，因为需要解决借用问题）
         // let observer = Box::new(self);
@@ -72,9 +76,7 @@ impl eframe::App for TabBasedApp {
         egui::CentralPanel::default().show(ctx, |ui| {
             self.tab_manager.render_active_tab(ui);
         });
-
-        // 持续重绘以保证PTY数据实时读取
-        ctx.request_repaint();
+        // 不再逐帧重绘：SSH输出到达时由Actor唤醒，其余定时刷新由各面板自行请求
     }
 
     fn save(&mut self, _storage: &mut dyn eframe::Storage) {
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 use crate::ui::ConnectionConfig;
+use crate::terminal::emulator::DEFAULT_SCROLLBACK_LINES;
+use crate::ui::terminal::theme::DEFAULT_THEME;
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct AppConfig {
@@ -12,17 +14,63 @@ pub struct AppConfig {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppSettings {
+    /// 终端配色方案名称（内置主题或导入的配色文件）
     pub theme: String,
+    /// 终端字号（Ctrl+= / Ctrl+- 调整）
     pub font_size: u16,
+    /// 终端字体：文件路径或系统字体文件名，为空时自动选择系统等宽字体
+    #[serde(default)]
+    pub font_path: Option<String>,
+    /// 后备字体（文件路径或系统字体文件名），终端字体缺字时按顺序使用，之后是自动找到的CJK字体
+    #[serde(default)]
+    pub fallback_fonts: Vec<String>,
     pub refresh_interval: u64,
+    /// 终端回滚历史行数（每个Tab）
+    #[serde(default = "default_scrollback_lines")]
+    pub scrollback_lines: usize,
+    /// 选中文本后自动复制到剪贴板
+    #[serde(default)]
+    pub copy_on_select: bool,
+    /// 粘贴多行内容前先确认
+    #[serde(default = "default_true")]
+    pub confirm_multiline_paste: bool,
+    /// 响铃时终端闪烁一下（视觉响铃）
+    #[serde(default = "default_true")]
+    pub visual_bell: bool,
+    /// 后台Tab中的长命令结束时发送桌面通知（需要Shell集成）
+    #[serde(default = "default_true")]
+    pub notify_long_commands: bool,
+    /// 执行超过多少秒的命令算作长命令
+    #[serde(default = "default_long_command_secs")]
+    pub long_command_secs: u64,
+}
+
+fn default_scrollback_lines() -> usize {
+    DEFAULT_SCROLLBACK_LINES
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_long_command_secs() -> u64 {
+    10
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
-            theme: "default".to_string(),
+            theme: DEFAULT_THEME.to_string(),
             font_size: 14,
+            font_path: None,
+            fallback_fonts: Vec::new(),
             refresh_interval: 1000,
+            scrollback_lines: DEFAULT_SCROLLBACK_LINES,
+            copy_on_select: false,
+            confirm_multiline_paste: true,
+            visual_bell: true,
+            notify_long_commands: true,
+            long_command_secs: default_long_command_secs(),
         }
     }
 }
@@ -33,13 +81,32 @@ impl AppConfig {
 
         if config_path.exists() {
             let content = std::fs::read_to_string(&config_path)?;
-            let config: AppConfig = serde_json::from_str(&content)?;
+            let value: serde_json::Value = serde_json::from_str(&content)?;
+            let missing_ids = Self::missing_ids(&value);
+            let config: AppConfig = serde_json::from_value(value)?;
+            // 旧配置没有id，加载时生成的id要立即保存，否则每次启动都会变化，跳板机引用随之失效
+            if missing_ids {
+                match config.save() {
+                    Ok(()) => crate::app_log!(info, "Config", "🆔 已为旧配置中的连接和转发规则生成id"),
+                    Err(e) => crate::app_log!(warn, "Config", "保存生成的连接id失败: {}", e),
+                }
+            }
             Ok(config)
         } else {
             Ok(Self::default())
         }
     }
 
+    /// 配置中是否有缺少id的连接或转发规则
+    fn missing_ids(value: &serde_json::Value) -> bool {
+        let lacks_id = |item: &serde_json::Value| item.get("id").is_none();
+        let connections = value.get("connections").and_then(|c| c.as_array());
+        connections.into_iter().flatten().any(|connection| {
+            let forwards = connection.get("forwards").and_then(|f| f.as_array());
+            lacks_id(connection) || forwards.into_iter().flatten().any(lacks_id)
+        })
+    }
+
     pub fn save(&self) -> Result<()> {
         let config_path = Self::config_path()?;
 
@@ -53,6 +120,47 @@ impl AppConfig {
         Ok(())
     }
 
+    /// 🔗 解析连接的跳板机链（按连接顺序，跳板机自身的跳板机会先展开）
+    pub fn resolve_jump_chain(&self, connection: &ConnectionConfig) -> Result<Vec<ConnectionConfig>> {
+        let mut chain = Vec::new();
+        let mut visiting = vec![connection.id.clone()];
+        self.collect_jump_hosts(connection, &mut visiting, &mut chain)?;
+        Ok(chain)
+    }
+
+    fn collect_jump_hosts(
+        &self,
+        connection: &ConnectionConfig,
+        visiting: &mut Vec<String>,
+        chain: &mut Vec<ConnectionConfig>,
+    ) -> Result<()> {
+        for jump_id in &connection.jump_hosts {
+            let jump = self
+                .connections
+                .iter()
+                .find(|c| &c.id == jump_id)
+                .ok_or_else(|| anyhow::anyhow!("连接 {} 引用的跳板机不存在（可能已被删除）", connection.name))?;
+
+            if visiting.contains(&jump.id) {
+                return Err(anyhow::anyhow!("跳板机配置存在循环引用: {}", jump.name));
+            }
+
+            visiting.push(jump.id.clone());
+            self.collect_jump_hosts(jump, visiting, chain)?;
+            visiting.pop();
+            chain.push(jump.clone());
+        }
+        Ok(())
+    }
+
+    /// 导入的配色文件保存目录
+    pub fn themes_dir() -> Result<PathBuf> {
+        let config_dir =
+            dirs::config_dir().ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
+
+        Ok(config_dir.join("ay-dev-tool").join("themes"))
+    }
+
     fn config_path() -> Result<PathBuf> {
         let config_dir =
             dirs::config_dir().ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
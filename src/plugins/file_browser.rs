@@ -1,20 +1,55 @@
 use anyhow::Result;
 use serde_json::{Value, json};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc::Receiver;
 
 use super::Plugin;
+use crate::ssh::Ssh2Manager;
+use crate::ssh::sftp::{RemoteListing, parse_id_names};
+use crate::ssh::transfer::{TransferCommand, TransferDirection, TransferItem, TransferState};
+use crate::utils::{format_permissions, format_timestamp};
+
+/// 远程浏览目标 - 某个终端Tab的SSH会话
+#[derive(Clone)]
+pub struct RemoteTarget {
+    pub ssh_manager: Arc<Ssh2Manager>,
+    pub session_id: String,
+    pub label: String,
+}
+
+/// 排序字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Name,
+    Size,
+    Modified,
+}
 
 pub struct FileBrowser {
     current_path: PathBuf,
     files: Vec<FileInfo>,
+    remote: Option<RemoteTarget>,
+    remote_paths: HashMap<String, PathBuf>, // 每个会话最后浏览的路径
+    pending: Option<Receiver<Result<RemoteListing>>>,
+    error: Option<String>,
+    show_hidden: bool,
+    sort_key: SortKey,
+    sort_ascending: bool,
+    transfers: Vec<TransferItem>, // 当前会话的传输队列快照
 }
 
 #[derive(Debug, Clone)]
 struct FileInfo {
     name: String,
     is_directory: bool,
+    is_symlink: bool,
     size: u64,
-    modified: String,
+    permissions: String,
+    owner: String,
+    group: String,
+    modified: Option<u64>,
 }
 
 impl FileBrowser {
@@ -22,46 +57,293 @@ impl FileBrowser {
         Self {
             current_path: PathBuf::from("/"),
             files: Vec::new(),
+            remote: None,
+            remote_paths: HashMap::new(),
+            pending: None,
+            error: None,
+            show_hidden: false,
+            sort_key: SortKey::Name,
+            sort_ascending: true,
+            transfers: Vec::new(),
+        }
+    }
+
+    /// 切换浏览目标：Some为远程会话，None为本机
+    pub fn set_remote(&mut self, remote: Option<RemoteTarget>) {
+        if self.remote.as_ref().map(|r| &r.session_id) == remote.as_ref().map(|r| &r.session_id) {
+            return;
+        }
+
+        // 记住离开的会话所在目录
+        if let Some(previous) = &self.remote {
+            self.remote_paths
+                .insert(previous.session_id.clone(), self.current_path.clone());
+        }
+
+        self.current_path = match &remote {
+            // 空路径由服务器解析为用户主目录
+            Some(target) => self
+                .remote_paths
+                .get(&target.session_id)
+                .cloned()
+                .unwrap_or_default(),
+            None => PathBuf::from("/"),
+        };
+        crate::app_log!(info, "FileBrowser", "📁 浏览目标切换为: {}",
+            remote.as_ref().map_or("本机", |r| r.label.as_str()));
+
+        self.remote = remote;
+        self.files.clear();
+        self.transfers.clear();
+        self.pending = None;
+        self.error = None;
+        let _ = self.refresh_files();
+    }
+
+    /// 当前远程会话ID（本机模式为None）
+    pub fn remote_session(&self) -> Option<&str> {
+        self.remote.as_ref().map(|r| r.session_id.as_str())
+    }
+
+    /// 忘记已关闭会话的路径记录
+    pub fn forget_session(&mut self, session_id: &str) {
+        self.remote_paths.remove(session_id);
+        if self.remote_session() == Some(session_id) {
+            self.set_remote(None);
         }
     }
 
-    pub fn set_path(&mut self, path: PathBuf) {
+    pub fn navigate(&mut self, path: PathBuf) {
         self.current_path = path;
+        let _ = self.refresh_files();
+    }
+
+    /// 进入当前目录下的子目录
+    pub fn enter(&mut self, name: &str) {
+        let path = if self.remote.is_some() {
+            // 远程路径始终使用 `/` 分隔，不受本机平台影响
+            let current = self.current_path.to_string_lossy();
+            PathBuf::from(format!("{}/{}", current.trim_end_matches('/'), name))
+        } else {
+            self.current_path.join(name)
+        };
+        self.navigate(path);
+    }
+
+    pub fn go_up(&mut self) {
+        if let Some(parent) = self.current_path.parent() {
+            self.navigate(parent.to_path_buf());
+        }
+    }
+
+    pub fn set_show_hidden(&mut self, show_hidden: bool) {
+        self.show_hidden = show_hidden;
+    }
+
+    /// 点击同一列切换升降序，点击新列按升序排序
+    pub fn sort_by(&mut self, key: SortKey) {
+        if self.sort_key == key {
+            self.sort_ascending = !self.sort_ascending;
+        } else {
+            self.sort_key = key;
+            self.sort_ascending = true;
+        }
+        self.sort_files();
+    }
+
+    /// 📦 上传本地文件/目录到当前远程目录
+    pub fn upload(&mut self, local_paths: Vec<PathBuf>) {
+        let remote_dir = self.current_path.to_string_lossy().to_string();
+        for local in local_paths {
+            crate::app_log!(info, "FileBrowser", "📦 上传 {} -> {}", local.display(), remote_dir);
+            self.transfer(TransferCommand::Upload {
+                local,
+                remote_dir: remote_dir.clone(),
+            });
+        }
+    }
+
+    /// 当前目录下某一项的远程路径（始终使用 `/` 分隔）
+    pub fn entry_path(&self, name: &str) -> String {
+        let current = self.current_path.to_string_lossy();
+        format!("{}/{}", current.trim_end_matches('/'), name)
+    }
+
+    /// 📦 下载当前目录下的文件/目录到本地目录
+    pub fn download(&mut self, name: &str, is_dir: bool, local_dir: PathBuf) {
+        let remote = self.entry_path(name);
+        crate::app_log!(info, "FileBrowser", "📦 下载 {} -> {}", remote, local_dir.display());
+        self.transfer(TransferCommand::Download {
+            remote,
+            is_dir,
+            local_dir,
+        });
+    }
+
+    /// 📦 发送传输命令到当前远程会话
+    pub fn transfer(&mut self, command: TransferCommand) {
+        let Some(target) = &self.remote else {
+            return;
+        };
+        if let Err(e) = target.ssh_manager.transfer(&target.session_id, command) {
+            self.error = Some(e.to_string());
+        }
+    }
+
+    /// 检查远程目录请求是否完成，并同步传输队列
+    pub fn poll(&mut self) {
+        if let Some(target) = &self.remote {
+            let transfers = target.ssh_manager.transfer_status(&target.session_id);
+            // 有上传完成时刷新目录，新文件立即可见
+            let uploaded = |items: &[TransferItem]| {
+                items
+                    .iter()
+                    .filter(|item| {
+                        item.direction == TransferDirection::Upload && item.state == TransferState::Done
+                    })
+                    .count()
+            };
+            let refresh = uploaded(&transfers) > uploaded(&self.transfers) && self.pending.is_none();
+            self.transfers = transfers;
+            if refresh {
+                let _ = self.refresh_files();
+            }
+        }
+
+        let Some(result) = self.pending.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.pending = None;
+
+        match result {
+            Ok(listing) => {
+                self.current_path = listing.path;
+                self.files = listing
+                    .entries
+                    .into_iter()
+                    .map(|entry| FileInfo {
+                        permissions: entry
+                            .permissions
+                            .map(|mode| format_permissions(mode, entry.is_dir, entry.is_symlink))
+                            .unwrap_or_default(),
+                        name: entry.name,
+                        is_directory: entry.is_dir,
+                        is_symlink: entry.is_symlink,
+                        size: entry.size,
+                        owner: entry.owner,
+                        group: entry.group,
+                        modified: entry.modified,
+                    })
+                    .collect();
+                self.error = None;
+                self.sort_files();
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    pub fn refresh_files(&mut self) -> Result<()> {
+        match &self.remote {
+            Some(target) => {
+                // 远程：请求交给会话的Actor执行，结果在poll中处理
+                let path = self.current_path.to_string_lossy().to_string();
+                match target.ssh_manager.list_remote_dir(&target.session_id, &path) {
+                    Ok(pending) => self.pending = Some(pending),
+                    Err(e) => self.error = Some(e.to_string()),
+                }
+                Ok(())
+            }
+            None => self.refresh_local_files(),
+        }
     }
 
-    fn refresh_files(&mut self) -> Result<()> {
+    fn refresh_local_files(&mut self) -> Result<()> {
         self.files.clear();
+        self.error = None;
 
-        if let Ok(entries) = std::fs::read_dir(&self.current_path) {
-            for entry in entries.flatten() {
-                let metadata = entry.metadata()?;
-                let name = entry.file_name().to_string_lossy().to_string();
-                let modified = format!(
-                    "{:?}",
-                    metadata
+        #[cfg(unix)]
+        let (users, groups): (HashMap<u32, String>, HashMap<u32, String>) = (
+            std::fs::read_to_string("/etc/passwd")
+                .map(|content| parse_id_names(&content))
+                .unwrap_or_default(),
+            std::fs::read_to_string("/etc/group")
+                .map(|content| parse_id_names(&content))
+                .unwrap_or_default(),
+        );
+
+        match std::fs::read_dir(&self.current_path) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    let Ok(link_metadata) = entry.metadata() else {
+                        continue;
+                    };
+                    let is_symlink = entry.file_type().is_ok_and(|t| t.is_symlink());
+                    // 跟随符号链接判断是否为目录
+                    let metadata = std::fs::metadata(entry.path()).unwrap_or(link_metadata);
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let modified = metadata
                         .modified()
-                        .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
-                );
-
-                self.files.push(FileInfo {
-                    name,
-                    is_directory: metadata.is_dir(),
-                    size: metadata.len(),
-                    modified,
-                });
+                        .ok()
+                        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                        .map(|duration| duration.as_secs());
+
+                    #[cfg(unix)]
+                    let (permissions, owner, group) = {
+                        use std::os::unix::fs::MetadataExt;
+                        let name_of = |names: &HashMap<u32, String>, id: u32| {
+                            names.get(&id).cloned().unwrap_or_else(|| id.to_string())
+                        };
+                        (
+                            format_permissions(metadata.mode(), metadata.is_dir(), is_symlink),
+                            name_of(&users, metadata.uid()),
+                            name_of(&groups, metadata.gid()),
+                        )
+                    };
+                    #[cfg(not(unix))]
+                    let (permissions, owner, group) = (
+                        if metadata.permissions().readonly() { "只读" } else { "读写" }.to_string(),
+                        String::new(),
+                        String::new(),
+                    );
+
+                    self.files.push(FileInfo {
+                        name,
+                        is_directory: metadata.is_dir(),
+                        is_symlink,
+                        size: metadata.len(),
+                        permissions,
+                        owner,
+                        group,
+                        modified,
+                    });
+                }
+            }
+            Err(e) => {
+                self.error = Some(format!("无法读取目录 {}: {}", self.current_path.display(), e));
             }
         }
 
-        // 排序：目录在前，然后按名称排序
-        self.files
-            .sort_by(|a, b| match (a.is_directory, b.is_directory) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => a.name.cmp(&b.name),
-            });
-
+        self.sort_files();
         Ok(())
     }
+
+    /// 排序：目录始终在前，然后按选定的字段排序
+    fn sort_files(&mut self) {
+        let key = self.sort_key;
+        let ascending = self.sort_ascending;
+        self.files.sort_by(|a, b| {
+            b.is_directory.cmp(&a.is_directory).then_with(|| {
+                let ordering = match key {
+                    SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                    SortKey::Size => a.size.cmp(&b.size),
+                    SortKey::Modified => a.modified.cmp(&b.modified),
+                };
+                if ascending { ordering } else { ordering.reverse() }
+            })
+        });
+    }
 }
 
 impl Plugin for FileBrowser {
@@ -87,12 +369,17 @@ impl Plugin for FileBrowser {
         let files: Vec<Value> = self
             .files
             .iter()
+            .filter(|file| self.show_hidden || !file.name.starts_with('.'))
             .map(|file| {
                 json!({
                     "name": file.name,
                     "is_directory": file.is_directory,
+                    "is_symlink": file.is_symlink,
                     "size": file.size,
-                    "modified": file.modified,
+                    "permissions": file.permissions,
+                    "owner": file.owner,
+                    "group": file.group,
+                    "modified": file.modified.map(format_timestamp).unwrap_or_default(),
                     "type": if file.is_directory { "directory" } else { "file" }
                 })
             })
@@ -100,8 +387,46 @@ impl Plugin for FileBrowser {
 
         json!({
             "current_path": self.current_path.to_string_lossy(),
+            "mode": if self.remote.is_some() { "remote" } else { "local" },
+            "target": self.remote.as_ref().map_or("本机", |r| r.label.as_str()),
+            "loading": self.pending.is_some(),
+            "error": self.error,
+            "show_hidden": self.show_hidden,
+            "sort_key": match self.sort_key {
+                SortKey::Name => "name",
+                SortKey::Size => "size",
+                SortKey::Modified => "modified",
+            },
+            "sort_ascending": self.sort_ascending,
+            "file_count": files.len(),
             "files": files,
-            "file_count": self.files.len()
+            "transfers": self.transfers.iter().map(|item| json!({
+                "id": item.id,
+                "direction": match item.direction {
+                    TransferDirection::Upload => "upload",
+                    TransferDirection::Download => "download",
+                },
+                "name": item.name(),
+                "local_path": item.local_path.to_string_lossy(),
+                "remote_path": item.remote_path,
+                "is_dir": item.is_dir,
+                "transferred": item.transferred,
+                "total": item.total,
+                "progress": item.progress(),
+                "speed": item.speed,
+                "state": match item.state {
+                    TransferState::Queued => "queued",
+                    TransferState::Running => "running",
+                    TransferState::Done => "done",
+                    TransferState::Failed(_) => "failed",
+                    TransferState::Cancelled => "cancelled",
+                    TransferState::Skipped(_) => "skipped",
+                },
+                "error": match &item.state {
+                    TransferState::Failed(e) | TransferState::Skipped(e) => Some(e.as_str()),
+                    _ => None,
+                },
+            })).collect::<Vec<Value>>(),
         })
     }
 }
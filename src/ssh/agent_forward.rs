@@ -0,0 +1,237 @@
+use libssh2_sys as raw;
+use ssh2::Session;
+use std::ffi::c_void;
+use std::os::raw::{c_char, c_int};
+use std::sync::{Arc, Mutex};
+
+#[cfg(unix)]
+use std::io::{ErrorKind, Read, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+/// libssh2回调类型：服务器打开 auth-agent@openssh.com 通道
+const LIBSSH2_CALLBACK_AUTHAGENT: c_int = 7;
+
+unsafe extern "C" {
+    fn libssh2_session_callback_set(
+        session: *mut raw::LIBSSH2_SESSION,
+        cbtype: c_int,
+        callback: *mut c_void,
+    ) -> *mut c_void;
+}
+
+/// 服务器新打开、尚未接管的agent通道（回调中写入，Actor线程取出）
+type PendingChannels = Mutex<Vec<usize>>;
+
+/// libssh2回调：只记录通道指针，真正的数据转发在Actor线程的 `pump` 中完成
+unsafe extern "C" fn on_authagent_channel(
+    _session: *mut raw::LIBSSH2_SESSION,
+    channel: *mut raw::LIBSSH2_CHANNEL,
+    abstract_: *mut *mut c_void,
+) {
+    unsafe {
+        if abstract_.is_null() || (*abstract_).is_null() {
+            return;
+        }
+        let pending = &*(*abstract_ as *const PendingChannels);
+        if let Ok(mut pending) = pending.lock() {
+            pending.push(channel as usize);
+        }
+    }
+}
+
+/// 一条被转发的agent通道：远程channel <-> 本地SSH_AUTH_SOCK
+#[cfg(unix)]
+struct ForwardedChannel {
+    channel: *mut raw::LIBSSH2_CHANNEL,
+    agent: UnixStream,
+    to_remote: Vec<u8>,
+}
+
+/// 🔑 SSH Agent转发器 - 把远程shell中的agent请求转发到本地ssh-agent
+///
+/// libssh2只负责接受通道，数据需要由调用方在持有会话的线程里周期性地 `pump`。
+pub struct AgentForwarder {
+    session: Session,
+    pending: Arc<PendingChannels>,
+    #[cfg(unix)]
+    channels: Vec<ForwardedChannel>,
+}
+
+// 通道指针只在Actor线程中使用
+unsafe impl Send for AgentForwarder {}
+
+impl AgentForwarder {
+    /// 在会话上注册agent通道回调（必须在请求agent转发之前调用）
+    pub fn install(session: &Session) -> Self {
+        let pending: Arc<PendingChannels> = Arc::new(Mutex::new(Vec::new()));
+
+        {
+            let raw_session = session.raw();
+            let raw_ptr = &*raw_session as *const raw::LIBSSH2_SESSION as *mut _;
+            unsafe {
+                // 会话的abstract指针ssh2库未使用，这里用它传递待接管通道队列
+                *raw::libssh2_session_abstract(raw_ptr) = Arc::as_ptr(&pending) as *mut c_void;
+                libssh2_session_callback_set(
+                    raw_ptr,
+                    LIBSSH2_CALLBACK_AUTHAGENT,
+                    on_authagent_channel as *mut c_void,
+                );
+            }
+        }
+
+        crate::app_log!(info, "AgentForward", "🔑 已注册SSH Agent转发回调");
+
+        Self {
+            session: session.clone(),
+            pending,
+            #[cfg(unix)]
+            channels: Vec::new(),
+        }
+    }
+
+    /// 在Actor线程中调用：接管新通道并在两端之间搬运数据
+    #[cfg(unix)]
+    pub fn pump(&mut self) {
+        let new_channels: Vec<usize> = match self.pending.lock() {
+            Ok(mut pending) => pending.drain(..).collect(),
+            Err(_) => Vec::new(),
+        };
+
+        for channel in new_channels {
+            let channel = channel as *mut raw::LIBSSH2_CHANNEL;
+            match Self::connect_local_agent() {
+                Ok(agent) => {
+                    crate::app_log!(debug, "AgentForward", "🔑 远程请求使用本地agent");
+                    self.channels.push(ForwardedChannel {
+                        channel,
+                        agent,
+                        to_remote: Vec::new(),
+                    });
+                }
+                Err(e) => {
+                    crate::app_log!(warn, "AgentForward", "🔑 无法连接本地agent: {}", e);
+                    let _guard = self.session.raw();
+                    unsafe {
+                        raw::libssh2_channel_close(channel);
+                        raw::libssh2_channel_free(channel);
+                    }
+                }
+            }
+        }
+
+        let session = self.session.clone();
+        let _guard = session.raw();
+        self.channels.retain_mut(|forwarded| {
+            let alive = unsafe { Self::pump_channel(forwarded) };
+            if !alive {
+                unsafe {
+                    raw::libssh2_channel_close(forwarded.channel);
+                    raw::libssh2_channel_free(forwarded.channel);
+                }
+            }
+            alive
+        });
+    }
+
+    /// Windows上的agent是命名管道，暂不支持转发
+    #[cfg(not(unix))]
+    pub fn pump(&mut self) {
+        if let Ok(mut pending) = self.pending.lock() {
+            for channel in pending.drain(..) {
+                let channel = channel as *mut raw::LIBSSH2_CHANNEL;
+                let _guard = self.session.raw();
+                unsafe {
+                    raw::libssh2_channel_close(channel);
+                    raw::libssh2_channel_free(channel);
+                }
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn connect_local_agent() -> std::io::Result<UnixStream> {
+        let path = std::env::var("SSH_AUTH_SOCK")
+            .map_err(|_| std::io::Error::new(ErrorKind::NotFound, "SSH_AUTH_SOCK未设置"))?;
+        let agent = UnixStream::connect(path)?;
+        agent.set_nonblocking(true)?;
+        Ok(agent)
+    }
+
+    /// 搬运一次数据，返回通道是否仍然存活（调用方需持有会话锁）
+    #[cfg(unix)]
+    unsafe fn pump_channel(forwarded: &mut ForwardedChannel) -> bool {
+        let mut buffer = [0u8; 4096];
+
+        // 远程 -> 本地agent
+        loop {
+            let n = unsafe {
+                raw::libssh2_channel_read_ex(
+                    forwarded.channel,
+                    0,
+                    buffer.as_mut_ptr() as *mut c_char,
+                    buffer.len(),
+                )
+            };
+            if n > 0 {
+                if forwarded.agent.set_nonblocking(false).is_err()
+                    || forwarded.agent.write_all(&buffer[..n as usize]).is_err()
+                    || forwarded.agent.set_nonblocking(true).is_err()
+                {
+                    return false;
+                }
+            } else if n == raw::LIBSSH2_ERROR_EAGAIN as isize || n == 0 {
+                break;
+            } else {
+                return false;
+            }
+        }
+
+        // 本地agent -> 远程
+        loop {
+            match forwarded.agent.read(&mut buffer) {
+                Ok(0) => return false,
+                Ok(n) => forwarded.to_remote.extend_from_slice(&buffer[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => return false,
+            }
+        }
+
+        while !forwarded.to_remote.is_empty() {
+            let n = unsafe {
+                raw::libssh2_channel_write_ex(
+                    forwarded.channel,
+                    0,
+                    forwarded.to_remote.as_ptr() as *const c_char,
+                    forwarded.to_remote.len(),
+                )
+            };
+            if n > 0 {
+                forwarded.to_remote.drain(..n as usize);
+            } else if n == raw::LIBSSH2_ERROR_EAGAIN as isize {
+                break;
+            } else {
+                return false;
+            }
+        }
+
+        unsafe { raw::libssh2_channel_eof(forwarded.channel) == 0 }
+    }
+}
+
+impl Drop for AgentForwarder {
+    fn drop(&mut self) {
+        let raw_session = self.session.raw();
+        let raw_ptr = &*raw_session as *const raw::LIBSSH2_SESSION as *mut _;
+        unsafe {
+            libssh2_session_callback_set(raw_ptr, LIBSSH2_CALLBACK_AUTHAGENT, std::ptr::null_mut());
+            *raw::libssh2_session_abstract(raw_ptr) = std::ptr::null_mut();
+
+            #[cfg(unix)]
+            for forwarded in self.channels.drain(..) {
+                raw::libssh2_channel_close(forwarded.channel);
+                raw::libssh2_channel_free(forwarded.channel);
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use ssh2::{KeyboardInteractivePrompt, Prompt};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+
+/// 🔑 认证过程中需要用户参与的请求（由连接线程发往UI线程）
+pub enum AuthPrompt {
+    /// 私钥口令
+    Passphrase {
+        key_file: String,
+        /// 上一次输入的口令是否错误
+        retry: bool,
+        reply: Sender<Option<PassphraseReply>>,
+    },
+    /// keyboard-interactive 认证（OTP/2FA等）
+    KeyboardInteractive {
+        username: String,
+        instructions: String,
+        prompts: Vec<(String, bool)>, // (提示文本, 是否回显)
+        reply: Sender<Option<Vec<String>>>,
+    },
+}
+
+/// 用户输入的私钥口令
+pub struct PassphraseReply {
+    pub passphrase: String,
+    /// 是否在本次运行期间缓存口令
+    pub remember: bool,
+}
+
+/// 🔑 认证提示器 - 连接线程通过它阻塞等待UI的回答
+#[derive(Clone)]
+pub struct AuthPrompter {
+    sender: Sender<AuthPrompt>,
+}
+
+impl AuthPrompter {
+    pub fn new(sender: Sender<AuthPrompt>) -> Self {
+        Self { sender }
+    }
+
+    /// 请求私钥口令（返回None表示用户取消）
+    pub fn ask_passphrase(&self, key_file: &str, retry: bool) -> Option<PassphraseReply> {
+        let (reply, answer) = mpsc::channel();
+        self.sender
+            .send(AuthPrompt::Passphrase {
+                key_file: key_file.to_string(),
+                retry,
+                reply,
+            })
+            .ok()?;
+        answer.recv().ok().flatten()
+    }
+
+    /// 请求keyboard-interactive回答（返回None表示用户取消）
+    pub fn ask_keyboard_interactive(
+        &self,
+        username: &str,
+        instructions: &str,
+        prompts: Vec<(String, bool)>,
+    ) -> Option<Vec<String>> {
+        let (reply, answer) = mpsc::channel();
+        self.sender
+            .send(AuthPrompt::KeyboardInteractive {
+                username: username.to_string(),
+                instructions: instructions.to_string(),
+                prompts,
+                reply,
+            })
+            .ok()?;
+        answer.recv().ok().flatten()
+    }
+}
+
+/// keyboard-interactive 回调适配器
+pub struct InteractiveResponder<'a> {
+    pub prompter: Option<&'a AuthPrompter>,
+    /// 已保存的密码，用于自动回答单个密码提示
+    pub password: Option<&'a str>,
+    pub cancelled: bool,
+}
+
+impl KeyboardInteractivePrompt for InteractiveResponder<'_> {
+    fn prompt<'b>(
+        &mut self,
+        username: &str,
+        instructions: &str,
+        prompts: &[Prompt<'b>],
+    ) -> Vec<String> {
+        if prompts.is_empty() {
+            return Vec::new();
+        }
+
+        // 服务器用keyboard-interactive代替密码认证时，直接使用已保存的密码
+        if let (Some(password), [prompt]) = (self.password, prompts)
+            && !prompt.echo
+            && prompt.text.to_lowercase().contains("password")
+        {
+            crate::app_log!(debug, "SSH2", "keyboard-interactive: 使用已保存密码回答");
+            return vec![password.to_string()];
+        }
+
+        let questions = prompts
+            .iter()
+            .map(|p| (p.text.to_string(), p.echo))
+            .collect();
+        match self
+            .prompter
+            .and_then(|p| p.ask_keyboard_interactive(username, instructions, questions))
+        {
+            Some(answers) => answers,
+            None => {
+                self.cancelled = true;
+                vec![String::new(); prompts.len()]
+            }
+        }
+    }
+}
+
+/// 会话级私钥口令缓存（仅保存在内存中）
+fn passphrase_cache() -> &'static Mutex<HashMap<String, String>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn cached_passphrase(key_file: &str) -> Option<String> {
+    passphrase_cache().lock().ok()?.get(key_file).cloned()
+}
+
+pub fn cache_passphrase(key_file: &str, passphrase: &str) {
+    if let Ok(mut cache) = passphrase_cache().lock() {
+        cache.insert(key_file.to_string(), passphrase.to_string());
+    }
+}
+
+pub fn forget_passphrase(key_file: &str) {
+    if let Ok(mut cache) = passphrase_cache().lock() {
+        cache.remove(key_file);
+    }
+}
+
+/// 判断私钥文件是否被口令加密（PEM的ENCRYPTED头或OpenSSH格式的cipher字段）
+pub fn key_is_encrypted(key_file: &str) -> bool {
+    use base64::Engine;
+
+    let Ok(content) = std::fs::read_to_string(key_file) else {
+        return false;
+    };
+    if content.contains("ENCRYPTED") {
+        return true;
+    }
+    if !content.contains("BEGIN OPENSSH PRIVATE KEY") {
+        return false;
+    }
+
+    let body: String = content
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    let Ok(data) = base64::engine::general_purpose::STANDARD.decode(body.trim()) else {
+        return false;
+    };
+
+    // openssh-key-v1\0 + string ciphername
+    const MAGIC: &[u8] = b"openssh-key-v1\0";
+    let Some(rest) = data.strip_prefix(MAGIC) else {
+        return false;
+    };
+    if rest.len() < 4 {
+        return false;
+    }
+    let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+    rest.get(4..4 + len).is_some_and(|cipher| cipher != b"none")
+}
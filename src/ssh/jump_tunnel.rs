@@ -0,0 +1,156 @@
+use anyhow::{Result, anyhow};
+use libssh2_sys as raw;
+use ssh2::{Channel, ErrorCode};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::ssh2_client::Ssh2Connection;
+
+/// 🔗 跳板机隧道 - 把跳板机上的 direct-tcpip 通道桥接到本地回环socket
+///
+/// libssh2的会话只能运行在真实的socket上，所以下一跳的SSH会话连接到本地socket，
+/// 由后台线程在socket与跳板机通道之间搬运数据。
+pub struct JumpTunnel {
+    label: String,
+    running: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl JumpTunnel {
+    /// 通过已认证的跳板机打开到 `host:port` 的隧道，返回隧道和供下一跳使用的本地socket
+    pub fn open(hop: Ssh2Connection, host: &str, port: u16) -> Result<(Self, TcpStream)> {
+        let label = format!("{} ➜ {}:{}", hop.config.hop_label(), host, port);
+
+        let channel = hop
+            .session()
+            .channel_direct_tcpip(host, port, None)
+            .map_err(|e| anyhow!("跳板机 {} 无法转发到 {}:{}: {}", hop.config.hop_label(), host, port, e))?;
+
+        // 本地回环socket对：只接受我们自己发起的那一个连接
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let local = TcpStream::connect(listener.local_addr()?)?;
+        let (bridge, peer) = listener.accept()?;
+        if peer != local.local_addr()? {
+            return Err(anyhow!("跳板机隧道被其他本地连接抢占: {}", peer));
+        }
+        bridge.set_nonblocking(true)?;
+        bridge.set_nodelay(true)?;
+
+        hop.session().set_blocking(false);
+
+        let running = Arc::new(AtomicBool::new(true));
+        let worker = {
+            let running = Arc::clone(&running);
+            let label = label.clone();
+            thread::spawn(move || Self::run(hop, channel, bridge, running, label))
+        };
+
+        crate::app_log!(info, "JumpTunnel", "🔗 隧道已建立: {}", label);
+
+        Ok((
+            Self {
+                label,
+                running,
+                worker: Some(worker),
+            },
+            local,
+        ))
+    }
+
+    /// 隧道线程：持有跳板机连接，直到任一端关闭
+    fn run(
+        hop: Ssh2Connection,
+        mut channel: Channel,
+        mut bridge: TcpStream,
+        running: Arc<AtomicBool>,
+        label: String,
+    ) {
+        let mut buffer = [0u8; 16384];
+        let mut to_channel: Vec<u8> = Vec::new();
+        let mut to_bridge: Vec<u8> = Vec::new();
+
+        'pump: while running.load(Ordering::Relaxed) {
+            let mut idle = true;
+
+            // 💓 跳板机会话也需要保活，失联时关闭隧道，让目标会话随之报错
+            if let Err(e) = hop.session().keepalive_send()
+                && e.code() != ErrorCode::Session(raw::LIBSSH2_ERROR_EAGAIN)
+            {
+                crate::app_log!(warn, "JumpTunnel", "隧道 {} 保活失败: {}", label, e);
+                break;
+            }
+
+            // 跳板机通道 -> 本地socket
+            match channel.read(&mut buffer) {
+                Ok(0) if channel.eof() => break,
+                Ok(0) => {}
+                Ok(n) => {
+                    to_bridge.extend_from_slice(&buffer[..n]);
+                    idle = false;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    crate::app_log!(warn, "JumpTunnel", "隧道 {} 读取失败: {}", label, e);
+                    break;
+                }
+            }
+            while !to_bridge.is_empty() {
+                match bridge.write(&to_bridge) {
+                    Ok(n) => {
+                        to_bridge.drain(..n);
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(_) => break 'pump,
+                }
+            }
+
+            // 本地socket -> 跳板机通道
+            if to_channel.is_empty() {
+                match bridge.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        to_channel.extend_from_slice(&buffer[..n]);
+                        idle = false;
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                    Err(_) => break,
+                }
+            }
+            while !to_channel.is_empty() {
+                match channel.write(&to_channel) {
+                    Ok(n) => {
+                        to_channel.drain(..n);
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        crate::app_log!(warn, "JumpTunnel", "隧道 {} 写入失败: {}", label, e);
+                        break 'pump;
+                    }
+                }
+            }
+
+            if idle {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        hop.session().set_blocking(true);
+        let _ = channel.close();
+        let _ = hop.session().disconnect(None, "Jump tunnel closed", None);
+        crate::app_log!(info, "JumpTunnel", "🔗 隧道已关闭: {}", label);
+    }
+}
+
+impl Drop for JumpTunnel {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        crate::app_log!(debug, "JumpTunnel", "隧道资源已释放: {}", self.label);
+    }
+}
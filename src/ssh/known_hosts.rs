@@ -0,0 +1,298 @@
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use sha2::{Digest, Sha256};
+use ssh2::{CheckResult, HashType, HostKeyType, KnownHostFileKind, KnownHosts, Session};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 🔐 主机密钥信息 - 用于首次连接确认和密钥变更提示
+#[derive(Debug, Clone)]
+pub struct HostKeyInfo {
+    pub host: String,
+    pub port: u16,
+    pub key_type: HostKeyType,
+    pub fingerprint: String,
+    pub key: Vec<u8>,
+}
+
+impl HostKeyInfo {
+    /// 密钥类型名称（与OpenSSH保持一致）
+    pub fn key_type_name(&self) -> &'static str {
+        key_type_name(self.key_type)
+    }
+}
+
+/// 🔐 主机密钥校验失败的原因 - UI根据类型决定弹窗确认或直接拒绝
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum HostKeyError {
+    /// 首次连接：known_hosts中没有该主机的记录
+    #[error("首次连接 {}:{}，请确认主机指纹: {} {}", .0.host, .0.port, .0.key_type_name(), .0.fingerprint)]
+    Unknown(HostKeyInfo),
+    /// 主机密钥与已保存的记录不一致，可能存在中间人攻击；sources为记录不一致的文件
+    #[error("{}", changed_message(info, sources))]
+    Changed {
+        info: HostKeyInfo,
+        sources: Vec<&'static str>,
+    },
+}
+
+/// 密钥变更的提示：按旧记录所在的文件说明如何删除（重置按钮只能删除应用保存的记录）
+fn changed_message(info: &HostKeyInfo, sources: &[&str]) -> String {
+    let mut message = format!(
+        "⚠️ 主机 {}:{} 的密钥已改变，已拒绝连接！\n可能存在中间人攻击，也可能是服务器重装。\n当前指纹: {} {}",
+        info.host, info.port, info.key_type_name(), info.fingerprint
+    );
+    if sources.contains(&SOURCE_USER) {
+        message.push_str(&format!(
+            "\n旧记录在 {} 中，确认无误后请执行: ssh-keygen -R \"{}\"",
+            SOURCE_USER,
+            host_entry_name(&info.host, info.port)
+        ));
+    }
+    if sources.contains(&SOURCE_APP) {
+        message.push_str("\n旧记录由应用保存，确认无误后请在连接列表中重置该主机的指纹");
+    }
+    message
+}
+
+/// 已保存的主机指纹记录
+#[derive(Debug, Clone)]
+pub struct StoredHostKey {
+    /// 记录来源（用户known_hosts或应用known_hosts）
+    pub source: &'static str,
+    pub fingerprint: String,
+}
+
+pub const SOURCE_USER: &str = "~/.ssh/known_hosts";
+pub const SOURCE_APP: &str = "应用";
+
+/// 指纹记录的版本号，应用信任或重置指纹后加一，UI据此刷新指纹缓存
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 应用自有的known_hosts文件路径（与配置文件放在同一目录）
+pub fn app_known_hosts_path() -> Result<PathBuf> {
+    let config_dir =
+        dirs::config_dir().ok_or_else(|| anyhow!("Could not find config directory"))?;
+
+    Ok(config_dir.join("ay-dev-tool").join("known_hosts"))
+}
+
+/// 用户的OpenSSH known_hosts文件路径
+pub fn user_known_hosts_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts"))
+}
+
+/// known_hosts中的主机名格式：非22端口使用 `[host]:port`
+fn host_entry_name(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+fn key_type_name(key_type: HostKeyType) -> &'static str {
+    match key_type {
+        HostKeyType::Rsa => "ssh-rsa",
+        HostKeyType::Dss => "ssh-dss",
+        HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+        HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+        HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+        HostKeyType::Ed25519 => "ssh-ed25519",
+        HostKeyType::Unknown => "unknown",
+    }
+}
+
+/// 计算原始公钥的SHA256指纹（OpenSSH格式）
+fn fingerprint_of(key: &[u8]) -> String {
+    format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(key)))
+}
+
+/// 读取一个known_hosts文件（文件不存在时返回空集合）
+fn load_file(known_hosts: &mut KnownHosts, path: &Path) -> Result<()> {
+    if path.exists() {
+        known_hosts
+            .read_file(path, KnownHostFileKind::OpenSSH)
+            .map_err(|e| anyhow!("读取known_hosts失败 {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// 逐行读取known_hosts文件，跳过libssh2无法解析的行，返回跳过的行号（文件不存在时返回空）
+///
+/// libssh2整文件读取时遇到第一个无法解析的行（如它不支持的密钥类型）就会停止，其后的记录都会丢失
+fn load_lines(known_hosts: &mut KnownHosts, path: &Path) -> Result<Vec<usize>> {
+    let mut skipped = Vec::new();
+    for (number, line) in read_lines(path)? {
+        if known_hosts.read_str(&line, KnownHostFileKind::OpenSSH).is_err() {
+            skipped.push(number);
+        }
+    }
+    Ok(skipped)
+}
+
+/// known_hosts文件中的记录行（行号从1开始），跳过空行和注释
+fn read_lines(path: &Path) -> Result<Vec<(usize, String)>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(anyhow!("读取known_hosts失败 {}: {}", path.display(), e)),
+    };
+    Ok(content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| (number, line.to_string()))
+        .collect())
+}
+
+/// 🔐 校验服务器主机密钥 - 必须在handshake之后、认证之前调用
+pub fn verify(session: &Session, host: &str, port: u16) -> Result<()> {
+    let (key, key_type) = session
+        .host_key()
+        .ok_or_else(|| anyhow!("无法获取服务器主机密钥"))?;
+
+    let fingerprint = session
+        .host_key_hash(HashType::Sha256)
+        .map(|hash| format!("SHA256:{}", STANDARD_NO_PAD.encode(hash)))
+        .unwrap_or_else(|| fingerprint_of(key));
+
+    let mut known_hosts = session.known_hosts()?;
+    if let Some(user_path) = user_known_hosts_path() {
+        // 用户文件可能包含libssh2不支持的条目，只跳过这些行；文件无法读取时不能确认主机身份，直接拒绝
+        let skipped = load_lines(&mut known_hosts, &user_path)
+            .map_err(|e| anyhow!("{}，无法校验主机密钥", e))?;
+        if !skipped.is_empty() {
+            crate::app_log!(warn, "KnownHosts", "跳过 {} 中无法解析的行: {:?}",
+                user_path.display(), skipped);
+        }
+    }
+    load_file(&mut known_hosts, &app_known_hosts_path()?)?;
+
+    let info = HostKeyInfo {
+        host: host.to_string(),
+        port,
+        key_type,
+        fingerprint,
+        key: key.to_vec(),
+    };
+
+    match known_hosts.check_port(host, port, key) {
+        CheckResult::Match => {
+            crate::app_log!(info, "KnownHosts", "🔐 主机密钥校验通过: {}:{} {}",
+                host, port, info.fingerprint);
+            Ok(())
+        }
+        CheckResult::NotFound => {
+            crate::app_log!(warn, "KnownHosts", "🔐 未知主机: {}:{} {}",
+                host, port, info.fingerprint);
+            Err(HostKeyError::Unknown(info).into())
+        }
+        CheckResult::Mismatch => {
+            crate::app_log!(error, "KnownHosts", "🚨 主机密钥不匹配: {}:{} {}",
+                host, port, info.fingerprint);
+            // 当前密钥之外的记录即为冲突的记录
+            let mut sources: Vec<&'static str> = stored_fingerprints(host, port)
+                .into_iter()
+                .filter(|stored| stored.fingerprint != info.fingerprint)
+                .map(|stored| stored.source)
+                .collect();
+            sources.dedup();
+            Err(HostKeyError::Changed { info, sources }.into())
+        }
+        CheckResult::Failure => Err(anyhow!("主机密钥校验失败: {}:{}", host, port)),
+    }
+}
+
+/// 🔐 信任主机密钥 - 写入应用自有的known_hosts文件
+pub fn trust(info: &HostKeyInfo) -> Result<()> {
+    let path = app_known_hosts_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let session = Session::new()?;
+    let mut known_hosts = session.known_hosts()?;
+    load_file(&mut known_hosts, &path)?;
+
+    let name = host_entry_name(&info.host, info.port);
+    known_hosts.add(&name, &info.key, &name, info.key_type.into())?;
+    known_hosts.write_file(&path, KnownHostFileKind::OpenSSH)?;
+
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    crate::app_log!(info, "KnownHosts", "🔐 已保存主机指纹: {} {}", name, info.fingerprint);
+    Ok(())
+}
+
+/// 查询某个主机已保存的所有指纹（用户known_hosts + 应用known_hosts）
+///
+/// 每行单独交给libssh2匹配，哈希（`|1|`）和通配符形式的主机名也能找到
+pub fn stored_fingerprints(host: &str, port: u16) -> Vec<StoredHostKey> {
+    let mut result = Vec::new();
+    let Ok(session) = Session::new() else {
+        return result;
+    };
+
+    let sources = [
+        (SOURCE_USER, user_known_hosts_path()),
+        (SOURCE_APP, app_known_hosts_path().ok()),
+    ];
+
+    for (source, path) in sources {
+        let Some(path) = path else { continue };
+        let Ok(lines) = read_lines(&path) else { continue };
+
+        for (_, line) in lines {
+            let Ok(mut known_hosts) = session.known_hosts() else { continue };
+            if known_hosts.read_str(&line, KnownHostFileKind::OpenSSH).is_err() {
+                continue;
+            }
+            for entry in known_hosts.hosts().unwrap_or_default() {
+                let Ok(key) = STANDARD.decode(entry.key()) else { continue };
+                if !matches!(known_hosts.check_port(host, port, &key), CheckResult::Match) {
+                    continue;
+                }
+                // 非22端口时libssh2也接受不带端口的记录，同一密钥可能出现多次
+                let fingerprint = fingerprint_of(&key);
+                if !result.iter().any(|k: &StoredHostKey| k.source == source && k.fingerprint == fingerprint) {
+                    result.push(StoredHostKey { source, fingerprint });
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// 🔐 重置主机指纹 - 只删除应用known_hosts中的记录，不修改用户的~/.ssh/known_hosts
+pub fn reset(host: &str, port: u16) -> Result<usize> {
+    let path = app_known_hosts_path()?;
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let session = Session::new()?;
+    let mut known_hosts = session.known_hosts()?;
+    load_file(&mut known_hosts, &path)?;
+
+    let name = host_entry_name(host, port);
+    let mut removed = 0;
+    for entry in known_hosts.hosts()? {
+        if entry.name() == Some(name.as_str()) {
+            known_hosts.remove(&entry)?;
+            removed += 1;
+        }
+    }
+
+    known_hosts.write_file(&path, KnownHostFileKind::OpenSSH)?;
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    crate::app_log!(info, "KnownHosts", "🔐 已重置主机指纹: {} ({} 条)", name, removed);
+    Ok(removed)
+}
+
+/// 指纹记录的版本号 - 用于UI判断指纹缓存是否过期
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Relaxed)
+}
@@ -1,5 +1,12 @@
 // SSH模块 - 基于ssh2库的原生SSH实现
 
 // 导出SSH2客户端实现
+pub mod agent_forward;
+pub mod auth_prompt;
+pub mod jump_tunnel;
+pub mod known_hosts;
+pub mod port_forward;
+pub mod sftp;
 pub mod ssh2_client;
+pub mod transfer;
 pub use ssh2_client::Ssh2Manager;
\ No newline at end of file
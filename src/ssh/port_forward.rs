@@ -0,0 +1,628 @@
+use anyhow::{Result, anyhow};
+use libssh2_sys as raw;
+use ssh2::{Channel, ErrorCode, Listener, Session};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::ssh2_client::with_blocking;
+use crate::ui::{ForwardKind, ForwardRule};
+
+/// 每次pump每条连接最多搬运的数据量，避免单条连接饿死Shell输出
+const PUMP_BUDGET: usize = 256 * 1024;
+
+/// 🔀 隧道状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum TunnelState {
+    Listening,
+    Failed(String),
+}
+
+/// 🔀 隧道运行状态 - UI读取快照显示
+#[derive(Debug, Clone)]
+pub struct TunnelStatus {
+    pub rule: ForwardRule,
+    pub state: TunnelState,
+    pub bound_port: u16, // 实际监听端口（远程转发可能由服务器分配）
+    pub active_connections: usize,
+    pub total_connections: u64,
+    pub bytes_sent: u64,     // 本地 -> 远程
+    pub bytes_received: u64, // 远程 -> 本地
+    pub last_error: Option<String>,
+}
+
+/// Actor线程写入、UI线程读取的隧道状态表
+pub type SharedTunnelStatus = Arc<Mutex<Vec<TunnelStatus>>>;
+
+enum TunnelListener {
+    Local(TcpListener),
+    Remote(Listener),
+    Dynamic(TcpListener),
+}
+
+struct Tunnel {
+    rule: ForwardRule,
+    listener: TunnelListener,
+}
+
+/// SOCKS5握手阶段
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SocksPhase {
+    Greeting,
+    Request,
+    Connecting, // 已收到CONNECT请求，等待通道打开后回复
+}
+
+/// 待打开的direct-tcpip通道
+#[derive(Debug, Clone)]
+struct ChannelRequest {
+    host: String,
+    port: u16,
+    source: Option<(String, u16)>,
+}
+
+/// 一条被转发的TCP连接：本地socket <-> SSH通道
+struct ForwardedConnection {
+    id: u64,
+    rule_id: String,
+    socket: TcpStream,
+    channel: Option<Channel>,       // 通道打开前（含SOCKS5握手期间）为None
+    open_request: Option<ChannelRequest>, // 等待打开的通道
+    socks: Option<SocksPhase>,      // 仅动态转发使用
+    handshake: Vec<u8>,
+    to_channel: Vec<u8>,
+    to_socket: Vec<u8>,
+    socket_eof: bool,               // 本地已关闭写端，不再读取socket
+    eof_sent: bool,                 // 已向远端发送EOF
+    closing: bool,                  // 握手失败，发送完回复后关闭
+    error: Option<String>,          // 关闭原因，关闭时记录到状态表
+}
+
+/// 远程转发中等待连接本地目标的通道：连接在辅助线程中建立，DNS解析和连接超时不阻塞Actor
+struct PendingConnect {
+    rule: ForwardRule,
+    channel: Channel,
+    result: Receiver<Result<TcpStream>>,
+}
+
+/// 🔀 端口转发器 - 在SSH会话上运行本地/远程/SOCKS5转发
+///
+/// 与agent转发一样，所有通道操作都在持有会话的Actor线程中通过 `pump` 完成。
+/// 通道以非阻塞方式打开，目标很慢或不可达时不会卡住Shell和其他隧道。
+pub struct PortForwarder {
+    session: Session,
+    tunnels: Vec<Tunnel>,
+    connections: Vec<ForwardedConnection>,
+    pending: Vec<PendingConnect>,
+    // libssh2同一时间只能有一个正在打开的direct-tcpip通道，EAGAIN后必须用相同参数重试；
+    // 发起请求的连接关闭后仍要把它完成，得到的通道直接丢弃
+    opening: Option<(u64, ChannelRequest)>,
+    next_connection_id: u64,
+    status: SharedTunnelStatus,
+}
+
+impl PortForwarder {
+    pub fn new(session: Session, status: SharedTunnelStatus) -> Self {
+        Self {
+            session,
+            tunnels: Vec::new(),
+            connections: Vec::new(),
+            pending: Vec::new(),
+            opening: None,
+            next_connection_id: 1,
+            status,
+        }
+    }
+
+    /// 是否有正在转发或正在建立的连接（Actor据此缩短轮询间隔）
+    pub fn is_busy(&self) -> bool {
+        !self.connections.is_empty() || !self.pending.is_empty()
+    }
+
+    /// 启动一条转发规则，失败时记录在状态表中
+    pub fn add(&mut self, rule: ForwardRule) {
+        self.remove(&rule.id);
+
+        let result = self.listen(&rule);
+        let (state, bound_port) = match &result {
+            Ok((_, port)) => {
+                crate::app_log!(info, "PortForward", "🔀 转发已启动: {} (端口 {})", rule.summary(), port);
+                (TunnelState::Listening, *port)
+            }
+            Err(e) => {
+                crate::app_log!(error, "PortForward", "🔀 转发启动失败 {}: {}", rule.summary(), e);
+                (TunnelState::Failed(e.to_string()), rule.bind_port)
+            }
+        };
+
+        if let Ok(mut status) = self.status.lock() {
+            status.push(TunnelStatus {
+                rule: rule.clone(),
+                state,
+                bound_port,
+                active_connections: 0,
+                total_connections: 0,
+                bytes_sent: 0,
+                bytes_received: 0,
+                last_error: None,
+            });
+        }
+
+        if let Ok((listener, _)) = result {
+            self.tunnels.push(Tunnel { rule, listener });
+        }
+    }
+
+    /// 停止一条转发规则并关闭它的所有连接
+    pub fn remove(&mut self, rule_id: &str) {
+        self.connections.retain(|c| c.rule_id != rule_id);
+        self.pending.retain(|p| p.rule.id != rule_id);
+
+        if let Some(pos) = self.tunnels.iter().position(|t| t.rule.id == rule_id) {
+            let tunnel = self.tunnels.remove(pos);
+            crate::app_log!(info, "PortForward", "🔀 转发已停止: {}", tunnel.rule.summary());
+            // 取消远程监听需要等待服务器确认
+            with_blocking(&self.session, || drop(tunnel));
+        }
+
+        if let Ok(mut status) = self.status.lock() {
+            status.retain(|s| s.rule.id != rule_id);
+        }
+    }
+
+    fn listen(&self, rule: &ForwardRule) -> Result<(TunnelListener, u16)> {
+        match rule.kind {
+            ForwardKind::Local | ForwardKind::Dynamic => {
+                let listener = TcpListener::bind((rule.bind_address.as_str(), rule.bind_port))
+                    .map_err(|e| anyhow!("无法监听 {}:{}: {}", rule.bind_address, rule.bind_port, e))?;
+                listener.set_nonblocking(true)?;
+                let port = listener.local_addr()?.port();
+                let listener = if rule.kind == ForwardKind::Local {
+                    TunnelListener::Local(listener)
+                } else {
+                    TunnelListener::Dynamic(listener)
+                };
+                Ok((listener, port))
+            }
+            ForwardKind::Remote => {
+                let (listener, port) = with_blocking(&self.session, || {
+                    self.session
+                        .channel_forward_listen(rule.bind_port, Some(&rule.bind_address), None)
+                })
+                .map_err(|e| anyhow!("服务器拒绝监听 {}:{}: {}", rule.bind_address, rule.bind_port, e))?;
+                Ok((TunnelListener::Remote(listener), port))
+            }
+        }
+    }
+
+    /// 在Actor线程中调用：接受新连接、推进SOCKS5握手、打开通道、搬运数据
+    pub fn pump(&mut self) {
+        self.accept_connections();
+
+        let mut counters: Vec<(String, u64, u64)> = Vec::new();
+        let mut closed: Vec<(String, Option<String>)> = Vec::new();
+
+        self.connections.retain_mut(|connection| {
+            let mut sent = 0u64;
+            let mut received = 0u64;
+            let result = pump_connection(connection, &mut sent, &mut received);
+            if sent > 0 || received > 0 {
+                counters.push((connection.rule_id.clone(), sent, received));
+            }
+            match result {
+                Ok(true) => true,
+                Ok(false) => {
+                    closed.push((connection.rule_id.clone(), None));
+                    false
+                }
+                Err(e) => {
+                    closed.push((connection.rule_id.clone(), Some(e.to_string())));
+                    false
+                }
+            }
+        });
+
+        self.open_channels();
+
+        if counters.is_empty() && closed.is_empty() {
+            return;
+        }
+
+        if let Ok(mut status) = self.status.lock() {
+            for (rule_id, sent, received) in counters {
+                if let Some(entry) = status.iter_mut().find(|s| s.rule.id == rule_id) {
+                    entry.bytes_sent += sent;
+                    entry.bytes_received += received;
+                }
+            }
+            for (rule_id, error) in closed {
+                if let Some(entry) = status.iter_mut().find(|s| s.rule.id == rule_id) {
+                    entry.active_connections = entry.active_connections.saturating_sub(1);
+                    if let Some(error) = error {
+                        crate::app_log!(warn, "PortForward", "🔀 {} 连接异常: {}", entry.rule.summary(), error);
+                        entry.last_error = Some(error);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 按连接顺序逐个打开等待中的通道，遇到EAGAIN时留到下次pump重试
+    fn open_channels(&mut self) {
+        loop {
+            if self.opening.is_none() {
+                let next = self
+                    .connections
+                    .iter_mut()
+                    .find_map(|c| c.open_request.take().map(|request| (c.id, request)));
+                if next.is_none() {
+                    return;
+                }
+                self.opening = next;
+            }
+            let Some((id, request)) = &self.opening else {
+                return;
+            };
+
+            let source = request.source.as_ref().map(|(host, port)| (host.as_str(), *port));
+            let result = self.session.channel_direct_tcpip(&request.host, request.port, source);
+            if matches!(&result, Err(e) if e.code() == ErrorCode::Session(raw::LIBSSH2_ERROR_EAGAIN)) {
+                return;
+            }
+            let target = format!("{}:{}", request.host, request.port);
+            let id = *id;
+            self.opening = None;
+
+            // 连接已关闭时丢弃打开的通道
+            let Some(connection) = self.connections.iter_mut().find(|c| c.id == id) else {
+                continue;
+            };
+            let socks = connection.socks.is_some();
+            match result {
+                Ok(channel) => {
+                    if socks {
+                        crate::app_log!(debug, "PortForward", "🔀 SOCKS5 CONNECT {}", target);
+                        connection.to_socket.extend_from_slice(&socks_reply(0));
+                        connection.socks = None;
+                        // 握手后紧跟的数据直接转发
+                        connection.to_channel.append(&mut connection.handshake);
+                    }
+                    connection.channel = Some(channel);
+                }
+                Err(e) if socks => {
+                    connection.reject(&socks_reply(5), format!("SOCKS5 无法连接 {}: {}", target, e));
+                }
+                Err(e) => {
+                    connection.closing = true;
+                    connection.error = Some(format!("无法打开到 {} 的通道: {}", target, e));
+                }
+            }
+        }
+    }
+
+    fn accept_connections(&mut self) {
+        let mut accepted = Vec::new();
+        let mut errors = Vec::new();
+        let mut connecting = Vec::new();
+
+        for tunnel in &mut self.tunnels {
+            let rule = &tunnel.rule;
+            match &mut tunnel.listener {
+                TunnelListener::Local(listener) => {
+                    while let Ok((socket, peer)) = listener.accept() {
+                        let mut connection = ForwardedConnection::new(rule, socket, None);
+                        connection.open_request = Some(ChannelRequest {
+                            host: rule.target_host.clone(),
+                            port: rule.target_port,
+                            source: Some((peer.ip().to_string(), peer.port())),
+                        });
+                        accepted.push(connection);
+                    }
+                }
+                TunnelListener::Dynamic(listener) => {
+                    while let Ok((socket, _)) = listener.accept() {
+                        accepted.push(ForwardedConnection::new(rule, socket, None));
+                    }
+                }
+                TunnelListener::Remote(listener) => loop {
+                    match listener.accept() {
+                        Ok(channel) => connecting.push(PendingConnect::spawn(rule, channel)),
+                        Err(e) if e.code() == ErrorCode::Session(raw::LIBSSH2_ERROR_EAGAIN) => break,
+                        Err(e) => {
+                            errors.push((rule.id.clone(), format!("接受远程连接失败: {}", e)));
+                            break;
+                        }
+                    }
+                },
+            }
+        }
+
+        // 远程转发：取回已连上（或连接失败）的本地目标
+        for pending in std::mem::take(&mut self.pending).into_iter().chain(connecting) {
+            match pending.result.try_recv() {
+                Ok(Ok(socket)) => {
+                    accepted.push(ForwardedConnection::new(&pending.rule, socket, Some(pending.channel)))
+                }
+                Ok(Err(e)) => errors.push((pending.rule.id, e.to_string())),
+                Err(TryRecvError::Empty) => self.pending.push(pending),
+                Err(TryRecvError::Disconnected) => {
+                    errors.push((pending.rule.id, "连接目标的线程异常退出".to_string()))
+                }
+            }
+        }
+
+        if accepted.is_empty() && errors.is_empty() {
+            return;
+        }
+
+        if let Ok(mut status) = self.status.lock() {
+            for connection in &accepted {
+                if let Some(entry) = status.iter_mut().find(|s| s.rule.id == connection.rule_id) {
+                    entry.active_connections += 1;
+                    entry.total_connections += 1;
+                }
+            }
+            for (rule_id, error) in errors {
+                if let Some(entry) = status.iter_mut().find(|s| s.rule.id == rule_id) {
+                    crate::app_log!(warn, "PortForward", "🔀 {}: {}", entry.rule.summary(), error);
+                    entry.last_error = Some(error);
+                }
+            }
+        }
+
+        for mut connection in accepted {
+            connection.id = self.next_connection_id;
+            self.next_connection_id += 1;
+            self.connections.push(connection);
+        }
+    }
+}
+
+impl Drop for PortForwarder {
+    fn drop(&mut self) {
+        if let Ok(mut status) = self.status.lock() {
+            status.clear();
+        }
+    }
+}
+
+impl ForwardedConnection {
+    fn new(rule: &ForwardRule, socket: TcpStream, channel: Option<Channel>) -> Self {
+        let _ = socket.set_nonblocking(true);
+        let _ = socket.set_nodelay(true);
+        Self {
+            id: 0, // 加入连接表时分配
+            rule_id: rule.id.clone(),
+            socket,
+            socks: (rule.kind == ForwardKind::Dynamic).then_some(SocksPhase::Greeting),
+            channel,
+            open_request: None,
+            handshake: Vec::new(),
+            to_channel: Vec::new(),
+            to_socket: Vec::new(),
+            socket_eof: false,
+            eof_sent: false,
+            closing: false,
+            error: None,
+        }
+    }
+
+    /// 回复SOCKS5错误后关闭连接
+    fn reject(&mut self, reply: &[u8], error: String) {
+        self.to_socket.extend_from_slice(reply);
+        self.closing = true;
+        self.socks = None;
+        self.error = Some(error);
+    }
+}
+
+impl PendingConnect {
+    fn spawn(rule: &ForwardRule, channel: Channel) -> Self {
+        let (sender, result) = mpsc::channel();
+        let (host, port) = (rule.target_host.clone(), rule.target_port);
+        thread::spawn(move || {
+            // 转发规则已停止时接收端已释放，连接随之关闭
+            let _ = sender.send(connect_target(&host, port));
+        });
+        Self {
+            rule: rule.clone(),
+            channel,
+            result,
+        }
+    }
+}
+
+/// 远程转发：连接本地可达的目标（在辅助线程中调用）
+fn connect_target(host: &str, port: u16) -> Result<TcpStream> {
+    let addr = (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("无法解析目标地址 {}:{}", host, port))?;
+    TcpStream::connect_timeout(&addr, Duration::from_secs(5))
+        .map_err(|e| anyhow!("无法连接目标 {}:{}: {}", host, port, e))
+}
+
+/// 搬运一条连接的数据，返回连接是否仍然存活
+fn pump_connection(
+    connection: &mut ForwardedConnection,
+    sent: &mut u64,
+    received: &mut u64,
+) -> Result<bool> {
+    let mut buffer = [0u8; 16384];
+
+    // 本地socket -> 通道（握手阶段交给SOCKS5解析），本地关闭写端后不再读取
+    let mut budget = PUMP_BUDGET;
+    while !connection.socket_eof && budget > 0 && connection.to_channel.len() < PUMP_BUDGET {
+        match connection.socket.read(&mut buffer) {
+            Ok(0) => {
+                connection.socket_eof = true;
+                break;
+            }
+            Ok(n) => {
+                budget = budget.saturating_sub(n);
+                if connection.socks.is_some() {
+                    connection.handshake.extend_from_slice(&buffer[..n]);
+                } else {
+                    connection.to_channel.extend_from_slice(&buffer[..n]);
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if connection.socks.is_some() {
+        socks_handshake(connection)?;
+    }
+
+    if let Some(channel) = &mut connection.channel {
+        while !connection.to_channel.is_empty() {
+            match channel.write(&connection.to_channel) {
+                Ok(n) => {
+                    connection.to_channel.drain(..n);
+                    *sent += n as u64;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        // 本地半关闭：数据发完后向远端发送EOF，继续接收远端的响应，直到远端也发来EOF
+        if connection.socket_eof && connection.to_channel.is_empty() && !connection.eof_sent {
+            match channel.send_eof() {
+                Ok(()) => connection.eof_sent = true,
+                Err(e) if e.code() == ErrorCode::Session(raw::LIBSSH2_ERROR_EAGAIN) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        // 通道 -> 本地socket
+        let mut budget = PUMP_BUDGET;
+        while budget > 0 && connection.to_socket.len() < PUMP_BUDGET {
+            match channel.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => {
+                    budget = budget.saturating_sub(n);
+                    connection.to_socket.extend_from_slice(&buffer[..n]);
+                    *received += n as u64;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    while !connection.to_socket.is_empty() {
+        match connection.socket.write(&connection.to_socket) {
+            Ok(n) => {
+                connection.to_socket.drain(..n);
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            // 本地完全关闭（而不是半关闭）时不再需要远端的响应
+            Err(e) if connection.socket_eof && matches!(e.kind(), ErrorKind::BrokenPipe | ErrorKind::ConnectionReset) => {
+                return Ok(false);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if connection.closing {
+        if !connection.to_socket.is_empty() {
+            return Ok(true);
+        }
+        return match connection.error.take() {
+            Some(error) => Err(anyhow!(error)),
+            None => Ok(false),
+        };
+    }
+    if connection.socket_eof && connection.channel.is_none() && connection.open_request.is_none() {
+        // SOCKS5握手完成前本地就关闭了
+        return Ok(false);
+    }
+    let remote_closed = connection.channel.as_ref().is_some_and(|c| c.eof());
+    Ok(!(remote_closed && connection.to_socket.is_empty()))
+}
+
+/// 推进SOCKS5握手（RFC 1928，仅支持无认证的CONNECT），数据不完整时等下次pump读到更多数据
+fn socks_handshake(connection: &mut ForwardedConnection) -> Result<()> {
+    if connection.socks == Some(SocksPhase::Greeting) {
+        let buf = &connection.handshake;
+        if buf.len() < 2 {
+            return Ok(());
+        }
+        if buf[0] != 5 {
+            return Err(anyhow!("不支持的SOCKS版本: {}", buf[0]));
+        }
+        let methods_len = buf[1] as usize;
+        if buf.len() < 2 + methods_len {
+            return Ok(());
+        }
+        if !buf[2..2 + methods_len].contains(&0) {
+            connection.reject(&[5, 0xFF], "SOCKS客户端要求认证，当前仅支持无认证模式".to_string());
+            return Ok(());
+        }
+        connection.to_socket.extend_from_slice(&[5, 0]);
+        connection.handshake.drain(..2 + methods_len);
+        connection.socks = Some(SocksPhase::Request);
+    }
+
+    if connection.socks == Some(SocksPhase::Request) {
+        let Some(request) = parse_socks_request(&connection.handshake) else {
+            return Ok(());
+        };
+        let (host, port, consumed) = match request {
+            Ok(request) => request,
+            Err(reply) => {
+                connection.reject(&socks_reply(reply), format!("不支持的SOCKS5请求 (错误码 {})", reply));
+                return Ok(());
+            }
+        };
+
+        // 通道由 `open_channels` 打开，之后收到的数据留在handshake中，打开后再转发
+        connection.handshake.drain(..consumed);
+        connection.open_request = Some(ChannelRequest { host, port, source: None });
+        connection.socks = Some(SocksPhase::Connecting);
+    }
+
+    Ok(())
+}
+
+/// 解析SOCKS5请求：None表示数据不完整，Err为应回复的错误码
+fn parse_socks_request(buf: &[u8]) -> Option<Result<(String, u16, usize), u8>> {
+    if buf.len() < 4 {
+        return None;
+    }
+    if buf[0] != 5 {
+        return Some(Err(1));
+    }
+    if buf[1] != 1 {
+        return Some(Err(7)); // 仅支持CONNECT
+    }
+
+    let (host, addr_end) = match buf[3] {
+        1 => {
+            let addr = buf.get(4..8)?;
+            (std::net::Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]).to_string(), 8)
+        }
+        3 => {
+            let len = *buf.get(4)? as usize;
+            let name = buf.get(5..5 + len)?;
+            (String::from_utf8_lossy(name).to_string(), 5 + len)
+        }
+        4 => {
+            let addr: [u8; 16] = buf.get(4..20)?.try_into().ok()?;
+            (std::net::Ipv6Addr::from(addr).to_string(), 20)
+        }
+        _ => return Some(Err(8)),
+    };
+
+    let port = buf.get(addr_end..addr_end + 2)?;
+    Some(Ok((host, u16::from_be_bytes([port[0], port[1]]), addr_end + 2)))
+}
+
+fn socks_reply(code: u8) -> [u8; 10] {
+    [5, code, 0, 1, 0, 0, 0, 0, 0, 0]
+}
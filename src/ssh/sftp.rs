@@ -0,0 +1,218 @@
+use anyhow::{Result, anyhow};
+use ssh2::{Session, Sftp};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+
+use super::ssh2_client::with_blocking;
+use super::transfer::{SharedTransferStatus, TransferCommand, TransferQueue};
+
+/// 📁 远程目录项
+#[derive(Debug, Clone)]
+pub struct RemoteEntry {
+    pub name: String,
+    pub is_dir: bool,     // 指向目录的符号链接也视为目录
+    pub is_symlink: bool,
+    pub size: u64,
+    pub permissions: Option<u32>,
+    pub owner: String,
+    pub group: String,
+    pub modified: Option<u64>, // Unix时间戳（秒）
+}
+
+/// 📁 远程目录列表
+#[derive(Debug, Clone)]
+pub struct RemoteListing {
+    pub path: PathBuf, // 服务器解析后的绝对路径
+    pub entries: Vec<RemoteEntry>,
+}
+
+/// 📁 SFTP请求 - 发往持有会话的Actor线程执行
+#[derive(Debug, Clone)]
+pub enum SftpRequest {
+    /// 列出目录（空路径表示登录用户的主目录）
+    ListDir {
+        path: String,
+        reply: Sender<Result<RemoteListing>>,
+    },
+    /// 上传/下载队列操作
+    Transfer(TransferCommand),
+}
+
+/// 📁 SFTP客户端 - 复用Tab已有的SSH会话，子系统按需打开
+pub struct SftpClient {
+    session: Session,
+    sftp: Option<Sftp>,
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+    names_loaded: bool,
+    transfers: TransferQueue,
+}
+
+impl SftpClient {
+    pub fn new(session: Session, transfer_status: SharedTransferStatus) -> Self {
+        Self {
+            session,
+            sftp: None,
+            users: HashMap::new(),
+            groups: HashMap::new(),
+            names_loaded: false,
+            transfers: TransferQueue::new(transfer_status),
+        }
+    }
+
+    /// 在Actor线程中执行请求（临时切换为阻塞模式）
+    pub fn handle(&mut self, request: SftpRequest) {
+        let session = self.session.clone();
+        match request {
+            SftpRequest::ListDir { path, reply } => {
+                let result = with_blocking(&session, || self.list_dir(&path));
+                if let Err(e) = &result {
+                    crate::app_log!(warn, "SFTP", "📁 列出目录失败 {}: {}", path, e);
+                    // 子系统可能已失效，下次请求时重新打开
+                    self.sftp = None;
+                }
+                let _ = reply.send(result);
+            }
+            SftpRequest::Transfer(command) => {
+                with_blocking(&session, || {
+                    if let Err(e) = self.open_sftp() {
+                        crate::app_log!(error, "SFTP", "📦 {}", e);
+                        return;
+                    }
+                    if let Some(sftp) = &self.sftp {
+                        self.transfers.handle(sftp, command);
+                    }
+                });
+            }
+        }
+    }
+
+    /// 📦 推进传输队列一个时间片，返回是否还有待传输的文件
+    pub fn pump_transfers(&mut self) -> bool {
+        if !self.transfers.is_busy() {
+            return false;
+        }
+        let session = self.session.clone();
+        with_blocking(&session, || {
+            if let Err(e) = self.open_sftp() {
+                self.transfers.fail_pending(&e.to_string());
+                return;
+            }
+            if let Some(sftp) = &self.sftp {
+                self.transfers.pump(sftp);
+            }
+        });
+        self.transfers.is_busy()
+    }
+
+    /// 按需打开SFTP子系统
+    fn open_sftp(&mut self) -> Result<&Sftp> {
+        if self.sftp.is_none() {
+            let sftp = self
+                .session
+                .sftp()
+                .map_err(|e| anyhow!("无法打开SFTP子系统: {}", e))?;
+            crate::app_log!(info, "SFTP", "📁 SFTP子系统已打开");
+            self.sftp = Some(sftp);
+        }
+        Ok(self.sftp.as_ref().expect("sftp已初始化"))
+    }
+
+    fn list_dir(&mut self, path: &str) -> Result<RemoteListing> {
+        self.load_id_names();
+        self.open_sftp()?;
+
+        let Some(sftp) = &self.sftp else {
+            return Err(anyhow!("SFTP子系统未打开"));
+        };
+        let requested = if path.is_empty() { "." } else { path };
+        let path = sftp
+            .realpath(Path::new(requested))
+            .map_err(|e| anyhow!("无法访问 {}: {}", requested, e))?;
+        let raw_entries = sftp
+            .readdir(&path)
+            .map_err(|e| anyhow!("无法读取目录 {}: {}", path.display(), e))?;
+
+        let mut entries = Vec::with_capacity(raw_entries.len());
+        for (entry_path, stat) in raw_entries {
+            let name = entry_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if name.is_empty() || name == "." || name == ".." {
+                continue;
+            }
+
+            let is_symlink = stat.file_type().is_symlink();
+            // 符号链接需要跟随一次才能知道是否指向目录
+            let is_dir = if is_symlink {
+                sftp.stat(&entry_path).map(|target| target.is_dir()).unwrap_or(false)
+            } else {
+                stat.is_dir()
+            };
+
+            entries.push(RemoteEntry {
+                name,
+                is_dir,
+                is_symlink,
+                size: stat.size.unwrap_or(0),
+                permissions: stat.perm,
+                owner: Self::id_name(&self.users, stat.uid),
+                group: Self::id_name(&self.groups, stat.gid),
+                modified: stat.mtime,
+            });
+        }
+
+        crate::app_log!(debug, "SFTP", "📁 {}: {} 项", path.display(), entries.len());
+        Ok(RemoteListing { path, entries })
+    }
+
+    /// 读取远程 /etc/passwd 和 /etc/group，用于显示用户名/组名（失败时显示数字ID）
+    fn load_id_names(&mut self) {
+        if self.names_loaded {
+            return;
+        }
+        self.names_loaded = true;
+
+        let Ok(sftp) = self.open_sftp() else {
+            return;
+        };
+        let read = |path: &str| -> Option<String> {
+            let mut content = String::new();
+            sftp.open(Path::new(path)).ok()?.read_to_string(&mut content).ok()?;
+            Some(content)
+        };
+        let passwd = read("/etc/passwd");
+        let group = read("/etc/group");
+
+        if let Some(passwd) = passwd {
+            self.users = parse_id_names(&passwd);
+        }
+        if let Some(group) = group {
+            self.groups = parse_id_names(&group);
+        }
+    }
+
+    fn id_name(names: &HashMap<u32, String>, id: Option<u32>) -> String {
+        match id {
+            Some(id) => names.get(&id).cloned().unwrap_or_else(|| id.to_string()),
+            None => String::new(),
+        }
+    }
+}
+
+/// 解析 `/etc/passwd` / `/etc/group` 格式：`name:x:id:...`
+pub fn parse_id_names(content: &str) -> HashMap<u32, String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
@@ -1,4 +1,5 @@
-use ssh2::{Channel, Session};
+use libssh2_sys as raw;
+use ssh2::{Channel, ErrorCode, Session};
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::net::TcpStream;
@@ -7,20 +8,62 @@ use std::time::Duration;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
 use anyhow::{Result, anyhow};
+use eframe::egui;
 
-use crate::ui::{AuthType, ConnectionConfig};
+use super::agent_forward::AgentForwarder;
+use super::auth_prompt::{self, AuthPrompter, InteractiveResponder};
+use super::jump_tunnel::JumpTunnel;
+use super::known_hosts;
+use super::port_forward::{PortForwarder, SharedTunnelStatus, TunnelStatus};
+use super::sftp::{RemoteListing, SftpClient, SftpRequest};
+use super::transfer::{SharedTransferStatus, TransferCommand, TransferItem};
+use crate::ui::{AuthType, ConnectionConfig, ForwardRule};
+
+/// SSH保活间隔（秒），同时用作TCP保活的空闲时间
+const KEEPALIVE_INTERVAL: u32 = 15;
+
+/// 临时切换到阻塞模式执行需要服务器应答的操作（打开通道、监听、SFTP请求）
+pub(crate) fn with_blocking<T>(session: &Session, f: impl FnOnce() -> T) -> T {
+    let was_blocking = session.is_blocking();
+    session.set_blocking(true);
+    let result = f();
+    session.set_blocking(was_blocking);
+    result
+}
+
+/// 🔌 会话结束原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionEnd {
+    /// 远端正常关闭会话（如执行了exit）
+    Closed,
+    /// 连接中断（网络故障、保活失败等），可以自动重连
+    Lost(String),
+}
+
+/// Actor写入、UI读取的会话结束状态
+pub type SharedSessionEnd = Arc<Mutex<Option<SessionEnd>>>;
 
 /// 🎭 Actor模式 - SSH消息类型
 #[derive(Debug, Clone)]
 pub enum SshMessage {
     /// 🔑 发送原始数据到SSH服务器（统一接口）
     SendRaw(String),
+    /// 发送不经过字符编码转换的字节（如X10鼠标报告）
+    SendBytes(Vec<u8>),
     /// 读取SSH输出数据
     ReadOutput,
     /// 断开SSH连接
     Disconnect,
     /// 检查连接状态
     CheckStatus,
+    /// 🔀 在运行中的会话上启动端口转发
+    AddForward(ForwardRule),
+    /// 🔀 停止端口转发（规则id）
+    RemoveForward(String),
+    /// 📁 在当前会话上执行SFTP请求
+    Sftp(SftpRequest),
+    /// 调整PTY尺寸（列，行）
+    Resize(u16, u16),
 }
 
 /// 🎭 Actor模式 - SSH响应类型  
@@ -42,9 +85,11 @@ pub struct SshActor {
     /// 消息接收器 - 接收来自外部的操作请求
     message_receiver: Receiver<SshMessage>,
     /// 输出发送器 - 向UI发送SSH输出数据
-    output_sender: Sender<String>,
+    output_sender: Sender<Vec<u8>>,
     /// 响应发送器 - 发送操作结果
     response_sender: Option<Sender<SshResponse>>,
+    /// 有新输出或会话结束时唤醒UI重绘（UI空闲时不再逐帧轮询）
+    repaint: Option<egui::Context>,
 }
 
 impl SshActor {
@@ -52,13 +97,22 @@ impl SshActor {
     pub fn new(
         connection: Ssh2Connection,
         message_receiver: Receiver<SshMessage>,
-        output_sender: Sender<String>,
+        output_sender: Sender<Vec<u8>>,
+        repaint: Option<egui::Context>,
     ) -> Self {
         Self {
             connection,
             message_receiver,
             output_sender,
             response_sender: None,
+            repaint,
+        }
+    }
+
+    /// 🖌️ 请求UI重绘，以便读取新输出或检测会话状态
+    fn request_repaint(&self) {
+        if let Some(ctx) = &self.repaint {
+            ctx.request_repaint();
         }
     }
     
@@ -68,6 +122,15 @@ impl SshActor {
         
         // 主消息处理循环，同时处理输出读取
         loop {
+            // 🔑 转发远程shell中的agent请求
+            self.connection.pump_agent_forwarding();
+            // 🔀 端口转发数据搬运
+            let forwarding_busy = self.connection.pump_port_forwards();
+            // 📦 文件传输按时间片推进，期间Shell输出照常读取
+            let transfer_busy = self.connection.pump_transfers();
+            // 💓 按间隔发送SSH保活
+            self.connection.send_keepalive();
+
             // 非阻塞读取SSH输出
             if let Ok(output) = self.connection.read_output() {
                 if !output.is_empty() {
@@ -75,22 +138,49 @@ impl SshActor {
                         crate::app_log!(warn, "SshActor", "🎭 输出发送失败，接收器已关闭");
                         break;
                     }
+                    self.request_repaint();
                 }
             }
+
+            // 🔌 会话已结束（远端关闭或连接中断），由UI决定是否重连
+            if let Some(end) = self.connection.session_end() {
+                crate::app_log!(info, "SshActor", "🎭 会话结束: {:?}，退出Actor", end);
+                break;
+            }
             
-            // 非阻塞接收消息，给出Some(超时时间)
-            match self.message_receiver.recv_timeout(Duration::from_millis(10)) {
+            // 非阻塞接收消息，给出Some(超时时间)；有转发流量或传输时缩短等待
+            let wait = if forwarding_busy || transfer_busy { 1 } else { 10 };
+            match self.message_receiver.recv_timeout(Duration::from_millis(wait)) {
                 Ok(message) => {
                     match message {
                         SshMessage::SendRaw(data) => {
                             self.handle_send_raw(&data);
                         }
+                        SshMessage::SendBytes(data) => {
+                            if let Err(e) = self.connection.send_bytes(&data) {
+                                crate::app_log!(error, "SshActor", "🎭 数据发送失败: {}", e);
+                            }
+                        }
                         SshMessage::ReadOutput => {
                             // 输出在上面的循环中处理
                         }
                         SshMessage::CheckStatus => {
                             self.handle_check_status();
                         }
+                        SshMessage::AddForward(rule) => {
+                            self.connection.add_forward(rule);
+                        }
+                        SshMessage::RemoveForward(rule_id) => {
+                            self.connection.remove_forward(&rule_id);
+                        }
+                        SshMessage::Sftp(request) => {
+                            self.connection.handle_sftp(request);
+                        }
+                        SshMessage::Resize(cols, rows) => {
+                            if let Err(e) = self.connection.resize_terminal(cols, rows) {
+                                crate::app_log!(warn, "SshActor", "🎭 调整终端尺寸失败: {}", e);
+                            }
+                        }
                         SshMessage::Disconnect => {
                             crate::app_log!(info, "SshActor", "🎭 收到断开请求，退出Actor");
                             break;
@@ -107,8 +197,9 @@ impl SshActor {
             }
         }
         
-        // 清理资源
+        // 清理资源，唤醒UI检测会话结束
         self.cleanup();
+        self.request_repaint();
         crate::app_log!(info, "SshActor", "🎭 SSH Actor主循环结束");
     }
     
@@ -143,18 +234,27 @@ pub struct SshActorHandle {
     /// 消息发送器 - 向Actor发送操作请求
     message_sender: Sender<SshMessage>,
     /// 输出接收器 - 接收来自Actor的SSH输出
-    output_receiver: Receiver<String>,
+    output_receiver: Receiver<Vec<u8>>,
+    /// 端口转发状态（Actor写入，UI读取）
+    forward_status: SharedTunnelStatus,
+    /// 文件传输队列（Actor写入，UI读取）
+    transfer_status: SharedTransferStatus,
+    /// 会话结束原因（Actor写入，UI读取）
+    session_end: SharedSessionEnd,
     /// Actor线程句柄
     _actor_handle: thread::JoinHandle<()>,
 }
 
 impl SshActorHandle {
-    /// 创建SSH Actor和对应的句柄
-    pub fn spawn(connection: Ssh2Connection) -> Self {
+    /// 创建SSH Actor和对应的句柄，repaint用于在有新输出时唤醒UI
+    pub fn spawn(connection: Ssh2Connection, repaint: Option<egui::Context>) -> Self {
         let (msg_tx, msg_rx) = mpsc::channel::<SshMessage>();
-        let (out_tx, out_rx) = mpsc::channel::<String>();
+        let (out_tx, out_rx) = mpsc::channel::<Vec<u8>>();
+        let forward_status = connection.forward_status();
+        let transfer_status = connection.transfer_status();
+        let session_end = Arc::clone(&connection.session_end);
         
-        let actor = SshActor::new(connection, msg_rx, out_tx);
+        let actor = SshActor::new(connection, msg_rx, out_tx, repaint);
         let actor_handle = thread::spawn(move || {
             actor.run();
         });
@@ -162,6 +262,9 @@ impl SshActorHandle {
         Self {
             message_sender: msg_tx,
             output_receiver: out_rx,
+            forward_status,
+            transfer_status,
+            session_end,
             _actor_handle: actor_handle,
         }
     }
@@ -174,23 +277,79 @@ impl SshActorHandle {
         crate::app_log!(info, "SshActorHandle", "🚀 数据已提交给Actor: {:?}", data);
         Ok(())
     }
+
+    /// 发送原始字节（不做字符编码转换）
+    pub fn send_bytes(&self, data: Vec<u8>) -> Result<()> {
+        self.message_sender
+            .send(SshMessage::SendBytes(data))
+            .map_err(|_| anyhow!("数据发送失败：Actor已关闭"))
+    }
     
     /// 🎯 便捷方法：发送命令（自动添加换行符）
     pub fn execute_command(&self, command: &str) -> Result<()> {
         self.send_raw(&format!("{}\n", command))
     }
     
-    /// 从 SSH Actor 读取输出
-    pub fn read_output(&self) -> Result<String> {
+    /// 从 SSH Actor 读取输出（原始字节，由终端按连接的编码解码）
+    pub fn read_output(&self) -> Result<Vec<u8>> {
         match self.output_receiver.try_recv() {
             Ok(data) => {
                 crate::app_log!(debug, "SshActorHandle", "📨 从Actor收到输出: {} 字节", data.len());
                 Ok(data)
             }
-            Err(_) => Ok(String::new())
+            Err(_) => Ok(Vec::new())
         }
     }
     
+    /// 🔀 启动端口转发
+    pub fn add_forward(&self, rule: ForwardRule) -> Result<()> {
+        self.message_sender
+            .send(SshMessage::AddForward(rule))
+            .map_err(|_| anyhow!("转发请求发送失败：Actor已关闭"))
+    }
+
+    /// 🔀 停止端口转发
+    pub fn remove_forward(&self, rule_id: &str) -> Result<()> {
+        self.message_sender
+            .send(SshMessage::RemoveForward(rule_id.to_string()))
+            .map_err(|_| anyhow!("转发请求发送失败：Actor已关闭"))
+    }
+
+    /// 🔀 端口转发状态快照
+    pub fn forward_status(&self) -> Vec<TunnelStatus> {
+        self.forward_status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
+
+    /// 📁 发送SFTP请求
+    pub fn sftp(&self, request: SftpRequest) -> Result<()> {
+        self.message_sender
+            .send(SshMessage::Sftp(request))
+            .map_err(|_| anyhow!("SFTP请求发送失败：Actor已关闭"))
+    }
+
+    /// 🔌 会话结束原因（仍在运行时为None）
+    pub fn session_end(&self) -> Option<SessionEnd> {
+        self.session_end.lock().ok().and_then(|end| end.clone())
+    }
+
+    /// 📦 文件传输队列快照
+    pub fn transfer_status(&self) -> Vec<TransferItem> {
+        self.transfer_status
+            .lock()
+            .map(|items| items.clone())
+            .unwrap_or_default()
+    }
+
+    /// 📐 调整远程PTY尺寸
+    pub fn resize(&self, cols: u16, rows: u16) -> Result<()> {
+        self.message_sender
+            .send(SshMessage::Resize(cols, rows))
+            .map_err(|_| anyhow!("尺寸调整请求发送失败：Actor已关闭"))
+    }
+
     /// 断开SSH Actor
     pub fn disconnect(&self) -> Result<()> {
         self.message_sender
@@ -203,9 +362,18 @@ impl SshActorHandle {
 /// SSH2连接结构体 - 简化版本（被Actor管理）
 pub struct Ssh2Connection {
     pub config: ConnectionConfig,
+    prompter: Option<AuthPrompter>,
+    jump_hosts: Vec<ConnectionConfig>, // 按顺序经过的跳板机
     session: Session,
     channel: Option<Channel>,
     tcp_stream: Option<TcpStream>,
+    jump_tunnels: Vec<JumpTunnel>, // 必须比会话活得久，断开时最后释放
+    agent_forwarder: Option<AgentForwarder>,
+    port_forwarder: Option<PortForwarder>,
+    forward_status: SharedTunnelStatus,
+    sftp: Option<SftpClient>,
+    transfer_status: SharedTransferStatus,
+    session_end: SharedSessionEnd,
     pub is_connected: bool,
     pub terminal_size: (u16, u16),
 }
@@ -215,29 +383,143 @@ impl Ssh2Connection {
     pub fn new(config: ConnectionConfig) -> Self {
         Self {
             config,
+            prompter: None,
+            jump_hosts: Vec::new(),
             session: Session::new().unwrap(),
             channel: None,
             tcp_stream: None,
+            jump_tunnels: Vec::new(),
+            agent_forwarder: None,
+            port_forwarder: None,
+            forward_status: Arc::new(Mutex::new(Vec::new())),
+            sftp: None,
+            transfer_status: Arc::new(Mutex::new(Vec::new())),
+            session_end: Arc::new(Mutex::new(None)),
             is_connected: false,
             terminal_size: (80, 24), // 默认终端尺寸
         }
     }
 
+    /// 设置认证提示器（用于私钥口令和keyboard-interactive）
+    pub fn with_prompter(mut self, prompter: Option<AuthPrompter>) -> Self {
+        self.prompter = prompter;
+        self
+    }
+
+    /// 设置跳板机链（ProxyJump），为空时直连
+    pub fn with_jump_hosts(mut self, jump_hosts: Vec<ConnectionConfig>) -> Self {
+        self.jump_hosts = jump_hosts;
+        self
+    }
+
+    /// 底层SSH会话（跳板机隧道使用）
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// 完整跳转链名称，用于日志和错误信息
+    pub fn chain_label(&self) -> String {
+        self.config.chain_label(&self.jump_hosts)
+    }
+
     /// 建立SSH连接
     pub async fn connect(&mut self) -> Result<()> {
-        crate::app_log!(info, "SSH2", "开始连接到 {}@{}:{}", 
-            self.config.username, self.config.host, self.config.port);
+        crate::app_log!(info, "SSH2", "开始连接到 {} (端口 {})", 
+            self.chain_label(), self.config.port);
+
+        let tcp = self.open_transport().await?;
+        self.start_session(tcp).await.map_err(|e| {
+            if self.jump_hosts.is_empty() {
+                e
+            } else {
+                e.context(format!("目标主机 {} 连接失败", self.config.hop_label()))
+            }
+        })?;
+
+        // 创建Shell通道
+        let mut channel = self.session.channel_session()?;
+        channel.request_pty("xterm-256color", None, Some((self.terminal_size.0 as u32, self.terminal_size.1 as u32, 0u32, 0u32)))?;
+        if self.config.forward_agent {
+            self.request_agent_forwarding(&mut channel);
+        }
+        channel.shell()?;
+
+        // 🔀 启动配置中的端口转发
+        let mut port_forwarder = PortForwarder::new(self.session.clone(), Arc::clone(&self.forward_status));
+        for rule in self.config.forwards.iter().filter(|rule| rule.enabled) {
+            port_forwarder.add(rule.clone());
+        }
+        self.port_forwarder = Some(port_forwarder);
+        
+        // 🔑 关键：在创建Shell通道后设置非阻塞模式
+        self.session.set_blocking(false);
+        crate::app_log!(info, "SSH2", "SSH会话已设置为非阻塞模式");
+
+        self.channel = Some(channel);
+        self.is_connected = true;
+
+        crate::app_log!(info, "SSH2", "SSH2连接建立成功: {}", self.chain_label());
+
+        Ok(())
+    }
 
-        // 建立TCP连接
-        let tcp = TcpStream::connect(format!("{}:{}", self.config.host, self.config.port))
+    /// 建立TCP连接
+    fn tcp_connect(host: &str, port: u16) -> Result<TcpStream> {
+        let tcp = TcpStream::connect(format!("{}:{}", host, port))
             .map_err(|e| {
-                crate::app_log!(error, "SSH2", "TCP连接失败: {}", e);
-                anyhow!("TCP连接失败: {}", e)
+                crate::app_log!(error, "SSH2", "TCP连接失败 {}:{}: {}", host, port, e);
+                anyhow!("TCP连接失败 {}:{}: {}", host, port, e)
             })?;
-            
+        tcp.set_nodelay(true)?; // 禁用Nagle算法，提高响应性
+
+        // 💓 TCP保活：对端失联时由内核在约30秒内报错，而不是等待数分钟的重传超时
+        let keepalive = socket2::TcpKeepalive::new()
+            .with_time(Duration::from_secs(KEEPALIVE_INTERVAL as u64))
+            .with_interval(Duration::from_secs(5));
+        #[cfg(not(windows))]
+        let keepalive = keepalive.with_retries(3);
+        if let Err(e) = socket2::SockRef::from(&tcp).set_tcp_keepalive(&keepalive) {
+            crate::app_log!(warn, "SSH2", "设置TCP保活失败 {}:{}: {}", host, port, e);
+        }
+        Ok(tcp)
+    }
+
+    /// 🔗 建立到目标主机的传输通道：直连，或依次经过每个跳板机的 direct-tcpip 隧道
+    async fn open_transport(&mut self) -> Result<TcpStream> {
+        let Some(first_hop) = self.jump_hosts.first() else {
+            return Self::tcp_connect(&self.config.host, self.config.port);
+        };
+
+        let mut stream = Self::tcp_connect(&first_hop.host, first_hop.port)
+            .map_err(|e| e.context(format!("跳板机 {} 连接失败", first_hop.hop_label())))?;
+
+        for (i, hop_config) in self.jump_hosts.iter().enumerate() {
+            let hop_label = hop_config.hop_label();
+            crate::app_log!(info, "SSH2", "🔗 连接跳板机 {}/{}: {}", 
+                i + 1, self.jump_hosts.len(), hop_label);
+
+            // 每一跳使用自己保存的凭据认证
+            let mut hop = Ssh2Connection::new(hop_config.clone()).with_prompter(self.prompter.clone());
+            hop.start_session(stream)
+                .await
+                .map_err(|e| e.context(format!("跳板机 {} 连接失败", hop_label)))?;
+
+            let (next_host, next_port) = match self.jump_hosts.get(i + 1) {
+                Some(next) => (next.host.as_str(), next.port),
+                None => (self.config.host.as_str(), self.config.port),
+            };
+            let (tunnel, local) = JumpTunnel::open(hop, next_host, next_port)?;
+            self.jump_tunnels.push(tunnel);
+            stream = local;
+        }
+
+        Ok(stream)
+    }
+
+    /// 在已建立的传输通道上完成SSH握手、主机密钥校验和认证
+    async fn start_session(&mut self, tcp: TcpStream) -> Result<()> {
         tcp.set_read_timeout(Some(Duration::from_secs(30)))?;
         tcp.set_write_timeout(Some(Duration::from_secs(30)))?;
-        tcp.set_nodelay(true)?; // 禁用Nagle算法，提高响应性
 
         // 设置SSH会话
         self.session.set_tcp_stream(tcp.try_clone()?);
@@ -245,90 +527,316 @@ impl Ssh2Connection {
         // 设置SSH会话选项，提高兼容性
         self.session.set_compress(true);
         self.session.set_timeout(30000); // 30秒超时
+        // 💓 空闲时定期发送保活，避免被NAT/防火墙断开，并及时发现失联的连接
+        self.session.set_keepalive(true, KEEPALIVE_INTERVAL);
         
         // 尝试SSH握手
-        crate::app_log!(info, "SSH2", "开始SSH握手...");
+        crate::app_log!(info, "SSH2", "开始SSH握手: {}", self.config.hop_label());
         self.session.handshake().map_err(|e| {
             crate::app_log!(error, "SSH2", "SSH握手失败: {}", e);
             anyhow!("密钥交换失败，可能是服务器不支持客户端的加密算法。请检查：\n1. SSH服务器是否正常运行\n2. 防火墙是否阻止连接\n3. 网络连接是否稳定")
         })?;
         
-        crate::app_log!(info, "SSH2", "SSH握手成功，开始认证...");
+        // 🔐 认证前校验主机密钥，防止中间人攻击
+        crate::app_log!(info, "SSH2", "SSH握手成功，校验主机密钥...");
+        known_hosts::verify(&self.session, &self.config.host, self.config.port)?;
+
+        crate::app_log!(info, "SSH2", "主机密钥校验通过，开始认证...");
 
         // 认证
         self.authenticate().await?;
 
-        // 创建Shell通道
-        let mut channel = self.session.channel_session()?;
-        channel.request_pty("xterm-256color", None, Some((self.terminal_size.0 as u32, self.terminal_size.1 as u32, 0u32, 0u32)))?;
-        channel.shell()?;
-        
-        // 🔑 关键：在创建Shell通道后设置非阻塞模式
-        self.session.set_blocking(false);
-        crate::app_log!(info, "SSH2", "SSH会话已设置为非阻塞模式");
-
-        self.channel = Some(channel);
         self.tcp_stream = Some(tcp);
-        self.is_connected = true;
-
-        crate::app_log!(info, "SSH2", "SSH2连接建立成功: {}@{}", 
-            self.config.username, self.config.host);
-
         Ok(())
     }
 
-    /// SSH认证 - 支持密码和公钥认证
+    /// SSH认证 - 按配置顺序依次尝试服务器支持的认证方式
     async fn authenticate(&mut self) -> Result<()> {
-        match self.config.auth_type {
-            AuthType::Password => {
-                if let Some(password) = &self.config.password {
-                    crate::app_log!(info, "SSH2", "使用密码认证: {}", self.config.username);
-                    
-                    self.session
-                        .userauth_password(&self.config.username, password)
-                        .map_err(|e| {
-                            crate::app_log!(error, "SSH2", "密码认证失败: {}", e);
-                            anyhow!("密码认证失败，请检查用户名和密码是否正确: {}", e)
-                        })?
-                } else {
-                    return Err(anyhow!("密码认证需要提供密码"));
+        // 查询服务器支持的认证方式（none认证可能已经直接通过）
+        let advertised = match self.session.auth_methods(&self.config.username) {
+            Ok(methods) => methods.to_string(),
+            Err(_) if self.session.authenticated() => {
+                crate::app_log!(info, "SSH2", "服务器无需认证: {}", self.config.username);
+                return Ok(());
+            }
+            Err(e) => {
+                crate::app_log!(error, "SSH2", "查询认证方式失败: {}", e);
+                return Err(anyhow!("查询服务器认证方式失败: {}", e));
+            }
+        };
+        crate::app_log!(info, "SSH2", "服务器支持的认证方式: {}", advertised);
+
+        let mut failures = Vec::new();
+        for method in self.config.auth_sequence() {
+            if !advertised.split(',').any(|m| m == method.protocol_name()) {
+                crate::app_log!(debug, "SSH2", "服务器不支持{}，跳过", method.label());
+                continue;
+            }
+
+            match self.try_authenticate(&method) {
+                Ok(_) if self.session.authenticated() => {
+                    crate::app_log!(info, "SSH2", "SSH认证成功({}): {}", 
+                        method.label(), self.config.username);
+                    return Ok(());
+                }
+                Ok(_) => failures.push(format!("{}: 认证未通过", method.label())),
+                Err(e) => {
+                    crate::app_log!(warn, "SSH2", "{}失败: {}", method.label(), e);
+                    failures.push(format!("{}: {}", method.label(), e));
                 }
             }
+        }
+
+        crate::app_log!(error, "SSH2", "认证失败：所有认证方式均未通过");
+        if failures.is_empty() {
+            Err(anyhow!("认证失败：服务器仅支持 {}，请调整认证方式", advertised))
+        } else {
+            Err(anyhow!("认证失败：\n{}", failures.join("\n")))
+        }
+    }
+
+    /// 尝试单一认证方式
+    fn try_authenticate(&mut self, method: &AuthType) -> Result<()> {
+        match method {
+            AuthType::Password => {
+                let password = self.config.password.as_deref()
+                    .filter(|p| !p.is_empty())
+                    .ok_or_else(|| anyhow!("未保存密码"))?;
+                crate::app_log!(info, "SSH2", "使用密码认证: {}", self.config.username);
+
+                self.session
+                    .userauth_password(&self.config.username, password)
+                    .map_err(|e| anyhow!("密码错误或账户不可用: {}", e))
+            }
             AuthType::PublicKey => {
-                if let Some(key_file) = &self.config.key_file {
-                    crate::app_log!(info, "SSH2", "使用公钥认证: {}", key_file);
-                    
-                    self.session
-                        .userauth_pubkey_file(&self.config.username, None, 
-                                            std::path::Path::new(key_file), None)
-                        .map_err(|e| {
-                            crate::app_log!(error, "SSH2", "公钥认证失败: {}", e);
-                            anyhow!("公钥认证失败，请检查私钥文件路径和权限: {}", e)
-                        })?
-                } else {
-                    return Err(anyhow!("公钥认证需要提供私钥文件"));
+                let key_file = self.config.key_file.clone()
+                    .filter(|k| !k.is_empty())
+                    .ok_or_else(|| anyhow!("未配置私钥文件"))?;
+                crate::app_log!(info, "SSH2", "使用公钥认证: {}", key_file);
+
+                self.authenticate_with_key_file(&key_file)
+            }
+            AuthType::Agent => {
+                crate::app_log!(info, "SSH2", "使用SSH Agent认证: {}", self.config.username);
+                self.authenticate_with_agent()
+            }
+            AuthType::KeyboardInteractive => {
+                crate::app_log!(info, "SSH2", "使用keyboard-interactive认证: {}", self.config.username);
+                let mut responder = InteractiveResponder {
+                    prompter: self.prompter.as_ref(),
+                    password: self.config.password.as_deref().filter(|p| !p.is_empty()),
+                    cancelled: false,
+                };
+                let result = self.session
+                    .userauth_keyboard_interactive(&self.config.username, &mut responder);
+                if responder.cancelled {
+                    return Err(anyhow!("用户取消了验证"));
+                }
+                result.map_err(|e| anyhow!("验证码或回答不正确: {}", e))
+            }
+        }
+    }
+
+    /// 🔑 私钥文件认证 - 加密私钥会向UI请求口令（最多3次）
+    fn authenticate_with_key_file(&mut self, key_file: &str) -> Result<()> {
+        let path = std::path::Path::new(key_file);
+        let cached = auth_prompt::cached_passphrase(key_file);
+
+        let first_error = match self.session
+            .userauth_pubkey_file(&self.config.username, None, path, cached.as_deref())
+        {
+            Ok(_) => return Ok(()),
+            Err(e) => e,
+        };
+
+        if !auth_prompt::key_is_encrypted(key_file) {
+            return Err(anyhow!("请检查私钥文件路径和权限: {}", first_error));
+        }
+        if cached.is_some() {
+            auth_prompt::forget_passphrase(key_file);
+        }
+
+        let prompter = self.prompter.clone()
+            .ok_or_else(|| anyhow!("私钥已加密，但当前无法输入口令"))?;
+        let mut last_error = first_error;
+        for attempt in 0..3 {
+            let reply = prompter
+                .ask_passphrase(key_file, attempt > 0 || cached.is_some())
+                .ok_or_else(|| anyhow!("已取消输入私钥口令"))?;
+
+            match self.session.userauth_pubkey_file(
+                &self.config.username, None, path, Some(&reply.passphrase))
+            {
+                Ok(_) => {
+                    if reply.remember {
+                        auth_prompt::cache_passphrase(key_file, &reply.passphrase);
+                    }
+                    return Ok(());
+                }
+                Err(e) => {
+                    crate::app_log!(warn, "SSH2", "私钥口令错误或被拒绝: {}", e);
+                    last_error = e;
                 }
             }
         }
 
-        if !self.session.authenticated() {
-            crate::app_log!(error, "SSH2", "认证失败：用户名或密码不正确");
-            return Err(anyhow!("认证失败：用户名或密码不正确"));
+        Err(anyhow!("私钥口令错误: {}", last_error))
+    }
+
+    /// 🔑 SSH Agent认证 - 依次尝试agent中的每个身份
+    fn authenticate_with_agent(&mut self) -> Result<()> {
+        let mut agent = self.session.agent()?;
+        agent.connect().map_err(|e| {
+            crate::app_log!(error, "SSH2", "连接SSH Agent失败: {}", e);
+            anyhow!("无法连接SSH Agent，请确认ssh-agent已启动且SSH_AUTH_SOCK已设置: {}", e)
+        })?;
+        agent.list_identities()?;
+
+        let identities = agent.identities()?;
+        if identities.is_empty() {
+            let _ = agent.disconnect();
+            return Err(anyhow!("SSH Agent中没有可用的身份，请先执行 ssh-add"));
         }
 
-        crate::app_log!(info, "SSH2", "SSH认证成功: {}", self.config.username);
+        for identity in &identities {
+            match agent.userauth(&self.config.username, identity) {
+                Ok(_) => {
+                    crate::app_log!(info, "SSH2", "SSH Agent身份认证成功: {}", identity.comment());
+                    break;
+                }
+                Err(e) => {
+                    crate::app_log!(debug, "SSH2", "SSH Agent身份被拒绝 {}: {}", identity.comment(), e);
+                }
+            }
+        }
+        let _ = agent.disconnect();
+
+        if !self.session.authenticated() {
+            return Err(anyhow!("SSH Agent中的 {} 个身份均被服务器拒绝", identities.len()));
+        }
         Ok(())
     }
 
+    /// 🔑 请求在Shell通道上转发agent（失败时只记录警告，不影响连接）
+    fn request_agent_forwarding(&mut self, channel: &mut Channel) {
+        let forwarder = AgentForwarder::install(&self.session);
+        match channel.request_auth_agent_forwarding() {
+            Ok(_) => {
+                crate::app_log!(info, "SSH2", "已启用SSH Agent转发");
+                self.agent_forwarder = Some(forwarder);
+            }
+            Err(e) => {
+                crate::app_log!(warn, "SSH2", "服务器拒绝SSH Agent转发: {}", e);
+            }
+        }
+    }
+
+    /// 🔑 处理agent转发通道的数据（由Actor循环调用）
+    pub fn pump_agent_forwarding(&mut self) {
+        if let Some(forwarder) = &mut self.agent_forwarder {
+            forwarder.pump();
+        }
+    }
+
+    /// 端口转发状态表（Actor句柄持有同一份）
+    pub fn forward_status(&self) -> SharedTunnelStatus {
+        Arc::clone(&self.forward_status)
+    }
+
+    /// 🔀 在Actor线程中搬运端口转发数据，返回是否有活动连接
+    pub fn pump_port_forwards(&mut self) -> bool {
+        match &mut self.port_forwarder {
+            Some(forwarder) => {
+                forwarder.pump();
+                forwarder.is_busy()
+            }
+            None => false,
+        }
+    }
+
+    pub fn add_forward(&mut self, rule: ForwardRule) {
+        match &mut self.port_forwarder {
+            Some(forwarder) => forwarder.add(rule),
+            None => crate::app_log!(warn, "SSH2", "连接未建立，无法启动转发: {}", rule.summary()),
+        }
+    }
+
+    pub fn remove_forward(&mut self, rule_id: &str) {
+        if let Some(forwarder) = &mut self.port_forwarder {
+            forwarder.remove(rule_id);
+        }
+    }
+
+    /// 🔌 会话是否已结束（Actor据此退出循环）
+    pub fn session_end(&self) -> Option<SessionEnd> {
+        self.session_end.lock().ok().and_then(|end| end.clone())
+    }
+
+    /// 标记会话结束，只记录第一次的原因
+    fn end_session(&mut self, end: SessionEnd) {
+        self.is_connected = false;
+        if let Ok(mut current) = self.session_end.lock()
+            && current.is_none()
+        {
+            crate::app_log!(warn, "SSH2", "🔌 会话结束 {}: {:?}", self.chain_label(), end);
+            *current = Some(end);
+        }
+    }
+
+    /// 💓 发送SSH保活（libssh2按设置的间隔决定是否真正发送）
+    pub fn send_keepalive(&mut self) {
+        if !self.is_connected {
+            return;
+        }
+        match self.session.keepalive_send() {
+            Ok(_) => {}
+            Err(e) if e.code() == ErrorCode::Session(raw::LIBSSH2_ERROR_EAGAIN) => {}
+            Err(e) => self.end_session(SessionEnd::Lost(format!("保活失败: {}", e))),
+        }
+    }
+
+    /// 文件传输队列（Actor句柄持有同一份）
+    pub fn transfer_status(&self) -> SharedTransferStatus {
+        Arc::clone(&self.transfer_status)
+    }
+
+    /// 📁 执行SFTP请求（SFTP子系统在首次请求时打开）
+    pub fn handle_sftp(&mut self, request: SftpRequest) {
+        if !self.is_connected {
+            match request {
+                SftpRequest::ListDir { reply, .. } => {
+                    let _ = reply.send(Err(anyhow!("SSH连接未建立")));
+                }
+                SftpRequest::Transfer(command) => {
+                    crate::app_log!(warn, "SSH2", "连接未建立，忽略传输请求: {:?}", command);
+                }
+            }
+            return;
+        }
+        let transfer_status = Arc::clone(&self.transfer_status);
+        self.sftp
+            .get_or_insert_with(|| SftpClient::new(self.session.clone(), transfer_status))
+            .handle(request);
+    }
+
+    /// 📦 推进文件传输，返回是否还有待传输的文件
+    pub fn pump_transfers(&mut self) -> bool {
+        match &mut self.sftp {
+            Some(sftp) if self.is_connected => sftp.pump_transfers(),
+            _ => false,
+        }
+    }
+
     /// 🔑 发送原始数据到SSH服务器（统一接口，调用层决定发送内容）
     pub fn send_raw(&mut self, data: &str) -> Result<()> {
         if !self.is_connected {
             return Err(anyhow!("SSH连接未建立"));
         }
 
-        if let Some(channel) = &mut self.channel {
-            channel.write_all(data.as_bytes())?;
-            channel.flush()?;
+        if self.channel.is_some() {
+            // 按连接配置的编码发送，老旧服务器上输入的中文才能被正确识别
+            let encoded = self.config.encoding.encode(data);
+            self.send_bytes(&encoded)?;
             
             // 根据内容类型提供更好的日志
             if data.ends_with('\n') {
@@ -346,15 +854,26 @@ impl Ssh2Connection {
         }
     }
     
+    /// 发送原始字节到SSH通道
+    pub fn send_bytes(&mut self, data: &[u8]) -> Result<()> {
+        if !self.is_connected {
+            return Err(anyhow!("SSH连接未建立"));
+        }
+        let channel = self.channel.as_mut().ok_or_else(|| anyhow!("SSH通道未创建"))?;
+        channel.write_all(data)?;
+        channel.flush()?;
+        Ok(())
+    }
+
     /// 🎯 便捷方法：发送命令（自动添加换行符）
     pub fn send_command(&mut self, command: &str) -> Result<()> {
         self.send_raw(&format!("{}\n", command))
     }
 
-    /// 读取SSH输出 - 完全非阻塞实现
-    pub fn read_output(&mut self) -> Result<String> {
+    /// 读取SSH输出 - 完全非阻塞实现，返回原始字节（多字节字符可能被拆分在两次读取之间）
+    pub fn read_output(&mut self) -> Result<Vec<u8>> {
         if !self.is_connected {
-            return Ok(String::new());
+            return Ok(Vec::new());
         }
 
         if let Some(channel) = &mut self.channel {
@@ -363,37 +882,36 @@ impl Ssh2Connection {
             // 使用try_read或者设置非阻塞模式
             match channel.read(&mut buffer) {
                 Ok(n) if n > 0 => {
-                    let data = String::from_utf8_lossy(&buffer[..n]).to_string();
                     crate::app_log!(debug, "SSH2", "读取到SSH输出: {} 字节", n);
-                    Ok(data)
+                    Ok(buffer[..n].to_vec())
+                }
+                Ok(_) if channel.eof() => {
+                    // 远端关闭了Shell（如执行了exit）
+                    self.end_session(SessionEnd::Closed);
+                    Ok(Vec::new())
                 }
                 Ok(_) => {
-                    // 没有数据，返回空字符串
-                    Ok(String::new())
+                    // 没有数据
+                    Ok(Vec::new())
                 }
                 Err(e) => {
                     // 检查是否为非阻塞读取的正常情况
                     match e.kind() {
                         std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => {
                             // 非阻塞模式下没有数据可读或超时
-                            Ok(String::new())
-                        }
-                        std::io::ErrorKind::BrokenPipe | std::io::ErrorKind::ConnectionAborted => {
-                            // 连接已断开
-                            crate::app_log!(warn, "SSH2", "SSH连接已断开: {}", e);
-                            self.is_connected = false;
-                            Ok(String::new())
+                            Ok(Vec::new())
                         }
                         _ => {
-                            // 其他错误，记录但不抛出，避免中断整个流程
-                            crate::app_log!(warn, "SSH2", "读取SSH输出错误: {}", e);
-                            Ok(String::new())
+                            // 连接已断开（socket错误、对端重置、保活超时等）
+                            crate::app_log!(warn, "SSH2", "SSH连接已断开: {}", e);
+                            self.end_session(SessionEnd::Lost(e.to_string()));
+                            Ok(Vec::new())
                         }
                     }
                 }
             }
         } else {
-            Ok(String::new())
+            Ok(Vec::new())
         }
     }
 
@@ -402,7 +920,10 @@ impl Ssh2Connection {
         self.terminal_size = (width, height);
         
         if let Some(channel) = &mut self.channel {
-            channel.request_pty_size(width as u32, height as u32, Some(0), Some(0))?;
+            // 会话处于非阻塞模式，请求需要等到服务器确认
+            with_blocking(&self.session, || {
+                channel.request_pty_size(width as u32, height as u32, Some(0), Some(0))
+            })?;
             crate::app_log!(debug, "SSH2", "调整终端尺寸: {}x{}", width, height);
         }
         
@@ -416,12 +937,16 @@ impl Ssh2Connection {
                 let _ = channel.close();
                 let _ = channel.wait_close();
             }
+            self.agent_forwarder = None;
+            self.port_forwarder = None;
+            self.sftp = None;
 
             self.session.disconnect(None, "User requested disconnection", None)?;
             self.is_connected = false;
+            // 目标会话断开后再关闭跳板机隧道
+            self.jump_tunnels.clear();
             
-            crate::app_log!(info, "SSH2", "SSH2连接已断开: {}@{}", 
-                self.config.username, self.config.host);
+            crate::app_log!(info, "SSH2", "SSH2连接已断开: {}", self.chain_label());
         }
         
         Ok(())
@@ -440,7 +965,7 @@ pub struct Ssh2ConnectionWrapper {
     // 命令发送通道
     command_sender: Sender<String>,
     // 输出接收通道  
-    output_receiver: Receiver<String>,
+    output_receiver: Receiver<Vec<u8>>,
     // 线程句柄
     _read_handle: thread::JoinHandle<()>,
     _write_handle: thread::JoinHandle<()>,
@@ -453,7 +978,7 @@ impl Ssh2ConnectionWrapper {
         
         // 创建通道
         let (cmd_sender, cmd_receiver) = mpsc::channel::<String>();
-        let (out_sender, out_receiver) = mpsc::channel::<String>();
+        let (out_sender, out_receiver) = mpsc::channel::<Vec<u8>>();
         
         // 🔑 关键：独立的读取线程
         let read_connection = Arc::clone(&connection);
@@ -552,13 +1077,13 @@ impl Ssh2ConnectionWrapper {
     }
     
     /// 🔑 读取输出（完全无锁）
-    pub fn read_output(&self) -> Result<String> {
+    pub fn read_output(&self) -> Result<Vec<u8>> {
         match self.output_receiver.try_recv() {
             Ok(data) => {
                 crate::app_log!(debug, "SSH2-Wrapper", "📨 收到输出: {} 字节", data.len());
                 Ok(data)
             }
-            Err(_) => Ok(String::new())
+            Err(_) => Ok(Vec::new())
         }
     }
     
@@ -584,6 +1109,7 @@ pub struct Ssh2Manager {
     // 🔑 关键：使用Actor句柄管理SSH连接，彻底消除锁竞争
     connections: Arc<Mutex<HashMap<String, SshActorHandle>>>,
     runtime: tokio::runtime::Runtime,
+    repaint: Option<egui::Context>, // 交给各连接的Actor，有输出时唤醒UI
 }
 
 impl Default for Ssh2Manager {
@@ -601,15 +1127,30 @@ impl Ssh2Manager {
         Self {
             connections: Arc::new(Mutex::new(HashMap::new())),
             runtime,
+            repaint: None,
         }
     }
 
+    /// 🖌️ SSH输出到达时唤醒UI重绘（UI只在有输入或数据时才重绘）
+    pub fn with_repaint(mut self, ctx: egui::Context) -> Self {
+        self.repaint = Some(ctx);
+        self
+    }
+
     /// 🔑 创建SSH连接（内部可变性）
-    pub fn create_connection(&self, id: String, config: &ConnectionConfig) -> Result<()> {
-        crate::app_log!(info, "SSH2Manager", "🚀 创建SSH连接: {} -> {}@{}:{}", 
-            id, config.username, config.host, config.port);
+    pub fn create_connection(
+        &self,
+        id: String,
+        config: &ConnectionConfig,
+        jump_hosts: Vec<ConnectionConfig>,
+        prompter: Option<AuthPrompter>,
+    ) -> Result<()> {
+        crate::app_log!(info, "SSH2Manager", "🚀 创建SSH连接: {} -> {}:{}", 
+            id, config.chain_label(&jump_hosts), config.port);
         
-        let mut connection = Ssh2Connection::new(config.clone());
+        let mut connection = Ssh2Connection::new(config.clone())
+            .with_prompter(prompter)
+            .with_jump_hosts(jump_hosts);
         
         // 异步连接建立
         let connection_result = self.runtime.block_on(async {
@@ -619,7 +1160,7 @@ impl Ssh2Manager {
         connection_result?;
         
         // 🔑 关键：创建 SSH Actor 句柄，彻底消除锁竞争
-        let actor_handle = SshActorHandle::spawn(connection);
+        let actor_handle = SshActorHandle::spawn(connection, self.repaint.clone());
         
         // 使用内部可变性更新连接集合
         {
@@ -641,13 +1182,18 @@ impl Ssh2Manager {
         }
     }
 
-    /// 🎯 便捷方法：执行命令（Actor模式）
-    pub fn execute_command(&self, id: &str, command: &str) -> Result<()> {
-        self.send_raw(id, &format!("{}\n", command))
+    /// 发送原始字节（Actor模式，不做字符编码转换）
+    pub fn send_bytes(&self, id: &str, data: Vec<u8>) -> Result<()> {
+        let connections = self.connections.lock().unwrap();
+        if let Some(actor_handle) = connections.get(id) {
+            actor_handle.send_bytes(data)
+        } else {
+            Err(anyhow!("连接不存在: {}", id))
+        }
     }
 
-    /// 🔑 读取输出（Actor模式）
-    pub fn read_output(&self, id: &str) -> Result<String> {
+    /// 🔑 读取输出（Actor模式，原始字节）
+    pub fn read_output(&self, id: &str) -> Result<Vec<u8>> {
         let connections = self.connections.lock().unwrap();
         if let Some(actor_handle) = connections.get(id) {
             actor_handle.read_output()
@@ -675,16 +1221,84 @@ impl Ssh2Manager {
         Ok(())
     }
 
+    /// 🔀 在运行中的连接上启动端口转发
+    pub fn add_forward(&self, id: &str, rule: ForwardRule) -> Result<()> {
+        let connections = self.connections.lock().unwrap();
+        match connections.get(id) {
+            Some(actor_handle) => actor_handle.add_forward(rule),
+            None => Err(anyhow!("连接不存在: {}", id)),
+        }
+    }
+
+    /// 🔀 停止端口转发
+    pub fn remove_forward(&self, id: &str, rule_id: &str) -> Result<()> {
+        let connections = self.connections.lock().unwrap();
+        match connections.get(id) {
+            Some(actor_handle) => actor_handle.remove_forward(rule_id),
+            None => Err(anyhow!("连接不存在: {}", id)),
+        }
+    }
+
+    /// 🔀 端口转发状态快照（连接不存在时为空）
+    pub fn forward_status(&self, id: &str) -> Vec<TunnelStatus> {
+        let connections = self.connections.lock().unwrap();
+        connections
+            .get(id)
+            .map(|actor_handle| actor_handle.forward_status())
+            .unwrap_or_default()
+    }
+
+    /// 📁 列出远程目录（结果通过返回的通道异步送达，不阻塞UI）
+    pub fn list_remote_dir(&self, id: &str, path: &str) -> Result<Receiver<Result<RemoteListing>>> {
+        let (reply, result) = mpsc::channel();
+        let connections = self.connections.lock().unwrap();
+        let actor_handle = connections
+            .get(id)
+            .ok_or_else(|| anyhow!("连接不存在: {}", id))?;
+        actor_handle.sftp(SftpRequest::ListDir {
+            path: path.to_string(),
+            reply,
+        })?;
+        Ok(result)
+    }
+
+    /// 📦 提交文件传输命令（在会话的Actor线程中执行）
+    pub fn transfer(&self, id: &str, command: TransferCommand) -> Result<()> {
+        let connections = self.connections.lock().unwrap();
+        match connections.get(id) {
+            Some(actor_handle) => actor_handle.sftp(SftpRequest::Transfer(command)),
+            None => Err(anyhow!("连接不存在: {}", id)),
+        }
+    }
+
+    /// 🔌 会话结束原因（连接不存在或仍在运行时为None）
+    pub fn session_end(&self, id: &str) -> Option<SessionEnd> {
+        let connections = self.connections.lock().unwrap();
+        connections.get(id).and_then(|actor_handle| actor_handle.session_end())
+    }
+
+    /// 📦 文件传输队列快照（连接不存在时为空）
+    pub fn transfer_status(&self, id: &str) -> Vec<TransferItem> {
+        let connections = self.connections.lock().unwrap();
+        connections
+            .get(id)
+            .map(|actor_handle| actor_handle.transfer_status())
+            .unwrap_or_default()
+    }
+
     /// 获取所有连接ID
     pub fn get_connection_ids(&self) -> Vec<String> {
         let connections = self.connections.lock().unwrap();
         connections.keys().cloned().collect()
     }
     
-    /// 调整终端尺寸（预留接口）
-    pub fn resize_terminal(&self, _id: &str, _width: u16, _height: u16) -> Result<()> {
-        // TODO: 实现终端尺寸调整
-        Ok(())
+    /// 📐 调整终端尺寸（列 x 行），在会话的Actor线程中发送 window-change 请求
+    pub fn resize_terminal(&self, id: &str, width: u16, height: u16) -> Result<()> {
+        let connections = self.connections.lock().unwrap();
+        match connections.get(id) {
+            Some(actor_handle) => actor_handle.resize(width, height),
+            None => Err(anyhow!("连接不存在: {}", id)),
+        }
     }
 }
 
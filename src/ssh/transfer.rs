@@ -0,0 +1,543 @@
+use anyhow::{Result, anyhow};
+use ssh2::{OpenFlags, OpenType, Sftp};
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 每次pump最多占用Actor线程的时间，保证Shell输出不被长时间阻塞
+const TIME_SLICE: Duration = Duration::from_millis(30);
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 📦 传输方向
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+/// 📦 传输状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferState {
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+    Cancelled,
+    /// 未传输的条目（如指向目录的符号链接），附带原因
+    Skipped(String),
+}
+
+/// 📦 传输队列中的单个文件或目录
+///
+/// 目录条目在轮到时展开：创建目标目录并读取一层内容，子条目排在它后面；total/transferred为子条目数
+#[derive(Debug, Clone)]
+pub struct TransferItem {
+    pub id: u64,
+    pub direction: TransferDirection,
+    pub local_path: PathBuf,
+    pub remote_path: String,
+    pub is_dir: bool,
+    pub total: u64,
+    pub transferred: u64,
+    pub speed: f64, // 字节/秒
+    pub state: TransferState,
+    resume: bool, // 下次开始时从目标文件已有的长度续传
+}
+
+impl TransferItem {
+    /// 显示用的文件名
+    pub fn name(&self) -> String {
+        match self.direction {
+            TransferDirection::Upload => self
+                .local_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            TransferDirection::Download => remote_file_name(&self.remote_path).to_string(),
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            if self.state == TransferState::Done { 1.0 } else { 0.0 }
+        } else {
+            (self.transferred as f64 / self.total as f64) as f32
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.state,
+            TransferState::Done | TransferState::Failed(_) | TransferState::Cancelled | TransferState::Skipped(_)
+        )
+    }
+}
+
+/// 📦 传输命令 - 由UI发往会话的Actor线程
+#[derive(Debug, Clone)]
+pub enum TransferCommand {
+    /// 上传本地文件或目录（递归）到远程目录
+    Upload { local: PathBuf, remote_dir: String },
+    /// 下载远程文件或目录（递归）到本地目录
+    Download {
+        remote: String,
+        is_dir: bool,
+        local_dir: PathBuf,
+    },
+    Cancel(u64),
+    /// 重新传输；resume为true时从目标文件已有的部分续传。已展开的目录和跳过的条目不能重试
+    Retry { id: u64, resume: bool },
+    /// 移除已完成和已取消的条目
+    ClearFinished,
+}
+
+/// Actor线程写入、UI线程读取的传输队列
+pub type SharedTransferStatus = Arc<Mutex<Vec<TransferItem>>>;
+
+/// 正在进行的传输
+struct ActiveTransfer {
+    id: u64,
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+    transferred: u64,
+    resumed_from: u64,
+    started: Instant,
+}
+
+/// 📦 传输队列 - 在Actor线程中按时间片逐块传输、逐个展开目录，一次只传一个文件
+pub struct TransferQueue {
+    status: SharedTransferStatus,
+    next_id: u64,
+    active: Option<ActiveTransfer>,
+}
+
+impl TransferQueue {
+    pub fn new(status: SharedTransferStatus) -> Self {
+        Self {
+            status,
+            next_id: 1,
+            active: None,
+        }
+    }
+
+    /// 是否还有待传输或正在传输的文件
+    pub fn is_busy(&self) -> bool {
+        self.active.is_some()
+            || self
+                .status
+                .lock()
+                .map(|items| items.iter().any(|item| item.state == TransferState::Queued))
+                .unwrap_or(false)
+    }
+
+    /// 处理UI发来的命令（调用方需处于阻塞模式）
+    pub fn handle(&mut self, sftp: &Sftp, command: TransferCommand) {
+        match command {
+            TransferCommand::Upload { local, remote_dir } => {
+                let remote = join_remote(&remote_dir, &local_file_name(&local));
+                match std::fs::metadata(&local) {
+                    Ok(metadata) => {
+                        let size = if metadata.is_dir() { 0 } else { metadata.len() };
+                        self.push(TransferDirection::Upload, local, remote, metadata.is_dir(), size);
+                    }
+                    Err(e) => {
+                        crate::app_log!(error, "Transfer", "📦 上传失败 {}: {}", local.display(), e);
+                        self.push_failed(TransferDirection::Upload, local, remote, e.to_string());
+                    }
+                }
+            }
+            TransferCommand::Download {
+                remote,
+                is_dir,
+                local_dir,
+            } => {
+                let local = local_dir.join(remote_file_name(&remote));
+                let size = if is_dir { Ok(0) } else { sftp.stat(Path::new(&remote)).map(|stat| stat.size.unwrap_or(0)) };
+                match size {
+                    Ok(size) => self.push(TransferDirection::Download, local, remote, is_dir, size),
+                    Err(e) => {
+                        crate::app_log!(error, "Transfer", "📦 下载失败 {}: {}", remote, e);
+                        self.push_failed(TransferDirection::Download, local, remote, e.to_string());
+                    }
+                }
+            }
+            TransferCommand::Cancel(id) => {
+                if self.active.as_ref().is_some_and(|active| active.id == id) {
+                    self.active = None;
+                }
+                self.update(id, |item| {
+                    if !item.is_finished() {
+                        item.state = TransferState::Cancelled;
+                        item.speed = 0.0;
+                    }
+                });
+            }
+            TransferCommand::Retry { id, resume } => {
+                self.update(id, |item| {
+                    // 已展开的目录，子条目还在队列中，再次展开会重复插入
+                    let expanded = item.is_dir && item.state == TransferState::Done;
+                    if item.is_finished() && !expanded && !matches!(item.state, TransferState::Skipped(_)) {
+                        item.state = TransferState::Queued;
+                        item.transferred = 0;
+                        item.speed = 0.0;
+                        item.resume = resume;
+                    }
+                });
+            }
+            TransferCommand::ClearFinished => {
+                if let Ok(mut items) = self.status.lock() {
+                    items.retain(|item| {
+                        !matches!(
+                            item.state,
+                            TransferState::Done | TransferState::Cancelled | TransferState::Skipped(_)
+                        )
+                    });
+                }
+            }
+        }
+    }
+
+    /// 传输一个时间片（调用方需处于阻塞模式）
+    pub fn pump(&mut self, sftp: &Sftp) {
+        let deadline = Instant::now() + TIME_SLICE;
+
+        while Instant::now() < deadline {
+            let Some(active) = self.active.as_mut() else {
+                // 没有正在传输的文件：打开下一个文件或展开下一个目录
+                if !self.start_next(sftp) {
+                    return;
+                }
+                continue;
+            };
+
+            let id = active.id;
+            let result = Self::copy_chunk(active);
+            let transferred = active.transferred;
+            let elapsed = active.started.elapsed().as_secs_f64();
+            let speed = if elapsed > 0.0 {
+                (transferred - active.resumed_from) as f64 / elapsed
+            } else {
+                0.0
+            };
+
+            match result {
+                Ok(true) => {
+                    self.active = None;
+                    self.update(id, |item| {
+                        item.transferred = transferred;
+                        item.total = item.total.max(transferred);
+                        item.speed = speed;
+                        item.state = TransferState::Done;
+                        crate::app_log!(info, "Transfer", "📦 传输完成: {}", item.name());
+                    });
+                }
+                Ok(false) => {
+                    self.update(id, |item| {
+                        item.transferred = transferred;
+                        item.speed = speed;
+                    });
+                }
+                Err(e) => {
+                    self.active = None;
+                    self.update(id, |item| {
+                        crate::app_log!(warn, "Transfer", "📦 传输失败 {}: {}", item.name(), e);
+                        item.transferred = transferred;
+                        item.speed = 0.0;
+                        item.state = TransferState::Failed(e.to_string());
+                    });
+                }
+            }
+        }
+    }
+
+    /// SFTP不可用时把排队中的文件全部标记为失败，避免反复重试
+    pub fn fail_pending(&mut self, error: &str) {
+        self.active = None;
+        if let Ok(mut items) = self.status.lock() {
+            for item in items.iter_mut().filter(|item| !item.is_finished()) {
+                item.state = TransferState::Failed(error.to_string());
+                item.speed = 0.0;
+            }
+        }
+    }
+
+    /// 复制一块数据，返回是否已传输完毕
+    fn copy_chunk(active: &mut ActiveTransfer) -> Result<bool> {
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        let n = active.reader.read(&mut buffer)?;
+        if n == 0 {
+            active.writer.flush()?;
+            return Ok(true);
+        }
+        active.writer.write_all(&buffer[..n])?;
+        active.transferred += n as u64;
+        Ok(false)
+    }
+
+    /// 处理下一个排队的条目：打开文件或展开目录，返回是否还有排队的条目
+    fn start_next(&mut self, sftp: &Sftp) -> bool {
+        let next = self.status.lock().ok().and_then(|items| {
+            items
+                .iter()
+                .find(|item| item.state == TransferState::Queued)
+                .cloned()
+        });
+        let Some(item) = next else {
+            return false;
+        };
+
+        if item.is_dir {
+            self.expand(sftp, &item);
+            return true;
+        }
+        match Self::open(sftp, &item) {
+            Ok(active) => {
+                let offset = active.transferred;
+                self.update(item.id, |item| {
+                    item.state = TransferState::Running;
+                    item.transferred = offset;
+                });
+                crate::app_log!(info, "Transfer", "📦 开始传输: {} (偏移 {})", item.name(), offset);
+                self.active = Some(active);
+            }
+            Err(e) => {
+                self.update(item.id, |item| item.state = TransferState::Failed(e.to_string()));
+            }
+        }
+        true
+    }
+
+    /// 📂 展开目录条目：子条目插入到它后面，按名称排序
+    ///
+    /// 读取失败时只把该目录标记为失败，不会留下部分子条目，重试时重新展开
+    fn expand(&mut self, sftp: &Sftp, dir: &TransferItem) {
+        let children = match dir.direction {
+            TransferDirection::Upload => self.list_local(sftp, dir),
+            TransferDirection::Download => self.list_remote(sftp, dir),
+        };
+        let children = match children {
+            Ok(children) => children,
+            Err(e) => {
+                crate::app_log!(warn, "Transfer", "📦 展开目录失败 {}: {}", dir.name(), e);
+                self.update(dir.id, |item| item.state = TransferState::Failed(e.to_string()));
+                return;
+            }
+        };
+
+        let count = children.len() as u64;
+        if let Ok(mut items) = self.status.lock()
+            && let Some(index) = items.iter().position(|item| item.id == dir.id)
+        {
+            let item = &mut items[index];
+            item.state = TransferState::Done;
+            item.total = count;
+            item.transferred = count;
+            items.splice(index + 1..index + 1, children);
+        }
+    }
+
+    /// 上传目录的子条目，同时在远程创建该目录
+    fn list_local(&mut self, sftp: &Sftp, dir: &TransferItem) -> Result<Vec<TransferItem>> {
+        ensure_remote_dir(sftp, &dir.remote_path)?;
+        let mut entries: Vec<_> = std::fs::read_dir(&dir.local_path)
+            .map_err(|e| anyhow!("无法读取本地目录 {}: {}", dir.local_path.display(), e))?
+            .flatten()
+            .collect();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let mut children = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let local = entry.path();
+            let remote = join_remote(&dir.remote_path, &entry.file_name().to_string_lossy());
+            let is_symlink = entry.file_type().is_ok_and(|file_type| file_type.is_symlink());
+            let mut child = self.new_item(TransferDirection::Upload, local, remote, false, 0);
+            match std::fs::metadata(&child.local_path) {
+                // 不跟随指向目录的符号链接，避免循环
+                Ok(metadata) if metadata.is_dir() && is_symlink => {
+                    child.state = TransferState::Skipped("已跳过指向目录的符号链接".to_string());
+                }
+                Ok(metadata) => {
+                    child.is_dir = metadata.is_dir();
+                    child.total = if child.is_dir { 0 } else { metadata.len() };
+                }
+                Err(e) => child.state = TransferState::Failed(format!("无法读取本地文件: {}", e)),
+            }
+            children.push(child);
+        }
+        Ok(children)
+    }
+
+    /// 下载目录的子条目，同时在本地创建该目录
+    fn list_remote(&mut self, sftp: &Sftp, dir: &TransferItem) -> Result<Vec<TransferItem>> {
+        std::fs::create_dir_all(&dir.local_path)
+            .map_err(|e| anyhow!("无法创建本地目录 {}: {}", dir.local_path.display(), e))?;
+        let mut entries = sftp
+            .readdir(Path::new(&dir.remote_path))
+            .map_err(|e| anyhow!("无法读取远程目录 {}: {}", dir.remote_path, e))?;
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut children = Vec::with_capacity(entries.len());
+        for (path, stat) in entries {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if name.is_empty() || name == "." || name == ".." {
+                continue;
+            }
+            let remote = join_remote(&dir.remote_path, &name);
+            let mut child = self.new_item(TransferDirection::Download, dir.local_path.join(&name), remote, false, 0);
+            if stat.file_type().is_symlink() {
+                // 符号链接下载目标文件的内容，不跟随指向目录的链接，避免循环
+                match sftp.stat(&path) {
+                    Ok(target) if target.is_dir() => {
+                        child.state = TransferState::Skipped("已跳过指向目录的符号链接".to_string());
+                    }
+                    Ok(target) => child.total = target.size.unwrap_or(0),
+                    Err(e) => child.state = TransferState::Failed(format!("符号链接的目标不可用: {}", e)),
+                }
+            } else {
+                child.is_dir = stat.is_dir();
+                child.total = if child.is_dir { 0 } else { stat.size.unwrap_or(0) };
+            }
+            children.push(child);
+        }
+        Ok(children)
+    }
+
+    fn open(sftp: &Sftp, item: &TransferItem) -> Result<ActiveTransfer> {
+        let remote_path = Path::new(&item.remote_path);
+        let (reader, writer, offset): (Box<dyn Read + Send>, Box<dyn Write + Send>, u64) =
+            match item.direction {
+                TransferDirection::Upload => {
+                    let mut local = std::fs::File::open(&item.local_path)
+                        .map_err(|e| anyhow!("无法打开本地文件 {}: {}", item.local_path.display(), e))?;
+                    let offset = if item.resume {
+                        sftp.stat(remote_path)
+                            .ok()
+                            .and_then(|stat| stat.size)
+                            .unwrap_or(0)
+                            .min(item.total)
+                    } else {
+                        0
+                    };
+                    let flags = if offset > 0 {
+                        OpenFlags::WRITE | OpenFlags::CREATE
+                    } else {
+                        OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE
+                    };
+                    let mut remote = sftp
+                        .open_mode(remote_path, flags, 0o644, OpenType::File)
+                        .map_err(|e| anyhow!("无法写入远程文件 {}: {}", item.remote_path, e))?;
+                    local.seek(SeekFrom::Start(offset))?;
+                    remote.seek(SeekFrom::Start(offset))?;
+                    (Box::new(local), Box::new(remote), offset)
+                }
+                TransferDirection::Download => {
+                    let mut remote = sftp
+                        .open(remote_path)
+                        .map_err(|e| anyhow!("无法读取远程文件 {}: {}", item.remote_path, e))?;
+                    let offset = if item.resume {
+                        std::fs::metadata(&item.local_path)
+                            .map(|metadata| metadata.len())
+                            .unwrap_or(0)
+                            .min(item.total)
+                    } else {
+                        0
+                    };
+                    let mut local = OpenOptions::new()
+                        .write(true)
+                        .create(true)
+                        .truncate(offset == 0)
+                        .open(&item.local_path)
+                        .map_err(|e| anyhow!("无法写入本地文件 {}: {}", item.local_path.display(), e))?;
+                    remote.seek(SeekFrom::Start(offset))?;
+                    local.seek(SeekFrom::Start(offset))?;
+                    (Box::new(remote), Box::new(local), offset)
+                }
+            };
+
+        Ok(ActiveTransfer {
+            id: item.id,
+            reader,
+            writer,
+            transferred: offset,
+            resumed_from: offset,
+            started: Instant::now(),
+        })
+    }
+
+    /// 新的排队条目（分配id，不加入队列）
+    fn new_item(
+        &mut self,
+        direction: TransferDirection,
+        local_path: PathBuf,
+        remote_path: String,
+        is_dir: bool,
+        total: u64,
+    ) -> TransferItem {
+        let item = TransferItem {
+            id: self.next_id,
+            direction,
+            local_path,
+            remote_path,
+            is_dir,
+            total,
+            transferred: 0,
+            speed: 0.0,
+            state: TransferState::Queued,
+            resume: false,
+        };
+        self.next_id += 1;
+        item
+    }
+
+    fn push(&mut self, direction: TransferDirection, local_path: PathBuf, remote_path: String, is_dir: bool, total: u64) {
+        let item = self.new_item(direction, local_path, remote_path, is_dir, total);
+        if let Ok(mut items) = self.status.lock() {
+            items.push(item);
+        }
+    }
+
+    fn push_failed(&mut self, direction: TransferDirection, local_path: PathBuf, remote_path: String, error: String) {
+        let mut item = self.new_item(direction, local_path, remote_path, false, 0);
+        item.state = TransferState::Failed(error);
+        if let Ok(mut items) = self.status.lock() {
+            items.push(item);
+        }
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut TransferItem)) {
+        if let Ok(mut items) = self.status.lock()
+            && let Some(item) = items.iter_mut().find(|item| item.id == id)
+        {
+            f(item);
+        }
+    }
+}
+
+/// 创建远程目录（已存在时忽略）
+fn ensure_remote_dir(sftp: &Sftp, path: &str) -> Result<()> {
+    if sftp.stat(Path::new(path)).is_ok_and(|stat| stat.is_dir()) {
+        return Ok(());
+    }
+    sftp.mkdir(Path::new(path), 0o755)
+        .map_err(|e| anyhow!("无法创建远程目录 {}: {}", path, e))
+}
+
+/// 远程路径始终使用 `/` 分隔
+fn join_remote(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+fn remote_file_name(path: &str) -> &str {
+    path.trim_end_matches('/').rsplit('/').next().unwrap_or(path)
+}
+
+fn local_file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
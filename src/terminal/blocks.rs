@@ -0,0 +1,279 @@
+// 命令块 - 按Shell集成的OSC 133标记把输出划分为提示符/命令/输出/退出码
+
+use std::time::{Duration, Instant};
+
+/// 位置（行号，列），行号见 `TerminalEmulator::first_line`
+pub type LinePos = (usize, u16);
+
+/// 最多保留的命令块数
+const MAX_BLOCKS: usize = 500;
+
+/// OSC 133 标记
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShellMark {
+    PromptStart,             // A：开始打印提示符
+    CommandStart,            // B：提示符结束，开始输入命令
+    OutputStart,             // C：命令开始执行
+    CommandEnd(Option<i32>), // D：命令结束，可带退出码
+}
+
+impl ShellMark {
+    /// 解析OSC内容 "133;X[;参数]"
+    pub fn parse(osc: &str) -> Option<Self> {
+        let mut params = osc.strip_prefix("133;")?.split(';');
+        Some(match params.next()? {
+            "A" => Self::PromptStart,
+            "B" => Self::CommandStart,
+            "C" => Self::OutputStart,
+            "D" => Self::CommandEnd(params.next().and_then(|code| code.parse().ok())),
+            _ => return None,
+        })
+    }
+}
+
+/// 🧱 一个命令块：从提示符开始，到下一个提示符之前结束
+#[derive(Debug, Clone)]
+pub struct CommandBlock {
+    pub prompt_start: LinePos,
+    pub command_start: Option<LinePos>,
+    pub output_start: Option<LinePos>,
+    pub end: Option<LinePos>,
+    pub prompt: String,
+    pub command: String,
+    pub output: String, // 命令结束时保存，之后不受回滚历史淘汰影响
+    pub exit_code: Option<i32>,
+    pub duration: Option<Duration>,
+    pub collapsed: bool,
+    started_at: Option<Instant>,
+}
+
+impl CommandBlock {
+    fn new(prompt_start: LinePos) -> Self {
+        Self {
+            prompt_start,
+            command_start: None,
+            output_start: None,
+            end: None,
+            prompt: String::new(),
+            command: String::new(),
+            output: String::new(),
+            exit_code: None,
+            duration: None,
+            collapsed: true,
+            started_at: None,
+        }
+    }
+
+    /// 是否执行过命令（空回车不算）
+    pub fn has_command(&self) -> bool {
+        self.output_start.is_some()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.has_command() && self.end.is_none()
+    }
+
+    /// 退出码为0时成功；未结束或shell没有报告退出码时为None
+    pub fn succeeded(&self) -> Option<bool> {
+        self.exit_code.map(|code| code == 0)
+    }
+
+    /// 状态说明，如 "✔ 1.2s"、"✘ 127 · 35ms"
+    pub fn status_label(&self) -> String {
+        if self.is_running() {
+            return "⏳ 运行中".to_string();
+        }
+        let duration = self.duration.map(format_duration).unwrap_or_default();
+        match self.exit_code {
+            Some(0) => format!("✔ {}", duration),
+            Some(code) => format!("✘ {} · {}", code, duration),
+            None => duration,
+        }
+    }
+
+    fn finish(&mut self, end: LinePos, exit_code: Option<i32>, output: String) {
+        self.end = Some(end);
+        self.exit_code = exit_code;
+        self.output = output;
+        self.duration = self.started_at.map(|started| started.elapsed());
+    }
+}
+
+/// 命令块列表（收到第一个OSC 133标记后才有内容）
+#[derive(Debug, Default)]
+pub struct CommandBlocks {
+    blocks: Vec<CommandBlock>,
+}
+
+impl CommandBlocks {
+    /// 远端shell是否启用了集成
+    pub fn is_active(&self) -> bool {
+        !self.blocks.is_empty()
+    }
+
+    pub fn blocks(&self) -> &[CommandBlock] {
+        &self.blocks
+    }
+
+    pub fn blocks_mut(&mut self) -> &mut [CommandBlock] {
+        &mut self.blocks
+    }
+
+    /// 最近的一个块（正在输入或正在执行的命令）
+    pub fn current(&self) -> Option<&CommandBlock> {
+        self.blocks.last()
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    /// A：新的提示符。上一个块没有执行命令时直接替换；
+    /// 没收到D就出现新提示符时按未知退出码结束，unfinished_output为它的输出，返回这样结束的块
+    pub fn prompt_start(&mut self, pos: LinePos, unfinished_output: String) -> Option<CommandBlock> {
+        let mut finished = None;
+        if let Some(last) = self.blocks.last_mut() {
+            if !last.has_command() {
+                self.blocks.pop();
+            } else if last.is_running() {
+                last.finish(pos, None, unfinished_output);
+                finished = Some(last.clone());
+            }
+        }
+        self.blocks.push(CommandBlock::new(pos));
+        if self.blocks.len() > MAX_BLOCKS {
+            self.blocks.remove(0);
+        }
+        finished
+    }
+
+    /// B：提示符结束
+    pub fn command_start(&mut self, pos: LinePos, prompt: String) {
+        if let Some(block) = self.blocks.last_mut() {
+            block.command_start = Some(pos);
+            block.prompt = prompt;
+        }
+    }
+
+    /// C：命令开始执行
+    pub fn output_start(&mut self, pos: LinePos, command: String) {
+        if let Some(block) = self.blocks.last_mut() {
+            block.output_start = Some(pos);
+            block.command = command;
+            block.started_at = Some(Instant::now());
+        }
+    }
+
+    /// D：命令结束（没有执行命令时忽略），返回结束的块
+    pub fn command_end(&mut self, pos: LinePos, exit_code: Option<i32>, output: String) -> Option<CommandBlock> {
+        let block = self.blocks.last_mut().filter(|block| block.is_running())?;
+        block.finish(pos, exit_code, output);
+        Some(block.clone())
+    }
+}
+
+/// 耗时显示：320ms、1.2s、2m05s
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        format!("{}ms", millis)
+    } else if millis < 60_000 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else {
+        let secs = duration.as_secs();
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 执行一条命令：提示符在第line行，输出占一行
+    fn run(blocks: &mut CommandBlocks, line: usize, command: &str, exit_code: Option<i32>) -> Option<CommandBlock> {
+        blocks.prompt_start((line, 0), String::new());
+        blocks.command_start((line, 2), "$ ".to_string());
+        blocks.output_start((line + 1, 0), command.to_string());
+        blocks.command_end((line + 2, 0), exit_code, "out".to_string())
+    }
+
+    #[test]
+    fn parses_shell_marks() {
+        assert_eq!(ShellMark::parse("133;A"), Some(ShellMark::PromptStart));
+        assert_eq!(ShellMark::parse("133;B"), Some(ShellMark::CommandStart));
+        assert_eq!(ShellMark::parse("133;C"), Some(ShellMark::OutputStart));
+        assert_eq!(ShellMark::parse("133;D;127"), Some(ShellMark::CommandEnd(Some(127))));
+        assert_eq!(ShellMark::parse("133;D"), Some(ShellMark::CommandEnd(None)));
+        assert_eq!(ShellMark::parse("133;Z"), None);
+        assert_eq!(ShellMark::parse("7;file://h/"), None);
+    }
+
+    #[test]
+    fn command_lifecycle() {
+        let mut blocks = CommandBlocks::default();
+        assert!(!blocks.is_active());
+        blocks.prompt_start((0, 0), String::new());
+        blocks.command_start((0, 2), "$ ".to_string());
+        blocks.output_start((1, 0), "ls".to_string());
+        assert!(blocks.current().unwrap().is_running());
+        assert_eq!(blocks.current().unwrap().status_label(), "⏳ 运行中");
+
+        let finished = blocks.command_end((3, 0), Some(0), "a\nb".to_string()).unwrap();
+        assert_eq!(finished.command, "ls");
+        assert_eq!(finished.prompt, "$ ");
+        assert_eq!(finished.output, "a\nb");
+        assert_eq!(finished.end, Some((3, 0)));
+        assert_eq!(finished.succeeded(), Some(true));
+        assert!(finished.duration.is_some());
+        assert!(finished.status_label().starts_with("✔ "));
+        // 重复的D忽略
+        assert!(blocks.command_end((4, 0), Some(1), String::new()).is_none());
+    }
+
+    #[test]
+    fn empty_prompt_is_replaced() {
+        let mut blocks = CommandBlocks::default();
+        blocks.prompt_start((0, 0), String::new());
+        blocks.command_start((0, 2), "$ ".to_string());
+        assert!(blocks.prompt_start((1, 0), String::new()).is_none());
+        assert_eq!(blocks.blocks().len(), 1);
+        assert_eq!(blocks.current().unwrap().prompt_start, (1, 0));
+    }
+
+    #[test]
+    fn new_prompt_finishes_command_without_exit_code() {
+        let mut blocks = CommandBlocks::default();
+        blocks.prompt_start((0, 0), String::new());
+        blocks.output_start((1, 0), "vim".to_string());
+        let finished = blocks.prompt_start((5, 0), "unfinished".to_string()).unwrap();
+        assert_eq!(finished.exit_code, None);
+        assert_eq!(finished.succeeded(), None);
+        assert_eq!(finished.output, "unfinished");
+        assert_eq!(blocks.blocks().len(), 2);
+    }
+
+    #[test]
+    fn failed_command_label_shows_exit_code() {
+        let mut blocks = CommandBlocks::default();
+        let finished = run(&mut blocks, 0, "false", Some(2)).unwrap();
+        assert_eq!(finished.succeeded(), Some(false));
+        assert!(finished.status_label().starts_with("✘ 2 · "));
+    }
+
+    #[test]
+    fn keeps_at_most_max_blocks() {
+        let mut blocks = CommandBlocks::default();
+        for index in 0..MAX_BLOCKS + 10 {
+            run(&mut blocks, index * 3, "true", Some(0));
+        }
+        assert_eq!(blocks.blocks().len(), MAX_BLOCKS);
+        assert_eq!(blocks.blocks()[0].prompt_start, (30, 0));
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(Duration::from_millis(320)), "320ms");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1.5s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m05s");
+    }
+}
@@ -0,0 +1,72 @@
+// 远端剪贴板 - 解析OSC 52读写本地剪贴板的请求
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// 单次写入剪贴板的最大字节数，防止远端塞入过大的内容
+const MAX_CLIPBOARD_BYTES: usize = 1024 * 1024;
+
+/// 📋 OSC 52 请求
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClipboardRequest {
+    /// 写入剪贴板
+    Set(String),
+    /// 读取剪贴板，回复时带回请求中的选择区参数
+    Query { selection: String },
+}
+
+impl ClipboardRequest {
+    /// 解析OSC内容 "52;选择区;base64数据"，数据为 "?" 时为读取；空数据（清空剪贴板）和无效数据忽略
+    pub fn parse(osc: &str) -> Option<Self> {
+        let (selection, data) = osc.strip_prefix("52;")?.split_once(';')?;
+        if data == "?" {
+            return Some(Self::Query {
+                selection: selection.to_string(),
+            });
+        }
+        if data.len() > MAX_CLIPBOARD_BYTES / 3 * 4 + 4 {
+            crate::app_log!(warn, "VT100", "📋 忽略过大的剪贴板写入: {} 字节", data.len());
+            return None;
+        }
+        let bytes = STANDARD.decode(data).ok().filter(|bytes| !bytes.is_empty())?;
+        Some(Self::Set(String::from_utf8_lossy(&bytes).into_owned()))
+    }
+
+    pub fn is_query(&self) -> bool {
+        matches!(self, Self::Query { .. })
+    }
+}
+
+/// 读取请求的回复 `ESC ] 52 ; 选择区 ; base64 BEL`，请求没有指定选择区时按剪贴板（c）回复
+pub fn clipboard_reply(selection: &str, text: &str) -> String {
+    let selection = if selection.is_empty() { "c" } else { selection };
+    format!("\x1b]52;{};{}\x07", selection, STANDARD.encode(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_set_and_query() {
+        assert_eq!(ClipboardRequest::parse("52;c;aGVsbG8="), Some(ClipboardRequest::Set("hello".to_string())));
+        let query = ClipboardRequest::parse("52;p;?").unwrap();
+        assert!(query.is_query());
+        assert_eq!(query, ClipboardRequest::Query { selection: "p".to_string() });
+    }
+
+    #[test]
+    fn ignores_empty_invalid_and_oversized_data() {
+        assert_eq!(ClipboardRequest::parse("52;c;"), None);
+        assert_eq!(ClipboardRequest::parse("52;c;!!!"), None);
+        assert_eq!(ClipboardRequest::parse("7;file://h/tmp"), None);
+        let oversized = format!("52;c;{}", "A".repeat(MAX_CLIPBOARD_BYTES / 3 * 4 + 8));
+        assert_eq!(ClipboardRequest::parse(&oversized), None);
+    }
+
+    #[test]
+    fn reply_defaults_to_clipboard_selection() {
+        assert_eq!(clipboard_reply("", "hi"), "\x1b]52;c;aGk=\x07");
+        assert_eq!(clipboard_reply("p", "hi"), "\x1b]52;p;aGk=\x07");
+    }
+}
@@ -0,0 +1,141 @@
+// 字符编码 - 远程终端使用的编码，以及SSH输出的增量解码
+
+use serde::{Deserialize, Serialize};
+
+/// 🔤 终端字符编码（老旧服务器可能仍在使用GBK/Big5）
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum TerminalEncoding {
+    #[default]
+    Utf8,
+    Gbk,
+    Gb18030,
+    Big5,
+}
+
+impl TerminalEncoding {
+    pub const ALL: [TerminalEncoding; 4] = [
+        TerminalEncoding::Utf8,
+        TerminalEncoding::Gbk,
+        TerminalEncoding::Gb18030,
+        TerminalEncoding::Big5,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TerminalEncoding::Utf8 => "UTF-8",
+            TerminalEncoding::Gbk => "GBK",
+            TerminalEncoding::Gb18030 => "GB18030",
+            TerminalEncoding::Big5 => "Big5",
+        }
+    }
+
+    pub fn encoding(&self) -> &'static encoding_rs::Encoding {
+        match self {
+            TerminalEncoding::Utf8 => encoding_rs::UTF_8,
+            TerminalEncoding::Gbk => encoding_rs::GBK,
+            TerminalEncoding::Gb18030 => encoding_rs::GB18030,
+            TerminalEncoding::Big5 => encoding_rs::BIG5,
+        }
+    }
+
+    /// 把输入编码为远程终端使用的字节
+    pub fn encode<'a>(&self, text: &'a str) -> std::borrow::Cow<'a, [u8]> {
+        self.encoding().encode(text).0
+    }
+}
+
+/// 🔤 增量解码器 - 把SSH输出的字节流解码为文本
+///
+/// SSH按固定大小的块读取，多字节字符可能被拆在两块之间。
+/// 解码器保留不完整的尾部字节，等下一块到达后再一起解码，避免出现乱码（U+FFFD）。
+pub struct StreamDecoder {
+    decoder: encoding_rs::Decoder,
+}
+
+impl StreamDecoder {
+    pub fn new(encoding: TerminalEncoding) -> Self {
+        Self {
+            // UTF-8流中的BOM按普通字符处理，不做编码嗅探
+            decoder: encoding.encoding().new_decoder_without_bom_handling(),
+        }
+    }
+
+    /// 解码一块数据，不完整的多字节序列留到下次
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        let capacity = self
+            .decoder
+            .max_utf8_buffer_length(bytes.len())
+            .unwrap_or(bytes.len() * 3 + 4);
+        let mut output = String::with_capacity(capacity);
+        let (_, _, _) = self.decoder.decode_to_string(bytes, &mut output, false);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在每个字节边界把数据拆成两块解码
+    fn assert_split_decodes(encoding: TerminalEncoding, bytes: &[u8], expected: &str) {
+        for split in 0..=bytes.len() {
+            let mut decoder = StreamDecoder::new(encoding);
+            let mut text = decoder.decode(&bytes[..split]);
+            text.push_str(&decoder.decode(&bytes[split..]));
+            assert_eq!(text, expected, "{} split at {}", encoding.label(), split);
+        }
+    }
+
+    #[test]
+    fn utf8_character_split_across_reads() {
+        let text = "ls 中文目录\r\n";
+        assert_split_decodes(TerminalEncoding::Utf8, text.as_bytes(), text);
+    }
+
+    #[test]
+    fn utf8_character_fed_byte_by_byte() {
+        let mut decoder = StreamDecoder::new(TerminalEncoding::Utf8);
+        let text: String = "中".as_bytes().iter().map(|b| decoder.decode(&[*b])).collect();
+        assert_eq!(text, "中");
+    }
+
+    #[test]
+    fn gbk_character_split_across_reads() {
+        // "中文" 的GBK编码
+        let bytes = [b'a', 0xD6, 0xD0, 0xCE, 0xC4, b'b'];
+        assert_split_decodes(TerminalEncoding::Gbk, &bytes, "a中文b");
+        assert_split_decodes(TerminalEncoding::Gb18030, &bytes, "a中文b");
+    }
+
+    #[test]
+    fn big5_character_split_across_reads() {
+        // "中" 的Big5编码
+        assert_split_decodes(TerminalEncoding::Big5, &[0xA4, 0xA4], "中");
+    }
+
+    #[test]
+    fn invalid_utf8_becomes_replacement_without_losing_following_bytes() {
+        let mut decoder = StreamDecoder::new(TerminalEncoding::Utf8);
+        assert_eq!(decoder.decode(b"a\xffb"), "a\u{FFFD}b");
+        // 被截断的多字节序列后面跟着ASCII
+        assert_eq!(decoder.decode(b"\xe4\xb8"), "");
+        assert_eq!(decoder.decode(b"x\xe4\xb8\xad"), "\u{FFFD}x中");
+    }
+
+    #[test]
+    fn invalid_gbk_becomes_replacement_without_losing_following_bytes() {
+        let mut decoder = StreamDecoder::new(TerminalEncoding::Gbk);
+        assert_eq!(decoder.decode(&[0xFF, b'o', b'k']), "\u{FFFD}ok");
+        // 双字节的首字节后面跟着ASCII：ASCII字节保留
+        assert_eq!(decoder.decode(&[0x81]), "");
+        assert_eq!(decoder.decode(&[b' ', 0xD6, 0xD0]), "\u{FFFD} 中");
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        for encoding in TerminalEncoding::ALL {
+            let bytes = encoding.encode("中文 ok");
+            assert_eq!(StreamDecoder::new(encoding).decode(&bytes), "中文 ok", "{}", encoding.label());
+        }
+    }
+}
@@ -0,0 +1,922 @@
+use vt100;
+
+use super::blocks::{CommandBlock, CommandBlocks, LinePos, ShellMark};
+use super::clipboard::ClipboardRequest;
+use super::decoder::{StreamDecoder, TerminalEncoding};
+use super::keys::KeyModes;
+use super::links::{Hyperlink, HyperlinkMark, Hyperlinks};
+use super::mouse::MouseReporting;
+use super::osc::{OscPiece, OscSplitter};
+use super::search::LineText;
+use super::selection::{Selection, SelectionMode};
+use super::shell_integration::WorkingDirectory;
+use super::types::{CellColor, CursorStyle, TerminalLine, TerminalSegment};
+
+/// 默认回滚历史行数
+pub const DEFAULT_SCROLLBACK_LINES: usize = 10_000;
+
+/// 回滚历史行数下限：解析时按历史上限分块，上限太小会让大量输出被切成很小的块
+pub const MIN_SCROLLBACK_LINES: usize = 1_000;
+
+/// 由我们处理的OSC序列：7为工作目录，8为超链接，52为剪贴板，133为Shell集成的命令块标记
+const INTERCEPTED_OSC: &[&str] = &["7", "8", "52", "133"];
+
+/// CSI序列的开头。DEC私有模式（`CSI ? Pm h/l`，切换备用屏幕也用它）和向上滚动（`CSI Ps S`）在解析时单独成块
+const CSI: &str = "\x1b[";
+
+/// vt100通过回调报告的标题和响铃
+#[derive(Default)]
+struct ParserEvents {
+    title: String,
+    bell: bool, // 自上次取走以来收到过响铃（BEL或可视响铃）
+}
+
+impl vt100::Callbacks for ParserEvents {
+    fn audible_bell(&mut self, _: &mut vt100::Screen) {
+        self.bell = true;
+    }
+
+    fn visual_bell(&mut self, _: &mut vt100::Screen) {
+        self.bell = true;
+    }
+
+    fn set_window_title(&mut self, _: &mut vt100::Screen, title: &[u8]) {
+        self.title = String::from_utf8_lossy(title).into_owned();
+    }
+}
+
+/// 远端程序设置的终端模式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerminalModes {
+    pub keys: KeyModes,               // 应用光标键/应用小键盘
+    pub mouse: Option<MouseReporting>, // 鼠标报告，未开启时为None
+    pub bracketed_paste: bool,
+    pub alternate_screen: bool, // vim、less等使用的备用屏幕
+    pub cursor_hidden: bool,
+    pub cursor_style: CursorStyle,
+}
+
+/// 🩹 自上次 `take_damage` 以来屏幕上变化的部分
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Damage {
+    pub rows: Vec<u16>, // 内容变化的屏幕行（升序）
+    pub cursor: bool,   // 光标位置、形状或可见性变化
+}
+
+impl Damage {
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty() && !self.cursor
+    }
+}
+
+/// 光标状态：位置、是否隐藏、样式，变化时记为damage
+type CursorState = ((u16, u16), bool, CursorStyle);
+
+/// 🖥️ 终端模拟器 - vt100解析 + 我们自己处理的OSC序列，不依赖界面
+///
+/// 输入字节（`feed`），读取屏幕（`screen`）、变化的行（`take_damage`）、模式（`modes`）和标题（`title`）。
+pub struct TerminalEmulator {
+    parser: vt100::Parser<ParserEvents>,
+    decoder: StreamDecoder, // 按远端编码增量解码输入的字节
+    width: u16,
+    height: u16,
+    scrollback_limit: usize, // 主屏幕滚出顶部的行由vt100保留，备用屏幕（vim等）不产生回滚
+    history: usize,          // 主屏幕的回滚历史行数（vt100不直接提供，每次解析后测得）
+    dropped: usize,          // 回滚历史写满后丢弃的行数，只增不减：行号 = 丢弃的行数 + 在历史中的位置
+    cursor_style: CursorStyle, // vt100不跟踪DECSCUSR，由我们自己解析
+    osc: OscSplitter,
+    blocks: CommandBlocks,
+    finished_commands: Vec<CommandBlock>, // 自上次取走以来结束的命令
+    hyperlinks: Hyperlinks,
+    clipboard_requests: Vec<ClipboardRequest>, // 等待界面按权限处理的OSC 52请求
+    working_directory: Option<WorkingDirectory>, // 远端shell通过OSC 7报告的当前目录
+    lines: Vec<TerminalLine>, // 当前视图（考虑回滚偏移），每个屏幕行一项
+    cells: Vec<Vec<vt100::Cell>>, // 提取lines时各行的单元格，单元格不变的行不再重新提取
+    links: Vec<Hyperlink>,        // 提取lines时视图内的超链接
+    damaged_rows: Vec<bool>,
+    cursor: CursorState,
+    cursor_damaged: bool,
+}
+
+impl TerminalEmulator {
+    pub fn new(width: u16, height: u16) -> Self {
+        let mut emulator = Self {
+            parser: vt100::Parser::new_with_callbacks(height, width, DEFAULT_SCROLLBACK_LINES, ParserEvents::default()),
+            decoder: StreamDecoder::new(TerminalEncoding::Utf8),
+            width,
+            height,
+            scrollback_limit: DEFAULT_SCROLLBACK_LINES,
+            history: 0,
+            dropped: 0,
+            cursor_style: CursorStyle::default(),
+            osc: OscSplitter::new(INTERCEPTED_OSC),
+            blocks: CommandBlocks::default(),
+            finished_commands: Vec::new(),
+            hyperlinks: Hyperlinks::default(),
+            clipboard_requests: Vec::new(),
+            working_directory: None,
+            lines: Vec::new(),
+            cells: Vec::new(),
+            links: Vec::new(),
+            damaged_rows: Vec::new(),
+            cursor: ((0, 0), false, CursorStyle::default()),
+            cursor_damaged: false,
+        };
+        emulator.refresh();
+        emulator
+    }
+
+    /// 🔤 设置远端的字符编码（每次建立连接前调用，同时丢弃上个会话残留的半个字符）
+    pub fn set_encoding(&mut self, encoding: TerminalEncoding) {
+        self.decoder = StreamDecoder::new(encoding);
+    }
+
+    /// 📥 输入远端输出的字节，不完整的多字节字符留到下一批
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let text = self.decoder.decode(bytes);
+        if !text.is_empty() {
+            self.feed_text(&text);
+        }
+    }
+
+    /// 📥 输入已解码的文本（如本地插入的提示）
+    pub fn feed_text(&mut self, data: &str) {
+        self.track_cursor_style(data);
+
+        // 有新输出时回到底部；OSC 133标记按此时的光标位置记录
+        self.parser.screen_mut().set_scrollback(0);
+        // 将数据传给解析器，取出的OSC序列按在数据中的位置依次处理
+        for piece in self.osc.split(data) {
+            match piece {
+                OscPiece::Text(text) => self.process(&text),
+                OscPiece::Osc(osc) => self.handle_osc(&osc),
+            }
+        }
+        self.refresh();
+    }
+
+    /// 交给vt100解析，同时统计回滚历史写满后丢弃的行数
+    ///
+    /// vt100不报告滚出了多少行，但视图不在底部时每滚出一行偏移加一（上限为历史行数）：
+    /// 解析前把偏移设为1，解析后的偏移减一即滚出的行数。数据按 `chunk_len` 分块，
+    /// 保证一块滚出的行数达不到上限；切换备用屏幕会把偏移清零，因此DEC私有模式序列单独成块。
+    fn process(&mut self, text: &str) {
+        for chunk in split_chunks(text, self.chunk_len()) {
+            let alternate = self.parser.screen().alternate_screen();
+            self.parser.screen_mut().set_scrollback(1);
+            self.parser.process(chunk.as_bytes());
+            let scrolled = self.scroll_offset().saturating_sub(1);
+            self.parser.screen_mut().set_scrollback(0);
+
+            // 备用屏幕没有回滚历史，主屏幕的历史在此期间不变
+            if alternate || self.parser.screen().alternate_screen() {
+                continue;
+            }
+            // 历史为空时偏移无法设为1，滚出的行都进入了历史（一块滚出的行数小于上限，不会丢弃）
+            let before = self.history;
+            self.history = self.measure_history();
+            self.dropped += (before + scrolled).saturating_sub(self.history);
+        }
+    }
+
+    /// 每块交给vt100的字节数，一块滚出的行数要小于历史上限减一
+    ///
+    /// 换行和自动折行每个字节最多滚出一行；`CSI Ps S` 最多滚出一屏，完整的序列单独成块，
+    /// 但上一批数据末尾不完整的序列会在下一块开头补完，因此每块再留出一屏的余量。
+    fn chunk_len(&self) -> usize {
+        self.scrollback_limit.saturating_sub(2 + self.height as usize).max(1)
+    }
+
+    /// 📺 当前视图的内容（考虑回滚偏移），每个屏幕行一项
+    pub fn screen(&self) -> &[TerminalLine] {
+        &self.lines
+    }
+
+    /// 🩹 取走自上次调用以来变化的行和光标
+    pub fn take_damage(&mut self) -> Damage {
+        let rows = self
+            .damaged_rows
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(row, damaged)| std::mem::take(damaged).then_some(row as u16))
+            .collect();
+        Damage {
+            rows,
+            cursor: std::mem::take(&mut self.cursor_damaged),
+        }
+    }
+
+    /// 远端程序设置的终端模式
+    pub fn modes(&self) -> TerminalModes {
+        let screen = self.parser.screen();
+        let mouse_mode = screen.mouse_protocol_mode();
+        TerminalModes {
+            keys: KeyModes {
+                application_cursor: screen.application_cursor(),
+                application_keypad: screen.application_keypad(),
+            },
+            mouse: (mouse_mode != vt100::MouseProtocolMode::None).then(|| MouseReporting {
+                mode: mouse_mode,
+                encoding: screen.mouse_protocol_encoding(),
+            }),
+            bracketed_paste: screen.bracketed_paste(),
+            alternate_screen: screen.alternate_screen(),
+            cursor_hidden: screen.hide_cursor(),
+            cursor_style: self.cursor_style,
+        }
+    }
+
+    /// 窗口标题（OSC 0/2），远端没有设置时为空
+    pub fn title(&self) -> &str {
+        &self.parser.callbacks().title
+    }
+
+    /// 🔔 自上次调用以来是否响过铃
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.parser.callbacks_mut().bell)
+    }
+
+    /// 解析光标样式序列 `ESC [ Ps SP q`
+    fn track_cursor_style(&mut self, data: &str) {
+        for (start, _) in data.match_indices("\x1b[") {
+            let rest = &data[start + 2..];
+            let digits = rest.bytes().take_while(|b| b.is_ascii_digit()).count();
+            if !rest[digits..].starts_with(" q") {
+                continue;
+            }
+            let param = rest[..digits].parse().unwrap_or(0);
+            if let Some(style) = CursorStyle::from_decscusr(param) {
+                crate::app_log!(debug, "VT100", "光标样式: {:?}", style);
+                self.cursor_style = style;
+            }
+        }
+    }
+
+    /// 处理vt100不支持的OSC序列
+    fn handle_osc(&mut self, osc: &str) {
+        if let Some(mark) = ShellMark::parse(osc) {
+            self.apply_shell_mark(mark);
+        } else if let Some(mark) = HyperlinkMark::parse(osc) {
+            self.apply_hyperlink_mark(mark);
+        } else if let Some(request) = ClipboardRequest::parse(osc) {
+            crate::app_log!(debug, "VT100", "📋 OSC 52: {}", if request.is_query() { "读取" } else { "写入" });
+            self.clipboard_requests.push(request);
+        } else if let Some(directory) = WorkingDirectory::from_osc7(osc) {
+            if self.working_directory.as_ref() != Some(&directory) {
+                crate::app_log!(debug, "VT100", "📂 工作目录: {}:{}", directory.host, directory.path);
+                self.working_directory = Some(directory);
+            }
+        } else {
+            crate::app_log!(debug, "VT100", "忽略OSC序列: {:?}", osc);
+        }
+    }
+
+    /// 取走远端程序的剪贴板请求
+    pub fn take_clipboard_requests(&mut self) -> Vec<ClipboardRequest> {
+        std::mem::take(&mut self.clipboard_requests)
+    }
+
+    /// 远端shell报告的当前目录（需要shell发送OSC 7）
+    pub fn working_directory(&self) -> Option<&WorkingDirectory> {
+        self.working_directory.as_ref()
+    }
+
+    /// 🧱 按OSC 133标记划分命令块，提示符/命令/输出的文本在标记到达时从屏幕读取
+    fn apply_shell_mark(&mut self, mark: ShellMark) {
+        // 备用屏幕（vim等）没有回滚历史，行号无法对应
+        if self.parser.screen().alternate_screen() {
+            return;
+        }
+        let pos = self.cursor_line_pos();
+        crate::app_log!(debug, "VT100", "🧱 Shell标记 {:?} @ {:?}", mark, pos);
+        let current = self.blocks.current();
+        match mark {
+            ShellMark::PromptStart => {
+                let unfinished = current.filter(|b| b.is_running()).and_then(|b| b.output_start);
+                let output = unfinished.map(|start| self.text_between(start, pos)).unwrap_or_default();
+                let finished = self.blocks.prompt_start(pos, output.trim_end().to_string());
+                self.finished_commands.extend(finished);
+            }
+            ShellMark::CommandStart => {
+                let prompt = current.map(|b| b.prompt_start).map(|start| self.text_between(start, pos));
+                self.blocks.command_start(pos, prompt.unwrap_or_default().trim().to_string());
+            }
+            ShellMark::OutputStart => {
+                let command = current.and_then(|b| b.command_start).map(|start| self.text_between(start, pos));
+                self.blocks.output_start(pos, command.unwrap_or_default().trim().to_string());
+            }
+            ShellMark::CommandEnd(exit_code) => {
+                let output = current.and_then(|b| b.output_start).map(|start| self.text_between(start, pos));
+                let finished = self.blocks.command_end(pos, exit_code, output.unwrap_or_default().trim_end().to_string());
+                self.finished_commands.extend(finished);
+            }
+        }
+    }
+
+    /// 🔗 OSC 8：链接开始时记下光标位置，结束时记录覆盖的区间（新链接开始也会结束上一个）
+    fn apply_hyperlink_mark(&mut self, mark: HyperlinkMark) {
+        if self.parser.screen().alternate_screen() {
+            return;
+        }
+        let pos = self.cursor_line_pos();
+        if let Some(start) = self.hyperlinks.open_start() {
+            let text = self.text_between(start, pos);
+            self.hyperlinks.close(pos, text);
+        }
+        if let HyperlinkMark::Open(uri) = mark {
+            crate::app_log!(debug, "VT100", "🔗 超链接 {} @ {:?}", uri, pos);
+            self.hyperlinks.open(pos, uri);
+        }
+    }
+
+    /// 光标位置（行号，列）
+    fn cursor_line_pos(&self) -> LinePos {
+        (self.cursor_line(), self.cursor_position().1)
+    }
+
+    /// 两个位置之间的文本，换行规则与复制选区相同（已从历史中丢弃的部分忽略）
+    fn text_between(&mut self, start: LinePos, end: LinePos) -> String {
+        if end <= start {
+            return String::new();
+        }
+        let first = start.0.max(self.first_line());
+        let lines = self.lines_in(first..end.0 + 1);
+        Selection::new(SelectionMode::Normal, start, end).text(&lines, first, self.width)
+    }
+
+    /// Shell集成划分出的命令块
+    pub fn command_blocks(&self) -> &CommandBlocks {
+        &self.blocks
+    }
+
+    pub fn command_blocks_mut(&mut self) -> &mut CommandBlocks {
+        &mut self.blocks
+    }
+
+    /// 取走自上次调用以来结束的命令
+    pub fn take_finished_commands(&mut self) -> Vec<CommandBlock> {
+        std::mem::take(&mut self.finished_commands)
+    }
+
+    /// 获取终端尺寸
+    pub fn size(&self) -> (u16, u16) {
+        (self.height, self.width)
+    }
+
+    /// 📐 调整屏幕尺寸（行，列），屏幕内容由vt100按新宽度保留
+    pub fn resize(&mut self, height: u16, width: u16) {
+        if (height, width) == (self.height, self.width) {
+            return;
+        }
+        self.parser.screen_mut().set_size(height, width);
+        self.height = height;
+        self.width = width;
+        crate::app_log!(debug, "VT100", "📐 屏幕尺寸: {}行 x {}列", height, width);
+        self.refresh();
+    }
+
+    /// 设置回滚历史行数上限（不小于 `MIN_SCROLLBACK_LINES`）。vt100只能在创建时指定，因此会重建解析器，应在产生输出之前调用
+    pub fn set_scrollback_limit(&mut self, lines: usize) {
+        let lines = lines.max(MIN_SCROLLBACK_LINES);
+        if lines == self.scrollback_limit {
+            return;
+        }
+        self.scrollback_limit = lines;
+        let events = std::mem::take(self.parser.callbacks_mut());
+        self.parser = vt100::Parser::new_with_callbacks(self.height, self.width, lines, events);
+        self.dropped += std::mem::take(&mut self.history);
+        self.blocks.clear();
+        self.hyperlinks.clear();
+        self.refresh();
+    }
+
+    /// 📜 当前向上回滚的行数（0表示在底部）
+    pub fn scroll_offset(&self) -> usize {
+        self.parser.screen().scrollback()
+    }
+
+    /// 📜 回滚lines行（正数向上翻看历史），偏移限制在已有历史范围内
+    pub fn scroll_by(&mut self, lines: isize) {
+        let offset = self.scroll_offset().saturating_add_signed(lines);
+        self.scroll_to(offset);
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll_to(0);
+    }
+
+    /// 回滚到指定偏移（超出历史范围时截断）
+    pub fn scroll_to(&mut self, offset: usize) {
+        let offset = offset.min(self.history_len());
+        if offset != self.scroll_offset() {
+            self.parser.screen_mut().set_scrollback(offset);
+            self.refresh();
+        }
+    }
+
+    /// 📜 已有的回滚历史行数，备用屏幕没有历史
+    pub fn history_len(&self) -> usize {
+        if self.parser.screen().alternate_screen() { 0 } else { self.history }
+    }
+
+    /// 测量主屏幕的回滚历史行数：vt100不提供历史长度，但保证把偏移截断到已有历史范围内
+    fn measure_history(&mut self) -> usize {
+        let offset = self.scroll_offset();
+        self.parser.screen_mut().set_scrollback(usize::MAX);
+        let len = self.scroll_offset();
+        self.parser.screen_mut().set_scrollback(offset);
+        len
+    }
+
+    /// 📜 仍保留的最早一行的行号，更早的行已从回滚历史中丢弃
+    ///
+    /// 行号在整个会话中保持不变：命令块、超链接、选区和搜索匹配都使用行号，历史写满后也不会错位。
+    pub fn first_line(&self) -> usize {
+        self.dropped
+    }
+
+    /// 视图第一行的行号
+    pub fn top_line(&self) -> usize {
+        self.first_line() + self.history_len() - self.scroll_offset()
+    }
+
+    /// 光标所在行的行号
+    pub fn cursor_line(&self) -> usize {
+        self.first_line() + self.history_len() + self.cursor_position().0 as usize
+    }
+
+    /// 🔍 回滚历史 + 当前屏幕的纯文本，第一项的行号为 `first_line`
+    pub fn searchable_lines(&mut self) -> Vec<LineText> {
+        self.lines_in(self.first_line()..usize::MAX)
+    }
+
+    /// 行号区间内的纯文本。已丢弃和尚未出现的行忽略，第一项的行号为 `range.start.max(first_line)`
+    pub fn lines_in(&mut self, range: std::ops::Range<usize>) -> Vec<LineText> {
+        let saved_offset = self.scroll_offset();
+        let first = self.first_line();
+        let history = self.history_len();
+        let rows = self.height as usize;
+        let start = range.start.max(first) - first;
+        let end = range.end.saturating_sub(first).min(history + rows);
+        let mut lines = Vec::with_capacity(end.saturating_sub(start));
+
+        // vt100只能按屏幕窗口读取历史：把要读的行滚到视图顶部，逐屏向下
+        let mut line = start;
+        while line < end {
+            let offset = history.saturating_sub(line);
+            self.parser.screen_mut().set_scrollback(offset);
+            let screen = self.parser.screen();
+            let top = history - offset;
+            while line < end && line < top + rows {
+                lines.push(Self::line_text(screen, (line - top) as u16));
+                line += 1;
+            }
+        }
+
+        self.parser.screen_mut().set_scrollback(saved_offset);
+        lines
+    }
+
+    /// 视图中第row行（考虑回滚偏移）的纯文本
+    pub fn visible_line_text(&self, row: u16) -> LineText {
+        Self::line_text(self.parser.screen(), row)
+    }
+
+    fn line_text(screen: &vt100::Screen, row: u16) -> LineText {
+        let mut line = LineText::new(screen.row_wrapped(row));
+        for col in 0..screen.size().1 {
+            let Some(cell) = screen.cell(row, col) else {
+                continue;
+            };
+            if cell.is_wide_continuation() {
+                continue;
+            }
+            let contents = cell.contents();
+            let text = if contents.is_empty() { " " } else { contents };
+            line.push(text, col, if cell.is_wide() { 2 } else { 1 });
+        }
+        line
+    }
+
+    /// 获取光标位置
+    pub fn cursor_position(&self) -> (u16, u16) {
+        let pos = self.parser.screen().cursor_position();
+        (pos.0, pos.1)
+    }
+
+    /// 🔑 从vt100更新视图内容：只重新提取单元格变化的行，内容不同的行记为damage
+    fn refresh(&mut self) {
+        let links = self.visible_hyperlinks();
+        // 链接区间变化（很少发生）时所有行的链接属性都要重新计算
+        let links_changed = links != self.links;
+        let screen = self.parser.screen();
+        let (rows, cols) = screen.size();
+
+        if self.lines.len() != rows as usize {
+            self.lines = vec![TerminalLine::new(); rows as usize];
+            self.cells = vec![Vec::new(); rows as usize];
+            self.damaged_rows = vec![true; rows as usize];
+        }
+        for row in 0..rows {
+            let cells = &mut self.cells[row as usize];
+            let unchanged = !links_changed
+                && cells.len() == cols as usize
+                && cells.iter().zip(0..cols).all(|(cell, col)| screen.cell(row, col) == Some(cell));
+            if unchanged {
+                continue;
+            }
+            cells.clear();
+            cells.extend((0..cols).filter_map(|col| screen.cell(row, col)).cloned());
+
+            let line = Self::extract_line(screen, row, &links);
+            if line != self.lines[row as usize] {
+                self.lines[row as usize] = line;
+                self.damaged_rows[row as usize] = true;
+            }
+        }
+        self.links = links;
+
+        let cursor = (self.cursor_position(), screen.hide_cursor(), self.cursor_style);
+        if cursor != self.cursor {
+            self.cursor = cursor;
+            self.cursor_damaged = true;
+        }
+    }
+
+    /// 视图内的超链接，行号换算为屏幕行（超出视图的部分截到视图边缘）
+    ///
+    /// 清屏后新内容可能落在旧链接的位置上，因此完整在视图内的链接要求文本与记录时一致。
+    fn visible_hyperlinks(&self) -> Vec<Hyperlink> {
+        if self.parser.screen().alternate_screen() {
+            return Vec::new();
+        }
+        let rows = self.height as usize;
+        let top = self.top_line();
+        let candidates: Vec<Hyperlink> = self.hyperlinks.overlapping(top..top + rows).cloned().collect();
+        if candidates.is_empty() {
+            return candidates;
+        }
+
+        let screen_lines: Vec<LineText> = (0..self.height).map(|row| self.visible_line_text(row)).collect();
+        candidates
+            .into_iter()
+            .filter_map(|mut link| {
+                let inside = link.start.0 >= top && link.end.0 < top + rows;
+                link.start = if link.start.0 >= top { (link.start.0 - top, link.start.1) } else { (0, 0) };
+                link.end = if link.end.0 < top + rows { (link.end.0 - top, link.end.1) } else { (rows, 0) };
+                if inside {
+                    let text = Selection::new(SelectionMode::Normal, link.start, link.end).text(&screen_lines, 0, self.width);
+                    if text != link.text {
+                        return None;
+                    }
+                }
+                Some(link)
+            })
+            .collect()
+    }
+
+    /// 从屏幕提取单行内容，links为 `visible_hyperlinks` 的结果
+    ///
+    /// 空单元格以空格占位，保证每个片段的列位置准确；宽字符和非ASCII字符单独成段，
+    /// 由渲染器按单元格定位，避免字体的字形宽度与单元格不一致导致错位。
+    fn extract_line(screen: &vt100::Screen, row: u16, links: &[Hyperlink]) -> TerminalLine {
+        let mut line = TerminalLine::new();
+        let mut current_segment = TerminalSegment::default();
+        let mut current_standalone = false;
+        let screen_width = screen.size().1;
+        
+        for col in 0..screen_width {
+            let Some(cell) = screen.cell(row, col) else {
+                continue;
+            };
+            // 宽字符的右半格已由左半格覆盖
+            if cell.is_wide_continuation() {
+                continue;
+            }
+
+            let contents = cell.contents();
+            let text = if contents.is_empty() { " " } else { contents };
+            let cell_width = if cell.is_wide() { 2 } else { 1 };
+            let standalone = cell_width == 2 || !text.is_ascii();
+
+            // 检查字符属性是否变化（粗体的基本色显示为对应的亮色）
+            let new_attrs = TerminalSegment {
+                text: String::new(),
+                col,
+                width: 0,
+                color: Self::cell_color(cell.fgcolor(), cell.bold()),
+                background_color: Self::cell_color(cell.bgcolor(), false),
+                bold: cell.bold(),
+                italic: cell.italic(),
+                underline: cell.underline(),
+                inverse: cell.inverse(),
+                link: links
+                    .iter()
+                    .find(|link| link.start <= (row as usize, col) && (row as usize, col) < link.end)
+                    .map(|link| link.uri.clone()),
+            };
+            
+            // 如果属性变化，保存当前片段并开始新片段
+            if current_segment.text.is_empty() {
+                current_segment = new_attrs;
+            } else if standalone || current_standalone || Self::attributes_changed(&current_segment, &new_attrs) {
+                line.segments.push(std::mem::replace(&mut current_segment, new_attrs));
+            }
+            
+            current_segment.text.push_str(text);
+            current_segment.width += cell_width;
+            current_standalone = standalone;
+        }
+        
+        // 添加最后一个片段
+        if !current_segment.text.is_empty() {
+            line.segments.push(current_segment);
+        }
+        
+        line
+    }
+    
+    /// vt100颜色（默认色为None）；bright为true时基本色显示为对应的亮色
+    fn cell_color(color: vt100::Color, bright: bool) -> Option<CellColor> {
+        match color {
+            vt100::Color::Default => None,
+            vt100::Color::Idx(idx) if bright && idx < 8 => Some(CellColor::Indexed(idx + 8)),
+            vt100::Color::Idx(idx) => Some(CellColor::Indexed(idx)),
+            vt100::Color::Rgb(r, g, b) => Some(CellColor::Rgb(r, g, b)),
+        }
+    }
+    
+    /// 检查属性是否变化
+    fn attributes_changed(current: &TerminalSegment, new: &TerminalSegment) -> bool {
+        current.color != new.color
+            || current.background_color != new.background_color
+            || current.bold != new.bold
+            || current.italic != new.italic
+            || current.underline != new.underline
+            || current.inverse != new.inverse
+            || current.link != new.link
+    }
+}
+
+/// 把文本切成不超过max_len字节的块（按字符边界），DEC私有模式和向上滚动序列单独成块
+fn split_chunks(text: &str, max_len: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut separate = separate_sequences(text).peekable();
+    let mut start = 0;
+    while start < text.len() {
+        while separate.next_if(|&(index, _)| index < start).is_some() {}
+        let end = match separate.peek() {
+            Some(&(index, end)) if index == start => end,
+            Some(&(index, _)) => index.min(start + max_len),
+            None => (start + max_len).min(text.len()),
+        };
+        let mut end = end;
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        chunks.push(&text[start..end]);
+        start = end;
+    }
+    chunks
+}
+
+/// 需要单独成块的CSI序列的（起点，终点）：DEC私有模式和 `CSI Ps S`
+///
+/// 序列到结束字节（0x40-0x7E）为止；不完整的DEC私有模式序列到文本末尾，其余不完整的序列不单独成块。
+fn separate_sequences(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    text.match_indices(CSI).filter_map(|(index, _)| {
+        let params = index + CSI.len();
+        let rest = &text.as_bytes()[params..];
+        let private = rest.first() == Some(&b'?');
+        match rest.iter().position(|b| (0x40..=0x7e).contains(b)) {
+            Some(i) if private || rest[i] == b'S' => Some((index, params + i + 1)),
+            None if private => Some((index, text.len())),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::types::CursorShape;
+
+    fn numbered_lines(lines: std::ops::Range<usize>) -> String {
+        lines.map(|i| format!("line {}\r\n", i)).collect()
+    }
+
+    fn texts(lines: &[LineText]) -> Vec<String> {
+        lines.iter().map(|line| line.text.trim_end().to_string()).collect()
+    }
+
+    /// 5行屏幕、1000行历史，输出line 0..1100后：历史为96-1095，屏幕为1096-1100（光标在空的第1100行）
+    fn full_history(feed: impl Fn(&mut TerminalEmulator, &str)) -> TerminalEmulator {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        emulator.set_scrollback_limit(MIN_SCROLLBACK_LINES);
+        feed(&mut emulator, &numbered_lines(0..1100));
+        emulator
+    }
+
+    #[test]
+    fn line_numbers_stay_stable_after_history_is_full() {
+        let by_line = full_history(|emulator, text| {
+            for line in text.split_inclusive('\n') {
+                emulator.feed(line.as_bytes());
+            }
+        });
+        let at_once = full_history(|emulator, text| emulator.feed(text.as_bytes()));
+        for mut emulator in [by_line, at_once] {
+            assert_eq!(emulator.history_len(), 1000);
+            assert_eq!(emulator.first_line(), 96);
+            assert_eq!(emulator.top_line(), 1096);
+            assert_eq!(emulator.cursor_line(), 1100);
+            assert_eq!(texts(&emulator.lines_in(100..102)), ["line 100", "line 101"]);
+            // 已丢弃的行忽略
+            assert_eq!(texts(&emulator.lines_in(0..97)), ["line 96"]);
+            // 回滚到历史最早处
+            emulator.scroll_by(2000);
+            assert_eq!(emulator.scroll_offset(), 1000);
+            assert_eq!(row_text(&emulator, 0), "line 96");
+        }
+    }
+
+    #[test]
+    fn alternate_screen_does_not_disturb_line_numbers() {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        emulator.set_scrollback_limit(MIN_SCROLLBACK_LINES);
+        emulator.feed(numbered_lines(0..1040).as_bytes());
+        // 备用屏幕中的滚动不进入历史
+        emulator.feed(format!("\x1b[?1049h{}\x1b[?1049l", numbered_lines(0..30)).as_bytes());
+        emulator.feed(numbered_lines(1040..1050).as_bytes());
+        assert_eq!(emulator.first_line(), 46);
+        assert_eq!(emulator.cursor_line(), 1050);
+        assert_eq!(texts(&emulator.lines_in(1049..1050)), ["line 1049"]);
+    }
+
+    #[test]
+    fn scroll_up_sequence_is_counted() {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        emulator.set_scrollback_limit(MIN_SCROLLBACK_LINES);
+        emulator.feed(numbered_lines(0..1010).as_bytes());
+        let first = emulator.first_line();
+        // CSI S 一次滚出多行
+        emulator.feed(b"\x1b[4S");
+        assert_eq!(emulator.first_line(), first + 4);
+        assert_eq!(emulator.history_len(), 1000);
+        // 每行一个CSI S，一块内的序列合计滚出的行数超过历史上限
+        emulator.feed("\x1b[5S".repeat(300).as_bytes());
+        assert_eq!(emulator.first_line(), first + 4 + 1500);
+        assert_eq!(emulator.history_len(), 1000);
+    }
+
+    #[test]
+    fn scrollback_limit_has_floor() {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        emulator.set_scrollback_limit(10);
+        emulator.feed(numbered_lines(0..1100).as_bytes());
+        assert_eq!(emulator.history_len(), MIN_SCROLLBACK_LINES);
+        assert_eq!(emulator.first_line(), 96);
+    }
+
+    #[test]
+    fn split_chunks_keeps_private_modes_separate() {
+        let chunks = split_chunks("ab\x1b[?1049hcdef\x1b[?25", 3);
+        assert_eq!(chunks, ["ab", "\x1b[?1049h", "cde", "f", "\x1b[?25"]);
+        // 向上滚动序列单独成块，其他CSI序列不拆开
+        assert_eq!(split_chunks("a\x1b[2Sb\x1b[1m", 10), ["a", "\x1b[2S", "b\x1b[1m"]);
+        // 不在字符中间切开
+        assert_eq!(split_chunks("中文", 1), ["中", "文"]);
+    }
+
+    fn row_text(emulator: &TerminalEmulator, row: usize) -> String {
+        let segments = &emulator.screen()[row].segments;
+        segments.iter().map(|segment| segment.text.as_str()).collect::<String>().trim_end().to_string()
+    }
+
+    #[test]
+    fn screen_shows_fed_text() {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        emulator.feed(b"hello\r\n\x1b[31mred\x1b[0m world");
+        assert_eq!(emulator.screen().len(), 5);
+        assert_eq!(row_text(&emulator, 0), "hello");
+        assert_eq!(row_text(&emulator, 1), "red world");
+        let red = emulator.screen()[1].segments.iter().find(|segment| segment.text == "red").unwrap();
+        assert_eq!(red.color, Some(CellColor::Indexed(1)));
+        assert_eq!(emulator.cursor_position(), (1, 9));
+    }
+
+    #[test]
+    fn damage_reports_changed_rows_once() {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        assert_eq!(emulator.take_damage().rows, [0, 1, 2, 3, 4]);
+        assert!(emulator.take_damage().is_empty());
+
+        emulator.feed(b"\x1b[3;1Hx");
+        assert_eq!(emulator.take_damage(), Damage { rows: vec![2], cursor: true });
+        // 只移动光标
+        emulator.feed(b"\x1b[H");
+        assert_eq!(emulator.take_damage(), Damage { rows: Vec::new(), cursor: true });
+        // 内容不变的重绘不算变化
+        emulator.feed(b"\x1b[3;1Hx\x1b[H");
+        assert!(emulator.take_damage().is_empty());
+    }
+
+    #[test]
+    fn scroll_by_moves_view_within_history() {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        emulator.set_scrollback_limit(100);
+        emulator.feed(numbered_lines(0..20).as_bytes());
+        // 共21行（光标在空的第20行），屏幕5行
+        assert_eq!(emulator.history_len(), 16);
+        assert_eq!(emulator.first_line(), 0);
+        assert_eq!(emulator.top_line(), 16);
+
+        emulator.scroll_by(3);
+        assert_eq!(emulator.scroll_offset(), 3);
+        assert_eq!(emulator.top_line(), 13);
+        assert_eq!(row_text(&emulator, 0), "line 13");
+        emulator.scroll_by(100);
+        assert_eq!(emulator.scroll_offset(), 16);
+        assert_eq!(row_text(&emulator, 0), "line 0");
+        emulator.scroll_by(-100);
+        assert_eq!(emulator.scroll_offset(), 0);
+        assert_eq!(row_text(&emulator, 0), "line 16");
+    }
+
+    #[test]
+    fn lines_in_spans_history_and_screen() {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        emulator.set_scrollback_limit(100);
+        emulator.feed(numbered_lines(0..20).as_bytes());
+        emulator.scroll_by(2);
+        // 第14、15行在历史中，16、17行在屏幕上；读取不影响回滚位置
+        assert_eq!(texts(&emulator.lines_in(14..18)), ["line 14", "line 15", "line 16", "line 17"]);
+        assert_eq!(emulator.scroll_offset(), 2);
+        assert_eq!(texts(&emulator.lines_in(19..30)), ["line 19", ""]);
+        assert_eq!(emulator.searchable_lines().len(), 21);
+    }
+
+    #[test]
+    fn osc_split_across_feeds() {
+        let mut emulator = TerminalEmulator::new(40, 5);
+        emulator.feed(b"a\x1b]7;file://host/tmp/my%20d");
+        emulator.feed(b"ir\x07b\x1b]2;my ti");
+        emulator.feed(b"tle\x07");
+        let cwd = emulator.working_directory().unwrap();
+        assert_eq!((cwd.host.as_str(), cwd.path.as_str()), ("host", "/tmp/my dir"));
+        assert_eq!(emulator.title(), "my title");
+        assert_eq!(row_text(&emulator, 0), "ab");
+    }
+
+    #[test]
+    fn utf8_character_split_across_feeds() {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        emulator.feed(&[b'>', 0xE4, 0xB8]);
+        emulator.feed(&[0xAD, b'<']);
+        assert_eq!(row_text(&emulator, 0), ">中<");
+        assert_eq!(emulator.cursor_position(), (0, 4));
+    }
+
+    #[test]
+    fn shell_marks_build_command_blocks() {
+        let mut emulator = TerminalEmulator::new(40, 10);
+        emulator.feed(b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07file\r\n\x1b]133;D;0\x07");
+        let finished = emulator.take_finished_commands();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].command, "ls");
+        assert_eq!(finished[0].output, "file");
+        assert_eq!(finished[0].exit_code, Some(0));
+        assert!(emulator.take_finished_commands().is_empty());
+        assert_eq!(emulator.command_blocks().blocks().len(), 1);
+    }
+
+    #[test]
+    fn osc8_hyperlink_is_attached_to_segments() {
+        let mut emulator = TerminalEmulator::new(40, 5);
+        emulator.feed(b"\x1b]8;;http://x\x07link\x1b]8;;\x07 plain");
+        let segments = &emulator.screen()[0].segments;
+        let link = segments.iter().find(|segment| segment.text == "link").unwrap();
+        assert_eq!(link.link.as_deref(), Some("http://x"));
+        assert!(segments.iter().filter(|segment| segment.text.contains("plain")).all(|segment| segment.link.is_none()));
+    }
+
+    #[test]
+    fn bell_clipboard_and_modes() {
+        let mut emulator = TerminalEmulator::new(20, 5);
+        emulator.feed(b"\x07\x1b]52;c;aGk=\x07\x1b[5 q\x1b[?1h\x1b[?2004h\x1b[?1000h\x1b[?1006h");
+        assert!(emulator.take_bell());
+        assert!(!emulator.take_bell());
+        assert_eq!(emulator.take_clipboard_requests(), [ClipboardRequest::Set("hi".to_string())]);
+        let modes = emulator.modes();
+        assert!(modes.keys.application_cursor);
+        assert!(modes.bracketed_paste);
+        assert_eq!(modes.cursor_style, CursorStyle { shape: CursorShape::Bar, blinking: true });
+        assert_eq!(
+            modes.mouse,
+            Some(MouseReporting {
+                mode: vt100::MouseProtocolMode::PressRelease,
+                encoding: vt100::MouseProtocolEncoding::Sgr,
+            })
+        );
+    }
+}
@@ -0,0 +1,314 @@
+// 键盘编码 - 把按键转换为发往PTY的xterm转义序列
+
+/// 终端按键
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TermKey {
+    /// 可打印字符（通常只有带Ctrl/Alt时才需要编码，普通输入直接发送文本）
+    Char(char),
+    Enter,
+    Backspace,
+    Tab,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    /// 功能键 F1-F12
+    F(u8),
+    /// 小键盘按键，以其输入的字符标识：'0'-'9' '.' '+' '-' '*' '/' '='，回车为'\r'
+    Keypad(char),
+}
+
+/// 修饰键状态
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KeyModifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+}
+
+impl KeyModifiers {
+    /// xterm修饰参数：1 + Shift(1) + Alt(2) + Ctrl(4)，无修饰键时返回None
+    fn xterm_param(&self) -> Option<u8> {
+        let param = 1 + self.shift as u8 + self.alt as u8 * 2 + self.ctrl as u8 * 4;
+        (param > 1).then_some(param)
+    }
+}
+
+/// 远端程序设置的键盘模式（来自vt100屏幕状态）
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KeyModes {
+    pub application_cursor: bool, // DECCKM：方向键发送 SS3 而不是 CSI
+    pub application_keypad: bool, // DECKPAM：小键盘发送 SS3 序列而不是字符
+}
+
+/// ⌨️ 按键编码，无法编码的组合返回None
+pub fn encode_key(key: TermKey, mods: KeyModifiers, modes: KeyModes) -> Option<String> {
+    let sequence = match key {
+        TermKey::Char(ch) => return encode_char(ch, mods),
+        TermKey::Enter => alt_prefixed("\r", mods),
+        TermKey::Backspace => alt_prefixed(if mods.ctrl { "\x08" } else { "\x7f" }, mods),
+        TermKey::Tab if mods.shift => "\x1b[Z".to_string(),
+        TermKey::Tab => alt_prefixed("\t", mods),
+        TermKey::Escape => alt_prefixed("\x1b", mods),
+        TermKey::Up => cursor_key('A', mods, modes),
+        TermKey::Down => cursor_key('B', mods, modes),
+        TermKey::Right => cursor_key('C', mods, modes),
+        TermKey::Left => cursor_key('D', mods, modes),
+        TermKey::Home => cursor_key('H', mods, modes),
+        TermKey::End => cursor_key('F', mods, modes),
+        TermKey::Insert => tilde_key(2, mods),
+        TermKey::Delete => tilde_key(3, mods),
+        TermKey::PageUp => tilde_key(5, mods),
+        TermKey::PageDown => tilde_key(6, mods),
+        TermKey::F(n @ 1..=4) => {
+            let final_char = (b'P' + n - 1) as char;
+            match mods.xterm_param() {
+                Some(param) => format!("\x1b[1;{}{}", param, final_char),
+                None => format!("\x1bO{}", final_char),
+            }
+        }
+        TermKey::F(n @ 5..=12) => {
+            const CODES: [u8; 8] = [15, 17, 18, 19, 20, 21, 23, 24];
+            tilde_key(CODES[(n - 5) as usize], mods)
+        }
+        TermKey::F(_) => return None,
+        TermKey::Keypad(ch) => return encode_keypad(ch, mods, modes),
+    };
+    Some(sequence)
+}
+
+/// Alt作为Meta：在序列前加ESC
+fn alt_prefixed(sequence: &str, mods: KeyModifiers) -> String {
+    if mods.alt {
+        format!("\x1b{}", sequence)
+    } else {
+        sequence.to_string()
+    }
+}
+
+/// 方向键/Home/End：普通模式 `CSI x`，应用模式 `SS3 x`，带修饰键时 `CSI 1;m x`
+fn cursor_key(final_char: char, mods: KeyModifiers, modes: KeyModes) -> String {
+    match mods.xterm_param() {
+        Some(param) => format!("\x1b[1;{}{}", param, final_char),
+        None if modes.application_cursor => format!("\x1bO{}", final_char),
+        None => format!("\x1b[{}", final_char),
+    }
+}
+
+/// 编辑键/F5-F12：`CSI n ~`，带修饰键时 `CSI n;m ~`
+fn tilde_key(code: u8, mods: KeyModifiers) -> String {
+    match mods.xterm_param() {
+        Some(param) => format!("\x1b[{};{}~", code, param),
+        None => format!("\x1b[{}~", code),
+    }
+}
+
+/// Ctrl组合键映射为控制字符，Alt加ESC前缀；Ctrl+Shift+字母与Ctrl+字母相同
+fn encode_char(ch: char, mods: KeyModifiers) -> Option<String> {
+    let ch = if mods.ctrl {
+        control_char(ch)?
+    } else {
+        ch
+    };
+    Some(alt_prefixed(ch.encode_utf8(&mut [0; 4]), mods))
+}
+
+/// xterm的Ctrl映射：字母→0x01-0x1A，以及 @[\]^_ 和数字键上的同位符号
+fn control_char(ch: char) -> Option<char> {
+    let byte = match ch.to_ascii_lowercase() {
+        c @ 'a'..='z' => c as u8 - b'a' + 1,
+        '@' | ' ' | '2' => 0x00,
+        '[' | '3' => 0x1b,
+        '\\' | '4' => 0x1c,
+        ']' | '5' => 0x1d,
+        '^' | '6' => 0x1e,
+        '_' | '-' | '7' | '/' => 0x1f,
+        '8' | '?' => 0x7f,
+        _ => return None,
+    };
+    Some(byte as char)
+}
+
+/// 小键盘：应用模式下发送 `SS3 x`，否则发送对应字符
+fn encode_keypad(ch: char, mods: KeyModifiers, modes: KeyModes) -> Option<String> {
+    if !modes.application_keypad {
+        return match ch {
+            '\r' => Some(alt_prefixed("\r", mods)),
+            _ => encode_char(ch, mods),
+        };
+    }
+    let final_char = match ch {
+        '0'..='9' => (b'p' + (ch as u8 - b'0')) as char,
+        '\r' => 'M',
+        '*' => 'j',
+        '+' => 'k',
+        ',' => 'l',
+        '-' => 'm',
+        '.' => 'n',
+        '/' => 'o',
+        '=' => 'X',
+        _ => return None,
+    };
+    Some(match mods.xterm_param() {
+        Some(param) => format!("\x1bO{}{}", param, final_char),
+        None => format!("\x1bO{}", final_char),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONE: KeyModifiers = KeyModifiers { shift: false, alt: false, ctrl: false };
+    const SHIFT: KeyModifiers = KeyModifiers { shift: true, alt: false, ctrl: false };
+    const ALT: KeyModifiers = KeyModifiers { shift: false, alt: true, ctrl: false };
+    const CTRL: KeyModifiers = KeyModifiers { shift: false, alt: false, ctrl: true };
+    const NORMAL: KeyModes = KeyModes { application_cursor: false, application_keypad: false };
+    const APPLICATION: KeyModes = KeyModes { application_cursor: true, application_keypad: true };
+
+    fn encode(key: TermKey, mods: KeyModifiers, modes: KeyModes) -> Option<String> {
+        encode_key(key, mods, modes)
+    }
+
+    #[test]
+    fn ctrl_letters_map_to_control_codes() {
+        for (i, ch) in ('a'..='z').enumerate() {
+            let expected = ((i + 1) as u8 as char).to_string();
+            assert_eq!(encode(TermKey::Char(ch), CTRL, NORMAL), Some(expected.clone()));
+            // Ctrl+Shift+字母与Ctrl+字母相同
+            let ctrl_shift = KeyModifiers { shift: true, ..CTRL };
+            assert_eq!(encode(TermKey::Char(ch.to_ascii_uppercase()), ctrl_shift, NORMAL), Some(expected));
+        }
+    }
+
+    #[test]
+    fn ctrl_symbols_follow_xterm() {
+        let cases = [
+            (' ', "\x00"),
+            ('@', "\x00"),
+            ('2', "\x00"),
+            ('[', "\x1b"),
+            ('3', "\x1b"),
+            ('\\', "\x1c"),
+            (']', "\x1d"),
+            ('^', "\x1e"),
+            ('/', "\x1f"),
+            ('-', "\x1f"),
+            ('7', "\x1f"),
+            ('8', "\x7f"),
+        ];
+        for (ch, expected) in cases {
+            assert_eq!(encode(TermKey::Char(ch), CTRL, NORMAL).as_deref(), Some(expected), "Ctrl+{:?}", ch);
+        }
+        assert_eq!(encode(TermKey::Char('.'), CTRL, NORMAL), None);
+    }
+
+    #[test]
+    fn alt_sends_esc_prefix() {
+        assert_eq!(encode(TermKey::Char('x'), ALT, NORMAL).as_deref(), Some("\x1bx"));
+        assert_eq!(encode(TermKey::Char('中'), ALT, NORMAL).as_deref(), Some("\x1b中"));
+        let ctrl_alt = KeyModifiers { alt: true, ..CTRL };
+        assert_eq!(encode(TermKey::Char('c'), ctrl_alt, NORMAL).as_deref(), Some("\x1b\x03"));
+        assert_eq!(encode(TermKey::Enter, ALT, NORMAL).as_deref(), Some("\x1b\r"));
+        assert_eq!(encode(TermKey::Backspace, ALT, NORMAL).as_deref(), Some("\x1b\x7f"));
+    }
+
+    #[test]
+    fn plain_editing_keys() {
+        assert_eq!(encode(TermKey::Enter, NONE, NORMAL).as_deref(), Some("\r"));
+        assert_eq!(encode(TermKey::Backspace, NONE, NORMAL).as_deref(), Some("\x7f"));
+        assert_eq!(encode(TermKey::Backspace, CTRL, NORMAL).as_deref(), Some("\x08"));
+        assert_eq!(encode(TermKey::Tab, NONE, NORMAL).as_deref(), Some("\t"));
+        assert_eq!(encode(TermKey::Escape, NONE, NORMAL).as_deref(), Some("\x1b"));
+        assert_eq!(encode(TermKey::Insert, NONE, NORMAL).as_deref(), Some("\x1b[2~"));
+        assert_eq!(encode(TermKey::Delete, NONE, NORMAL).as_deref(), Some("\x1b[3~"));
+        assert_eq!(encode(TermKey::PageUp, NONE, NORMAL).as_deref(), Some("\x1b[5~"));
+        assert_eq!(encode(TermKey::PageDown, SHIFT, NORMAL).as_deref(), Some("\x1b[6;2~"));
+    }
+
+    #[test]
+    fn shift_tab_is_back_tab() {
+        assert_eq!(encode(TermKey::Tab, SHIFT, NORMAL).as_deref(), Some("\x1b[Z"));
+        assert_eq!(encode(TermKey::Tab, SHIFT, APPLICATION).as_deref(), Some("\x1b[Z"));
+    }
+
+    #[test]
+    fn arrows_follow_application_cursor_mode() {
+        let keys = [(TermKey::Up, 'A'), (TermKey::Down, 'B'), (TermKey::Right, 'C'), (TermKey::Left, 'D')];
+        for (key, final_char) in keys {
+            assert_eq!(encode(key, NONE, NORMAL), Some(format!("\x1b[{}", final_char)));
+            assert_eq!(encode(key, NONE, APPLICATION), Some(format!("\x1bO{}", final_char)));
+        }
+        assert_eq!(encode(TermKey::Home, NONE, NORMAL).as_deref(), Some("\x1b[H"));
+        assert_eq!(encode(TermKey::End, NONE, APPLICATION).as_deref(), Some("\x1bOF"));
+    }
+
+    #[test]
+    fn modified_arrows_use_csi_1_m() {
+        let ctrl_shift = KeyModifiers { shift: true, ..CTRL };
+        let all = KeyModifiers { shift: true, alt: true, ctrl: true };
+        assert_eq!(encode(TermKey::Up, SHIFT, NORMAL).as_deref(), Some("\x1b[1;2A"));
+        assert_eq!(encode(TermKey::Down, ALT, NORMAL).as_deref(), Some("\x1b[1;3B"));
+        assert_eq!(encode(TermKey::Right, CTRL, NORMAL).as_deref(), Some("\x1b[1;5C"));
+        assert_eq!(encode(TermKey::Left, ctrl_shift, NORMAL).as_deref(), Some("\x1b[1;6D"));
+        assert_eq!(encode(TermKey::Home, all, NORMAL).as_deref(), Some("\x1b[1;8H"));
+        // 带修饰键时应用模式同样使用CSI
+        assert_eq!(encode(TermKey::Up, CTRL, APPLICATION).as_deref(), Some("\x1b[1;5A"));
+    }
+
+    #[test]
+    fn function_keys_f1_to_f4() {
+        for (n, final_char) in [(1, 'P'), (2, 'Q'), (3, 'R'), (4, 'S')] {
+            assert_eq!(encode(TermKey::F(n), NONE, NORMAL), Some(format!("\x1bO{}", final_char)));
+            assert_eq!(encode(TermKey::F(n), SHIFT, NORMAL), Some(format!("\x1b[1;2{}", final_char)));
+            assert_eq!(encode(TermKey::F(n), CTRL, NORMAL), Some(format!("\x1b[1;5{}", final_char)));
+        }
+    }
+
+    #[test]
+    fn function_keys_f5_to_f12_use_tilde_codes() {
+        let codes = [(5, 15), (6, 17), (7, 18), (8, 19), (9, 20), (10, 21), (11, 23), (12, 24)];
+        for (n, code) in codes {
+            assert_eq!(encode(TermKey::F(n), NONE, NORMAL), Some(format!("\x1b[{}~", code)));
+            assert_eq!(encode(TermKey::F(n), ALT, NORMAL), Some(format!("\x1b[{};3~", code)));
+        }
+        assert_eq!(encode(TermKey::F(0), NONE, NORMAL), None);
+        assert_eq!(encode(TermKey::F(13), NONE, NORMAL), None);
+    }
+
+    #[test]
+    fn keypad_in_normal_mode_sends_characters() {
+        for ch in ['0', '5', '9', '.', '+', '-', '*', '/'] {
+            assert_eq!(encode(TermKey::Keypad(ch), NONE, NORMAL), Some(ch.to_string()));
+        }
+        assert_eq!(encode(TermKey::Keypad('\r'), NONE, NORMAL).as_deref(), Some("\r"));
+        assert_eq!(encode(TermKey::Keypad('1'), ALT, NORMAL).as_deref(), Some("\x1b1"));
+    }
+
+    #[test]
+    fn keypad_in_application_mode_sends_ss3() {
+        let cases = [
+            ('0', "\x1bOp"),
+            ('9', "\x1bOy"),
+            ('\r', "\x1bOM"),
+            ('*', "\x1bOj"),
+            ('+', "\x1bOk"),
+            ('-', "\x1bOm"),
+            ('.', "\x1bOn"),
+            ('/', "\x1bOo"),
+            ('=', "\x1bOX"),
+        ];
+        for (ch, expected) in cases {
+            assert_eq!(encode(TermKey::Keypad(ch), NONE, APPLICATION).as_deref(), Some(expected), "{:?}", ch);
+        }
+        assert_eq!(encode(TermKey::Keypad('5'), SHIFT, APPLICATION).as_deref(), Some("\x1bO2u"));
+        assert_eq!(encode(TermKey::Keypad('x'), NONE, APPLICATION), None);
+    }
+}
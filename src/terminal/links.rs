@@ -0,0 +1,309 @@
+// 链接 - OSC 8超链接，以及从终端文本中识别的URL、IP地址和“路径:行:列”
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::blocks::LinePos;
+use super::search::LineText;
+use super::shell_integration::WorkingDirectory;
+
+/// 最多保留的OSC 8超链接数
+const MAX_HYPERLINKS: usize = 2000;
+
+/// http(s)/ftp/file URL，到空白或引号为止
+static URL_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\b(?:https?|ftp|file)://[^\s<>"'`]+"#).expect("URL正则"));
+/// 编译器、grep等输出的 "路径:行[:列]"：带目录的路径，或带扩展名的文件名
+static PATH_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"((?:~|\.{1,2})?/?(?:[\w.\-+@]+/)+[\w.\-+@]+|[\w\-+@.]*\.[A-Za-z]\w*):(\d+)(?::(\d+))?")
+        .expect("路径正则")
+});
+/// IPv4地址，可带端口
+static IP_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)(?::\d{1,5})?\b").expect("IP正则")
+});
+
+/// 🔗 链接目标
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    /// 在浏览器中打开
+    Url(String),
+    /// 远端文件，在文件浏览器中打开
+    Path { path: String, line: Option<u32>, column: Option<u32> },
+}
+
+impl LinkTarget {
+    /// OSC 8的URI：file:// 为远端路径，其余交给浏览器
+    pub fn from_uri(uri: &str) -> Self {
+        match WorkingDirectory::from_file_uri(uri) {
+            Some(location) => Self::Path { path: location.path, line: None, column: None },
+            None => Self::Url(uri.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for LinkTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Url(url) => write!(f, "{}", url),
+            Self::Path { path, line, column } => {
+                write!(f, "{}", path)?;
+                if let Some(line) = line {
+                    write!(f, ":{}", line)?;
+                }
+                if let Some(column) = column {
+                    write!(f, ":{}", column)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// OSC 8 标记 "8;参数;URI"
+#[derive(Debug, Clone, PartialEq)]
+pub enum HyperlinkMark {
+    Open(String),
+    Close, // URI为空
+}
+
+impl HyperlinkMark {
+    pub fn parse(osc: &str) -> Option<Self> {
+        let (_params, uri) = osc.strip_prefix("8;")?.split_once(';')?;
+        Some(if uri.is_empty() { Self::Close } else { Self::Open(uri.to_string()) })
+    }
+}
+
+/// 一个OSC 8超链接覆盖的区间 [start, end)，text为链接结束时区间内的文本
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hyperlink {
+    pub start: LinePos,
+    pub end: LinePos,
+    pub uri: String,
+    pub text: String,
+}
+
+/// 超链接列表，行号与命令块相同
+#[derive(Debug, Default)]
+pub struct Hyperlinks {
+    links: Vec<Hyperlink>,
+    open: Option<(LinePos, String)>, // 已开始、还没收到结束标记的链接
+}
+
+impl Hyperlinks {
+    /// 未结束链接的起点
+    pub fn open_start(&self) -> Option<LinePos> {
+        self.open.as_ref().map(|(start, _)| *start)
+    }
+
+    pub fn open(&mut self, start: LinePos, uri: String) {
+        self.open = Some((start, uri));
+    }
+
+    /// 结束当前链接（没有覆盖任何字符时丢弃）
+    pub fn close(&mut self, end: LinePos, text: String) {
+        let Some((start, uri)) = self.open.take() else {
+            return;
+        };
+        if end <= start || text.trim().is_empty() {
+            return;
+        }
+        self.links.push(Hyperlink { start, end, uri, text });
+        if self.links.len() > MAX_HYPERLINKS {
+            self.links.remove(0);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.links.clear();
+        self.open = None;
+    }
+
+    /// 与行区间相交的链接
+    pub fn overlapping(&self, lines: std::ops::Range<usize>) -> impl Iterator<Item = &Hyperlink> {
+        self.links
+            .iter()
+            .filter(move |link| link.start.0 < lines.end && link.end > (lines.start, 0))
+    }
+}
+
+/// 识别出的链接及其所在的列区间 (行, 起始列, 结束列)，行为传入的lines下标
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedLink {
+    pub target: LinkTarget,
+    pub spans: Vec<(usize, u16, u16)>,
+}
+
+/// 🔗 第line行第col列处的URL/路径/IP；自动换行的几行拼接后再识别，长URL折行也能完整打开
+pub fn detect_link(lines: &[LineText], line: usize, col: u16) -> Option<DetectedLink> {
+    lines.get(line)?;
+    let mut first = line;
+    while first > 0 && lines[first - 1].wrapped {
+        first -= 1;
+    }
+    let mut last = line;
+    while last + 1 < lines.len() && lines[last].wrapped {
+        last += 1;
+    }
+    let mut text = String::new();
+    let mut offsets = Vec::with_capacity(last - first + 1);
+    for line_text in &lines[first..=last] {
+        offsets.push(text.len());
+        text.push_str(&line_text.text);
+    }
+
+    for (start, end, target) in find_links(&text) {
+        let spans: Vec<_> = (first..=last)
+            .filter_map(|index| {
+                let base = offsets[index - first];
+                let (from, to) = (start.max(base), end.min(base + lines[index].text.len()));
+                if from >= to {
+                    return None;
+                }
+                let (start_col, end_col) = lines[index].columns(from - base, to - base)?;
+                Some((index, start_col, end_col))
+            })
+            .collect();
+        if spans.iter().any(|&(index, start_col, end_col)| index == line && (start_col..end_col).contains(&col)) {
+            return Some(DetectedLink { target, spans });
+        }
+    }
+    None
+}
+
+/// 文本中的所有链接（字节区间），重叠时URL优先，其次是路径、IP
+pub fn find_links(text: &str) -> Vec<(usize, usize, LinkTarget)> {
+    let mut links: Vec<(usize, usize, LinkTarget)> = Vec::new();
+    let mut add = |start: usize, end: usize, target: LinkTarget| {
+        if !links.iter().any(|(s, e, _)| start < *e && *s < end) {
+            links.push((start, end, target));
+        }
+    };
+
+    for found in URL_PATTERN.find_iter(text) {
+        let url = trim_url(found.as_str());
+        add(found.start(), found.start() + url.len(), LinkTarget::from_uri(url));
+    }
+    for captures in PATH_PATTERN.captures_iter(text) {
+        let (Some(whole), Some(path)) = (captures.get(0), captures.get(1)) else {
+            continue;
+        };
+        let number = |index: usize| captures.get(index).and_then(|m| m.as_str().parse().ok());
+        let target = LinkTarget::Path {
+            path: path.as_str().to_string(),
+            line: number(2),
+            column: number(3),
+        };
+        add(whole.start(), whole.end(), target);
+    }
+    for found in IP_PATTERN.find_iter(text) {
+        // 版本号等更长的数字序列中的一段不算
+        let rest = &text[found.end()..];
+        let continues = rest.starts_with('.') && rest[1..].starts_with(|c: char| c.is_ascii_digit());
+        if text[..found.start()].ends_with('.') || continues {
+            continue;
+        }
+        add(found.start(), found.end(), LinkTarget::Url(format!("http://{}", found.as_str())));
+    }
+
+    links.sort_by_key(|(start, _, _)| *start);
+    links
+}
+
+/// 去掉URL末尾的标点和不成对的右括号（句末的句号、Markdown的括号等）
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        let unbalanced = [('(', ')'), ('[', ']'), ('{', '}')]
+            .into_iter()
+            .find(|&(open, close)| trimmed.ends_with(close) && trimmed.matches(close).count() > trimmed.matches(open).count());
+        url = match unbalanced {
+            Some(_) => &trimmed[..trimmed.len() - 1],
+            None => return trimmed,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> LinkTarget {
+        LinkTarget::Url(url.to_string())
+    }
+
+    fn path(path: &str, line: Option<u32>, column: Option<u32>) -> LinkTarget {
+        LinkTarget::Path { path: path.to_string(), line, column }
+    }
+
+    fn targets(text: &str) -> Vec<LinkTarget> {
+        find_links(text).into_iter().map(|(_, _, target)| target).collect()
+    }
+
+    #[test]
+    fn finds_urls_without_trailing_punctuation() {
+        assert_eq!(targets("see https://example.com/a."), [url("https://example.com/a")]);
+        assert_eq!(targets("(https://x.org/a_(b))"), [url("https://x.org/a_(b)")]);
+        assert_eq!(targets("[doc](https://x.org/d)"), [url("https://x.org/d")]);
+    }
+
+    #[test]
+    fn finds_paths_with_line_and_column() {
+        assert_eq!(targets("src/main.rs:12:5: error"), [path("src/main.rs", Some(12), Some(5))]);
+        assert_eq!(targets("at main.rs:3"), [path("main.rs", Some(3), None)]);
+        // URL优先于其中的路径
+        assert_eq!(targets("https://host/file.rs:10"), [url("https://host/file.rs:10")]);
+    }
+
+    #[test]
+    fn finds_ip_addresses_but_not_version_numbers() {
+        assert_eq!(targets("ping 10.0.0.1:22 ok"), [url("http://10.0.0.1:22")]);
+        assert_eq!(targets("version 1.2.3.4.5"), []);
+    }
+
+    #[test]
+    fn file_uri_is_a_remote_path() {
+        let target = LinkTarget::from_uri("file://host/tmp/a%20b");
+        assert_eq!(target, path("/tmp/a b", None, None));
+        assert_eq!(LinkTarget::from_uri("https://x.org"), url("https://x.org"));
+        assert_eq!(path("a.rs", Some(3), Some(4)).to_string(), "a.rs:3:4");
+    }
+
+    #[test]
+    fn detects_link_across_wrapped_lines() {
+        let lines = vec![LineText::from_text("xx https://exa", true), LineText::from_text("mple.com/p yy", false)];
+        let link = detect_link(&lines, 1, 2).unwrap();
+        assert_eq!(link.target, url("https://example.com/p"));
+        assert_eq!(link.spans, [(0, 3, 14), (1, 0, 10)]);
+        assert_eq!(detect_link(&lines, 1, 11), None);
+        assert_eq!(detect_link(&lines, 5, 0), None);
+    }
+
+    #[test]
+    fn parses_hyperlink_marks() {
+        assert_eq!(HyperlinkMark::parse("8;;http://x"), Some(HyperlinkMark::Open("http://x".to_string())));
+        assert_eq!(HyperlinkMark::parse("8;id=1;u"), Some(HyperlinkMark::Open("u".to_string())));
+        assert_eq!(HyperlinkMark::parse("8;;"), Some(HyperlinkMark::Close));
+        assert_eq!(HyperlinkMark::parse("7;x"), None);
+    }
+
+    #[test]
+    fn hyperlinks_keep_closed_links_with_text() {
+        let mut links = Hyperlinks::default();
+        links.open((2, 0), "http://a".to_string());
+        assert_eq!(links.open_start(), Some((2, 0)));
+        links.close((2, 4), "link".to_string());
+        assert_eq!(links.open_start(), None);
+        // 没有文本的链接丢弃
+        links.open((3, 0), "http://b".to_string());
+        links.close((3, 0), String::new());
+        // 没有开始的链接忽略
+        links.close((4, 0), "x".to_string());
+
+        assert_eq!(links.overlapping(0..10).map(|link| link.uri.as_str()).collect::<Vec<_>>(), ["http://a"]);
+        assert_eq!(links.overlapping(3..10).count(), 0);
+        assert_eq!(links.overlapping(0..2).count(), 0);
+    }
+}
@@ -0,0 +1,19 @@
+// 终端核心 - 不依赖egui的终端模拟器：输入字节，读取屏幕、变化的行、模式和标题，界面只负责绘制
+
+pub mod blocks;
+pub mod clipboard;
+pub mod decoder;
+pub mod emulator;
+pub mod keys;
+pub mod links;
+pub mod mouse;
+pub mod osc;
+pub mod search;
+pub mod selection;
+pub mod shell_integration;
+pub mod types;
+
+// 重新导出公共接口
+pub use decoder::TerminalEncoding;
+pub use emulator::TerminalEmulator;
+pub use types::CellColor;
@@ -0,0 +1,183 @@
+// 鼠标报告 - 按远端程序请求的模式和编码生成xterm鼠标序列
+
+use super::keys::KeyModifiers;
+use vt100::{MouseProtocolEncoding, MouseProtocolMode};
+
+/// 鼠标按键
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    WheelUp,
+    WheelDown,
+}
+
+/// 鼠标事件类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseEventKind {
+    Press(MouseButton),
+    Release(MouseButton),
+    /// 移动，参数为当前按住的按键（没有按键时为None）
+    Motion(Option<MouseButton>),
+}
+
+/// 一次鼠标事件，行列从0开始
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    pub row: u16,
+    pub col: u16,
+    pub mods: KeyModifiers,
+}
+
+/// 远端程序请求的鼠标报告模式（来自vt100屏幕状态）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MouseReporting {
+    pub mode: MouseProtocolMode,         // 报告哪些事件：按下/释放/拖动/所有移动
+    pub encoding: MouseProtocolEncoding, // 序列格式：X10/UTF-8/SGR
+}
+
+/// 🖱️ 鼠标事件编码，当前模式不需要报告该事件或坐标超出编码范围时返回None
+pub fn encode_mouse(event: MouseEvent, reporting: MouseReporting) -> Option<Vec<u8>> {
+    let MouseReporting { mode, encoding } = reporting;
+    let reported = match event.kind {
+        MouseEventKind::Press(_) => mode != MouseProtocolMode::None,
+        // 滚轮没有释放事件
+        MouseEventKind::Release(MouseButton::WheelUp | MouseButton::WheelDown) => false,
+        MouseEventKind::Release(_) => matches!(
+            mode,
+            MouseProtocolMode::PressRelease | MouseProtocolMode::ButtonMotion | MouseProtocolMode::AnyMotion
+        ),
+        MouseEventKind::Motion(Some(_)) => {
+            matches!(mode, MouseProtocolMode::ButtonMotion | MouseProtocolMode::AnyMotion)
+        }
+        MouseEventKind::Motion(None) => mode == MouseProtocolMode::AnyMotion,
+    };
+    if !reported {
+        return None;
+    }
+
+    let mut code = match event.kind {
+        MouseEventKind::Press(button) => button_code(button),
+        // 非SGR编码无法区分释放的是哪个按键，统一用3
+        MouseEventKind::Release(button) if encoding == MouseProtocolEncoding::Sgr => button_code(button),
+        MouseEventKind::Release(_) => 3,
+        MouseEventKind::Motion(button) => button.map_or(3, button_code) + 32,
+    };
+    // X10兼容模式（只报告按下）不带修饰键
+    if mode != MouseProtocolMode::Press {
+        code += event.mods.shift as u32 * 4 + event.mods.alt as u32 * 8 + event.mods.ctrl as u32 * 16;
+    }
+    let col = event.col as u32 + 1;
+    let row = event.row as u32 + 1;
+
+    match encoding {
+        MouseProtocolEncoding::Sgr => {
+            let final_char = if matches!(event.kind, MouseEventKind::Release(_)) { 'm' } else { 'M' };
+            Some(format!("\x1b[<{};{};{}{}", code, col, row, final_char).into_bytes())
+        }
+        MouseProtocolEncoding::Utf8 => {
+            let mut sequence = String::from("\x1b[M");
+            for value in [code, col, row] {
+                // UTF-8扩展编码最大支持到2015
+                sequence.push(char::from_u32(value + 32).filter(|_| value + 32 <= 2047)?);
+            }
+            Some(sequence.into_bytes())
+        }
+        MouseProtocolEncoding::Default => {
+            let mut sequence = b"\x1b[M".to_vec();
+            for value in [code, col, row] {
+                sequence.push(u8::try_from(value + 32).ok()?);
+            }
+            Some(sequence)
+        }
+    }
+}
+
+/// 按键编号：左中右为0/1/2，滚轮上下为64/65
+fn button_code(button: MouseButton) -> u32 {
+    match button {
+        MouseButton::Left => 0,
+        MouseButton::Middle => 1,
+        MouseButton::Right => 2,
+        MouseButton::WheelUp => 64,
+        MouseButton::WheelDown => 65,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: MouseEventKind, row: u16, col: u16) -> MouseEvent {
+        MouseEvent { kind, row, col, mods: KeyModifiers::default() }
+    }
+
+    fn reporting(mode: MouseProtocolMode, encoding: MouseProtocolEncoding) -> MouseReporting {
+        MouseReporting { mode, encoding }
+    }
+
+    fn sgr(event: MouseEvent, mode: MouseProtocolMode) -> Option<String> {
+        encode_mouse(event, reporting(mode, MouseProtocolEncoding::Sgr)).map(|bytes| String::from_utf8(bytes).unwrap())
+    }
+
+    #[test]
+    fn sgr_reports_press_and_release_with_button() {
+        let mode = MouseProtocolMode::PressRelease;
+        assert_eq!(sgr(event(MouseEventKind::Press(MouseButton::Left), 0, 0), mode).as_deref(), Some("\x1b[<0;1;1M"));
+        assert_eq!(sgr(event(MouseEventKind::Release(MouseButton::Right), 4, 9), mode).as_deref(), Some("\x1b[<2;10;5m"));
+    }
+
+    #[test]
+    fn default_encoding_uses_offset_bytes() {
+        let reporting = reporting(MouseProtocolMode::PressRelease, MouseProtocolEncoding::Default);
+        let press = event(MouseEventKind::Press(MouseButton::Middle), 2, 3);
+        assert_eq!(encode_mouse(press, reporting), Some(b"\x1b[M\x21\x24\x23".to_vec()));
+        // 非SGR编码释放统一为3
+        let release = event(MouseEventKind::Release(MouseButton::Middle), 2, 3);
+        assert_eq!(encode_mouse(release, reporting), Some(b"\x1b[M\x23\x24\x23".to_vec()));
+        // 超出一个字节的坐标无法编码
+        assert_eq!(encode_mouse(event(MouseEventKind::Press(MouseButton::Left), 0, 222), reporting), Some(b"\x1b[M\x20\xff\x21".to_vec()));
+        assert_eq!(encode_mouse(event(MouseEventKind::Press(MouseButton::Left), 0, 223), reporting), None);
+    }
+
+    #[test]
+    fn utf8_encoding_extends_coordinates() {
+        let reporting = reporting(MouseProtocolMode::PressRelease, MouseProtocolEncoding::Utf8);
+        let press = event(MouseEventKind::Press(MouseButton::Left), 0, 300);
+        assert_eq!(encode_mouse(press, reporting), Some("\x1b[M \u{14d}!".as_bytes().to_vec()));
+        assert_eq!(encode_mouse(event(MouseEventKind::Press(MouseButton::Left), 0, 2100), reporting), None);
+    }
+
+    #[test]
+    fn modifiers_are_added_except_in_x10_mode() {
+        let mut press = event(MouseEventKind::Press(MouseButton::Left), 0, 0);
+        press.mods = KeyModifiers { shift: true, alt: false, ctrl: true };
+        assert_eq!(sgr(press, MouseProtocolMode::PressRelease).as_deref(), Some("\x1b[<20;1;1M"));
+        assert_eq!(sgr(press, MouseProtocolMode::Press).as_deref(), Some("\x1b[<0;1;1M"));
+    }
+
+    #[test]
+    fn mode_decides_which_events_are_reported() {
+        let release = event(MouseEventKind::Release(MouseButton::Left), 0, 0);
+        let drag = event(MouseEventKind::Motion(Some(MouseButton::Left)), 0, 0);
+        let hover = event(MouseEventKind::Motion(None), 0, 0);
+        let press = event(MouseEventKind::Press(MouseButton::Left), 0, 0);
+
+        assert_eq!(sgr(press, MouseProtocolMode::None), None);
+        assert_eq!(sgr(release, MouseProtocolMode::Press), None);
+        assert_eq!(sgr(drag, MouseProtocolMode::PressRelease), None);
+        assert_eq!(sgr(drag, MouseProtocolMode::ButtonMotion).as_deref(), Some("\x1b[<32;1;1M"));
+        assert_eq!(sgr(hover, MouseProtocolMode::ButtonMotion), None);
+        assert_eq!(sgr(hover, MouseProtocolMode::AnyMotion).as_deref(), Some("\x1b[<35;1;1M"));
+    }
+
+    #[test]
+    fn wheel_has_no_release() {
+        let mode = MouseProtocolMode::PressRelease;
+        assert_eq!(sgr(event(MouseEventKind::Press(MouseButton::WheelUp), 0, 0), mode).as_deref(), Some("\x1b[<64;1;1M"));
+        assert_eq!(sgr(event(MouseEventKind::Press(MouseButton::WheelDown), 0, 0), mode).as_deref(), Some("\x1b[<65;1;1M"));
+        assert_eq!(sgr(event(MouseEventKind::Release(MouseButton::WheelUp), 0, 0), mode), None);
+    }
+}
@@ -0,0 +1,138 @@
+// OSC序列拆分 - vt100只处理标题（OSC 0/1/2），其余需要的OSC序列由我们从数据流中取出
+
+/// 数据流片段
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscPiece {
+    /// 交给vt100的普通数据
+    Text(String),
+    /// 取出的OSC序列内容（不含 `ESC ]` 和结束符），如 "133;D;0"
+    Osc(String),
+}
+
+/// 增量拆分器：只取出指定编号的OSC序列，被批次截断的序列留到下一批再处理
+#[derive(Debug)]
+pub struct OscSplitter {
+    codes: &'static [&'static str],
+    pending: String,
+}
+
+impl OscSplitter {
+    pub fn new(codes: &'static [&'static str]) -> Self {
+        Self {
+            codes,
+            pending: String::new(),
+        }
+    }
+
+    /// 🔖 按顺序拆分为普通数据和OSC序列
+    pub fn split(&mut self, data: &str) -> Vec<OscPiece> {
+        let data = std::mem::take(&mut self.pending) + data;
+        let mut pieces = Vec::new();
+        let mut text_start = 0;
+        let mut search = 0;
+
+        while let Some(found) = data[search..].find('\x1b') {
+            let start = search + found;
+            let rest = &data[start + 1..];
+            if rest.is_empty() {
+                // 末尾单独的ESC可能是OSC的开头
+                self.pending = data[start..].to_string();
+                break;
+            }
+            let Some(body) = rest.strip_prefix(']') else {
+                search = start + 1;
+                continue;
+            };
+
+            let code_len = body.find(|c: char| !c.is_ascii_digit()).unwrap_or(body.len());
+            let code = &body[..code_len];
+            let wanted = |code: &str| self.codes.contains(&code);
+            // OSC以BEL或ST（ESC \）结束，vte遇到其他ESC也会结束OSC
+            let Some(end) = body.find(['\x07', '\x1b']) else {
+                // 序列不完整：需要的（或编号还没收全的）序列等下一批
+                if code_len == body.len() || wanted(code) {
+                    self.pending = data[start..].to_string();
+                }
+                break;
+            };
+            let terminator_len = match &body[end..] {
+                "\x1b" => {
+                    self.pending = data[start..].to_string();
+                    break;
+                }
+                s if s.starts_with("\x1b\\") => 2,
+                s if s.starts_with('\x07') => 1,
+                _ => 0,
+            };
+            let sequence_end = start + 2 + end + terminator_len;
+
+            if wanted(code) && (code_len == end || body[code_len..].starts_with(';')) {
+                if start > text_start {
+                    pieces.push(OscPiece::Text(data[text_start..start].to_string()));
+                }
+                pieces.push(OscPiece::Osc(body[..end].to_string()));
+                text_start = sequence_end;
+            }
+            search = sequence_end.max(start + 1);
+        }
+
+        let text_end = data.len() - self.pending.len();
+        if text_end > text_start {
+            pieces.push(OscPiece::Text(data[text_start..text_end].to_string()));
+        }
+        pieces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn splitter() -> OscSplitter {
+        OscSplitter::new(&["7", "133"])
+    }
+
+    fn text(text: &str) -> OscPiece {
+        OscPiece::Text(text.to_string())
+    }
+
+    fn osc(osc: &str) -> OscPiece {
+        OscPiece::Osc(osc.to_string())
+    }
+
+    #[test]
+    fn extracts_wanted_sequences() {
+        let mut splitter = splitter();
+        assert_eq!(splitter.split("hello"), [text("hello")]);
+        assert_eq!(
+            splitter.split("a\x1b]133;A\x07b\x1b]7;file://h/tmp\x1b\\c"),
+            [text("a"), osc("133;A"), text("b"), osc("7;file://h/tmp"), text("c")]
+        );
+    }
+
+    #[test]
+    fn other_sequences_pass_through() {
+        let mut splitter = splitter();
+        // 标题交给vt100；1337只是以133开头
+        let data = "\x1b]0;title\x07\x1b]1337;x\x07\x1b[1mbold";
+        assert_eq!(splitter.split(data), [text(data)]);
+    }
+
+    #[test]
+    fn sequence_split_across_batches_waits_for_rest() {
+        let mut splitter = splitter();
+        assert_eq!(splitter.split("ab\x1b]13"), [text("ab")]);
+        assert_eq!(splitter.split("3;D;0\x07cd"), [osc("133;D;0"), text("cd")]);
+
+        assert_eq!(splitter.split("x\x1b"), [text("x")]);
+        assert_eq!(splitter.split("]133;A\x1b"), []);
+        assert_eq!(splitter.split("\\y"), [osc("133;A"), text("y")]);
+    }
+
+    #[test]
+    fn incomplete_unwanted_sequence_is_not_held_back() {
+        let mut splitter = splitter();
+        assert_eq!(splitter.split("\x1b]0;tit"), [text("\x1b]0;tit")]);
+        assert_eq!(splitter.split("le\x07"), [text("le\x07")]);
+    }
+}
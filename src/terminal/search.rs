@@ -0,0 +1,337 @@
+// 终端搜索 - 在回滚历史和当前屏幕中查找文本
+
+use regex::{Regex, RegexBuilder};
+
+/// 一行的纯文本，以及每个字符所在的列（宽字符占两列）
+#[derive(Debug, Clone, Default)]
+pub struct LineText {
+    pub text: String,
+    pub wrapped: bool, // 该行因自动换行延续到下一行
+    cells: Vec<(usize, u16, u16)>, // (字节偏移, 起始列, 宽度)
+}
+
+impl LineText {
+    pub fn new(wrapped: bool) -> Self {
+        Self {
+            wrapped,
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, contents: &str, col: u16, width: u16) {
+        self.cells.push((self.text.len(), col, width));
+        self.text.push_str(contents);
+    }
+
+    /// 列区间 [start_col, end_col) 内的文本
+    pub fn slice(&self, start_col: u16, end_col: u16) -> &str {
+        let start = self.cells.iter().find(|(_, col, _)| *col >= start_col).map_or(self.text.len(), |c| c.0);
+        let end = self.cells.iter().find(|(_, col, _)| *col >= end_col).map_or(self.text.len(), |c| c.0);
+        &self.text[start..end.max(start)]
+    }
+
+    /// 包含col的单词的列区间 [start, end)；不在单词上时只选中该单元格
+    pub fn word_bounds(&self, col: u16) -> (u16, u16) {
+        let is_word = |offset: usize| {
+            self.text[offset..]
+                .chars()
+                .next()
+                .is_some_and(|ch| !ch.is_whitespace() && !"\"'`()[]{}<>|;,".contains(ch))
+        };
+        let Some(index) = self.cells.iter().position(|(_, start, width)| col < start + width) else {
+            return (col, col + 1);
+        };
+        let (_, cell_col, cell_width) = self.cells[index];
+        if !is_word(self.cells[index].0) {
+            return (cell_col, cell_col + cell_width);
+        }
+        let first = self.cells[..index].iter().rposition(|c| !is_word(c.0)).map_or(0, |i| i + 1);
+        let last = self.cells[index..].iter().position(|c| !is_word(c.0)).map_or(self.cells.len(), |i| index + i) - 1;
+        (self.cells[first].1, self.cells[last].1 + self.cells[last].2)
+    }
+
+    /// 字节区间 → 列区间 [start, end)
+    pub(super) fn columns(&self, start: usize, end: usize) -> Option<(u16, u16)> {
+        let first = self.cells.iter().rev().find(|(offset, _, _)| *offset <= start)?;
+        let last = self.cells.iter().rev().find(|(offset, _, _)| *offset < end)?;
+        Some((first.1, last.1 + last.2))
+    }
+}
+
+/// 搜索选项
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case_sensitive: bool,
+}
+
+/// 一处匹配，line为行号（见 `TerminalEmulator::first_line`）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchMatch {
+    pub line: usize,
+    pub start_col: u16,
+    pub end_col: u16,
+}
+
+/// 编译查询；非正则模式下按字面量匹配
+pub fn build_pattern(query: &str, options: SearchOptions) -> Result<Regex, regex::Error> {
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+}
+
+/// 🔍 查找所有匹配（忽略空匹配），lines的第一项为第first_line行
+pub fn find_matches(lines: &[LineText], first_line: usize, pattern: &Regex) -> Vec<SearchMatch> {
+    let mut matches = Vec::new();
+    for (line, text) in (first_line..).zip(lines) {
+        for found in pattern.find_iter(&text.text) {
+            if found.is_empty() {
+                continue;
+            }
+            if let Some((start_col, end_col)) = text.columns(found.start(), found.end()) {
+                matches.push(SearchMatch { line, start_col, end_col });
+            }
+        }
+    }
+    matches
+}
+
+/// 🔍 搜索状态：查询、匹配结果和当前选中的匹配
+#[derive(Debug, Default)]
+pub struct TerminalSearch {
+    pub open: bool,
+    pub query: String,
+    pub options: SearchOptions,
+    pub matches: Vec<SearchMatch>,
+    pub current: Option<usize>,
+    pub error: Option<String>, // 正则表达式无效
+    pattern: Option<Regex>,
+    scanned: Option<(usize, bool)>, // 已搜索到的行号（之前的行已滚入回滚历史，不会再变化）和当时是否在备用屏幕
+}
+
+impl TerminalSearch {
+    pub fn current_match(&self) -> Option<SearchMatch> {
+        self.current.and_then(|index| self.matches.get(index)).copied()
+    }
+
+    /// 增量搜索的起始行号；从未搜索过或切换了屏幕、需要全部重新搜索时返回None
+    pub fn rescan_from(&self, first_line: usize, alternate: bool) -> Option<usize> {
+        let (stable_until, scanned_alternate) = self.scanned?;
+        (scanned_alternate == alternate).then_some(stable_until.max(first_line))
+    }
+
+    /// 下次更新时全部重新搜索（搜索栏关闭期间的输出没有搜索过）
+    pub fn invalidate(&mut self) {
+        self.scanned = None;
+    }
+
+    /// 重新编译查询并全部重新搜索。尽量停留在原来的匹配处，首次搜索选中最靠近底部的匹配
+    ///
+    /// lines的第一项为第first_line行；stable_until之前的行已滚入回滚历史，之后的更新不再搜索它们。
+    pub fn refresh(&mut self, lines: &[LineText], first_line: usize, stable_until: usize, alternate: bool) {
+        self.error = None;
+        self.pattern = None;
+        if !self.query.is_empty() {
+            match build_pattern(&self.query, self.options) {
+                Ok(pattern) => self.pattern = Some(pattern),
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        }
+        self.update(lines, first_line, first_line, stable_until, alternate);
+    }
+
+    /// 🔍 有新输出时只重新搜索第from行起的lines（from见 `rescan_from`），之前未丢弃的匹配保留
+    pub fn update(&mut self, lines: &[LineText], from: usize, first_line: usize, stable_until: usize, alternate: bool) {
+        let previous = self.current_match();
+        self.matches.retain(|m| (first_line..from).contains(&m.line));
+        if let Some(pattern) = &self.pattern {
+            self.matches.extend(find_matches(lines, from, pattern));
+        }
+        self.scanned = Some((stable_until, alternate));
+
+        let last = self.matches.len().checked_sub(1);
+        self.current = match previous {
+            Some(previous) => self
+                .matches
+                .iter()
+                .position(|m| (m.line, m.start_col) >= (previous.line, previous.start_col))
+                .or(last),
+            None => last,
+        };
+    }
+
+    /// 跳到下一个（forward）或上一个匹配，首尾循环
+    pub fn step(&mut self, forward: bool) {
+        let count = self.matches.len();
+        if count == 0 {
+            return;
+        }
+        self.current = Some(match self.current {
+            Some(index) if forward => (index + 1) % count,
+            Some(index) => (index + count - 1) % count,
+            None if forward => 0,
+            None => count - 1,
+        });
+    }
+}
+
+#[cfg(test)]
+impl LineText {
+    /// 测试用：按字符依次占用单元格，CJK等宽字符占两列
+    pub(super) fn from_text(text: &str, wrapped: bool) -> Self {
+        let mut line = Self::new(wrapped);
+        let mut col = 0;
+        for ch in text.chars() {
+            let width = if ch >= '\u{1100}' { 2 } else { 1 };
+            line.push(ch.encode_utf8(&mut [0; 4]), col, width);
+            col += width;
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(texts: &[&str]) -> Vec<LineText> {
+        texts.iter().map(|text| LineText::from_text(text, false)).collect()
+    }
+
+    fn new_search(query: &str) -> TerminalSearch {
+        TerminalSearch {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn found_lines(search: &TerminalSearch) -> Vec<usize> {
+        search.matches.iter().map(|m| m.line).collect()
+    }
+
+    #[test]
+    fn update_rescans_only_lines_that_can_change() {
+        let mut search = new_search("foo");
+        // 第0行已在回滚历史中，第1-2行在屏幕上
+        search.refresh(&lines(&["foo 0", "bar", "foo 2"]), 0, 1, false);
+        assert_eq!(found_lines(&search), [0, 2]);
+        assert_eq!(search.rescan_from(0, false), Some(1));
+
+        // 新输出：屏幕上的行变化并滚动了一行
+        let screen = lines(&["foo bar", "x", "foo 3"]);
+        search.update(&screen, 1, 0, 2, false);
+        assert_eq!(found_lines(&search), [0, 1, 3]);
+
+        // 与全部重新搜索的结果相同
+        let mut full = new_search("foo");
+        full.refresh(&lines(&["foo 0", "foo bar", "x", "foo 3"]), 0, 2, false);
+        assert_eq!(full.matches, search.matches);
+    }
+
+    #[test]
+    fn update_drops_matches_of_discarded_lines() {
+        let mut search = new_search("foo");
+        search.refresh(&lines(&["foo 0", "foo 1", "bar"]), 10, 12, false);
+        assert_eq!(found_lines(&search), [10, 11]);
+        // 第10行已从历史中丢弃
+        let from = search.rescan_from(11, false).unwrap();
+        search.update(&lines(&["bar", "foo 13"]), from, 11, 13, false);
+        assert_eq!(found_lines(&search), [11, 13]);
+    }
+
+    #[test]
+    fn switching_screens_requires_full_rescan() {
+        let mut search = new_search("foo");
+        search.refresh(&lines(&["foo"]), 0, 0, false);
+        assert_eq!(search.rescan_from(0, true), None);
+        search.invalidate();
+        assert_eq!(search.rescan_from(0, false), None);
+    }
+
+    #[test]
+    fn literal_query_escapes_regex_syntax() {
+        let pattern = build_pattern("a.b", SearchOptions::default()).unwrap();
+        assert!(pattern.is_match("xa.by"));
+        assert!(!pattern.is_match("axb"));
+    }
+
+    #[test]
+    fn case_sensitivity_follows_option() {
+        let insensitive = build_pattern("Error", SearchOptions::default()).unwrap();
+        assert!(insensitive.is_match("ERROR: failed"));
+        let sensitive = build_pattern("Error", SearchOptions { case_sensitive: true, regex: false }).unwrap();
+        assert!(!sensitive.is_match("ERROR: failed"));
+    }
+
+    #[test]
+    fn invalid_regex_reports_error() {
+        let mut search = TerminalSearch {
+            query: "(".to_string(),
+            options: SearchOptions { regex: true, case_sensitive: false },
+            ..Default::default()
+        };
+        search.refresh(&lines(&["("]), 0, 0, false);
+        assert!(search.error.is_some());
+        assert!(search.matches.is_empty());
+        assert_eq!(search.current, None);
+    }
+
+    #[test]
+    fn matches_report_cell_columns() {
+        let pattern = build_pattern("abc", SearchOptions::default()).unwrap();
+        // 宽字符占两列
+        let matches = find_matches(&lines(&["中文abc abc"]), 7, &pattern);
+        assert_eq!(
+            matches,
+            [
+                SearchMatch { line: 7, start_col: 4, end_col: 7 },
+                SearchMatch { line: 7, start_col: 8, end_col: 11 },
+            ]
+        );
+        // 空匹配忽略
+        let empty = build_pattern("x*", SearchOptions { regex: true, case_sensitive: false }).unwrap();
+        assert!(find_matches(&lines(&["abc"]), 0, &empty).is_empty());
+    }
+
+    #[test]
+    fn first_search_selects_bottom_match_and_steps_wrap() {
+        let mut search = new_search("foo");
+        search.refresh(&lines(&["foo", "foo", "foo"]), 0, 0, false);
+        assert_eq!(search.current, Some(2));
+        search.step(true);
+        assert_eq!(search.current, Some(0));
+        search.step(false);
+        assert_eq!(search.current, Some(2));
+        search.step(false);
+        assert_eq!(search.current, Some(1));
+    }
+
+    #[test]
+    fn refresh_keeps_current_match() {
+        let mut search = new_search("foo");
+        search.refresh(&lines(&["foo", "foo", "foo"]), 0, 0, false);
+        search.step(true);
+        search.step(true);
+        assert_eq!(search.current_match().map(|m| m.line), Some(1));
+        search.refresh(&lines(&["foo", "foo", "foo", "foo"]), 0, 0, false);
+        assert_eq!(search.current_match().map(|m| m.line), Some(1));
+    }
+
+    #[test]
+    fn line_text_slices_and_word_bounds() {
+        let line = LineText::from_text("cd 中文/dir (x)", false);
+        assert_eq!(line.slice(0, 2), "cd");
+        assert_eq!(line.slice(3, 7), "中文");
+        // 单词到空白和括号为止，宽字符也算单词的一部分
+        assert_eq!(line.word_bounds(4), (3, 11));
+        assert_eq!(line.word_bounds(13), (13, 14));
+        // 不在单词上时只选中该单元格
+        assert_eq!(line.word_bounds(2), (2, 3));
+        assert_eq!(line.word_bounds(30), (30, 31));
+    }
+}
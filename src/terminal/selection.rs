@@ -0,0 +1,172 @@
+// 文本选择 - 坐标为（行号，列），行号与搜索相同，见 `TerminalEmulator::first_line`
+
+use super::search::LineText;
+
+/// 选择方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectionMode {
+    Normal,
+    Word,  // 双击：按单词扩展
+    Line,  // 三击：按整行扩展
+    Block, // Alt+拖动：矩形区域
+}
+
+/// 选区位置（行，列）
+pub type CellPos = (usize, u16);
+
+/// 📋 终端文本选区。按单词/整行选择时，起点和终点都是已扩展好的区间，列区间为 [start, end)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Selection {
+    pub mode: SelectionMode,
+    anchor: (CellPos, CellPos), // 按下鼠标处的区间，拖动时保持选中
+    head: (CellPos, CellPos),   // 鼠标当前所在的区间
+}
+
+impl Selection {
+    /// 以区间 [start, end) 开始选择
+    pub fn new(mode: SelectionMode, start: CellPos, end: CellPos) -> Self {
+        Self {
+            mode,
+            anchor: (start, end),
+            head: (start, end),
+        }
+    }
+
+    /// 拖动到新的区间
+    pub fn extend(&mut self, start: CellPos, end: CellPos) {
+        self.head = (start, end);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let (start, end) = self.bounds();
+        match self.mode {
+            SelectionMode::Block => start.1 >= end.1,
+            _ => start >= end,
+        }
+    }
+
+    /// 选区的起点和终点。Block模式下为矩形的左上角和右下角（行为闭区间，列为 [start, end)）
+    fn bounds(&self) -> (CellPos, CellPos) {
+        let (anchor_start, anchor_end) = self.anchor;
+        let (head_start, head_end) = self.head;
+        if self.mode == SelectionMode::Block {
+            let top = anchor_start.0.min(head_start.0);
+            let bottom = anchor_start.0.max(head_start.0);
+            let left = anchor_start.1.min(head_start.1);
+            let right = anchor_end.1.max(head_end.1);
+            return ((top, left), (bottom, right));
+        }
+        // 向前拖动时从单词/行的开头选起，向后拖动时选到单词/行的末尾
+        (anchor_start.min(head_start), anchor_end.max(head_end))
+    }
+
+    /// 选区涉及的行号区间
+    pub fn lines(&self) -> std::ops::Range<usize> {
+        let (start, end) = self.bounds();
+        start.0..end.0 + 1
+    }
+
+    /// 第line行被选中的列区间 [start, end)
+    pub fn columns(&self, line: usize, cols: u16) -> Option<(u16, u16)> {
+        let (start, end) = self.bounds();
+        if self.mode == SelectionMode::Block {
+            return (start.0..=end.0).contains(&line).then_some((start.1, end.1.min(cols)));
+        }
+        // 终点列为0时表示选到上一行末尾
+        if line < start.0 || line > end.0 || (line == end.0 && end.1 == 0 && line != start.0) {
+            return None;
+        }
+        let from = if line == start.0 { start.1 } else { 0 };
+        let to = if line == end.0 { end.1.min(cols) } else { cols };
+        (from < to).then_some((from, to))
+    }
+
+    /// 选中的文本，lines的第一项为第first_line行：每行去掉行尾空白；自动换行的行直接拼接，其余用换行分隔
+    pub fn text(&self, lines: &[LineText], first_line: usize, cols: u16) -> String {
+        let (start, end) = self.bounds();
+        let mut text = String::new();
+        let lines = (first_line..).zip(lines).skip_while(|(line, _)| *line < start.0);
+        for (line, line_text) in lines.take_while(|(line, _)| *line <= end.0) {
+            let Some((from, to)) = self.columns(line, cols) else {
+                continue;
+            };
+            let reaches_end = to >= cols;
+            let piece = line_text.slice(from, to);
+            // 自动换行的行在行尾没有真正的换行符，行尾空格也是内容的一部分
+            if self.mode != SelectionMode::Block && reaches_end && line_text.wrapped && line < end.0 {
+                text.push_str(piece);
+            } else {
+                text.push_str(piece.trim_end());
+                if line < end.0 {
+                    text.push('\n');
+                }
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(texts: &[&str]) -> Vec<LineText> {
+        texts.iter().map(|text| LineText::from_text(text, false)).collect()
+    }
+
+    #[test]
+    fn normal_selection_trims_trailing_spaces() {
+        let lines = lines(&["abc   ", "def", "ghi"]);
+        let selection = Selection::new(SelectionMode::Normal, (0, 1), (1, 2));
+        assert_eq!(selection.text(&lines, 0, 80), "bc\nde");
+        assert_eq!(selection.lines(), 0..2);
+        assert_eq!(selection.columns(0, 80), Some((1, 80)));
+        assert_eq!(selection.columns(1, 80), Some((0, 2)));
+        assert_eq!(selection.columns(2, 80), None);
+    }
+
+    #[test]
+    fn end_at_column_zero_stops_at_previous_line() {
+        let lines = lines(&["hello", "world"]);
+        let selection = Selection::new(SelectionMode::Normal, (0, 2), (1, 0));
+        assert_eq!(selection.columns(1, 80), None);
+        assert_eq!(selection.text(&lines, 0, 80), "llo\n");
+        assert!(Selection::new(SelectionMode::Normal, (3, 4), (3, 4)).is_empty());
+    }
+
+    #[test]
+    fn wrapped_lines_are_joined() {
+        let lines = vec![LineText::from_text("abcd", true), LineText::from_text("ef", false)];
+        let selection = Selection::new(SelectionMode::Normal, (0, 0), (1, 2));
+        assert_eq!(selection.text(&lines, 0, 4), "abcdef");
+    }
+
+    #[test]
+    fn line_numbers_start_at_first_line() {
+        let lines = lines(&["zero", "one", "two"]);
+        let selection = Selection::new(SelectionMode::Line, (101, 0), (102, 80));
+        assert_eq!(selection.text(&lines, 100, 80), "one\ntwo");
+        // 已淘汰的行不在lines中
+        let selection = Selection::new(SelectionMode::Normal, (98, 0), (100, 4));
+        assert_eq!(selection.text(&lines, 100, 80), "zero");
+    }
+
+    #[test]
+    fn block_selection_is_rectangular() {
+        let lines = lines(&["abcdef", "ghijkl", "mnopqr"]);
+        let mut selection = Selection::new(SelectionMode::Block, (1, 3), (1, 4));
+        selection.extend((0, 1), (0, 2));
+        assert_eq!(selection.lines(), 0..2);
+        assert_eq!(selection.text(&lines, 0, 80), "bcd\nhij");
+    }
+
+    #[test]
+    fn word_selection_dragged_backwards_keeps_anchor_word() {
+        let lines = lines(&["one two three"]);
+        let mut selection = Selection::new(SelectionMode::Word, (0, 4), (0, 7));
+        selection.extend((0, 0), (0, 3));
+        assert_eq!(selection.text(&lines, 0, 80), "one two");
+        selection.extend((0, 8), (0, 13));
+        assert_eq!(selection.text(&lines, 0, 80), "two three");
+    }
+}
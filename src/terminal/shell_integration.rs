@@ -0,0 +1,94 @@
+// Shell集成 - 可注入远端shell的集成脚本（OSC 133命令块标记、OSC 7工作目录），以及OSC 7的解析
+
+/// bash集成脚本：每行一条完整语句，可以追加到 ~/.bashrc，也可以拼成一行注入当前会话。
+/// DEBUG trap在PROMPT_COMMAND执行时也会触发，因此只在PROMPT_COMMAND全部执行完后才开始等待命令
+pub const BASH_INTEGRATION: &str = r#"__ay_osc133_precmd() { local ec=$?; [ -n "$__ay_osc133_running" ] && printf '\033]133;D;%s\007' "$ec"; __ay_osc133_running=; printf '\033]133;A\007'; }
+__ay_osc133_arm() { __ay_osc133_armed=1; }
+__ay_osc133_preexec() { [ -n "$__ay_osc133_armed" ] || return; __ay_osc133_armed=; case "$BASH_COMMAND" in __ay_osc*) return;; esac; __ay_osc133_running=1; printf '\033]133;C\007'; }
+trap '__ay_osc133_preexec' DEBUG
+[[ "$PROMPT_COMMAND" == *__ay_osc133_precmd* ]] || PROMPT_COMMAND="__ay_osc133_precmd${PROMPT_COMMAND:+; ${PROMPT_COMMAND%;}}; __ay_osc133_arm"
+[[ "$PS1" == *'133;B'* ]] || PS1="$PS1"'\[\033]133;B\007\]'"#;
+
+/// zsh集成脚本，格式同 `BASH_INTEGRATION`
+pub const ZSH_INTEGRATION: &str = r#"__ay_osc133_precmd() { local ec=$?; [[ -n "$__ay_osc133_running" ]] && printf '\033]133;D;%s\007' "$ec"; __ay_osc133_running=; printf '\033]133;A\007'; }
+__ay_osc133_preexec() { __ay_osc133_running=1; printf '\033]133;C\007'; }
+(( ${precmd_functions[(I)__ay_osc133_precmd]} )) || precmd_functions=(__ay_osc133_precmd $precmd_functions)
+(( ${preexec_functions[(I)__ay_osc133_preexec]} )) || preexec_functions+=(__ay_osc133_preexec)
+[[ "$PS1" == *'133;B'* ]] || PS1="$PS1"$'%{\e]133;B\a%}'"#;
+
+/// 目录跟踪脚本（bash/zsh通用）：每次显示提示符时用OSC 7报告当前目录，保留上一条命令的退出码
+pub const CWD_HOOK: &str = r#"__ay_osc7() { local ec=$?; printf '\033]7;file://%s%s\007' "${HOSTNAME:-$HOST}" "${PWD//%/%25}"; return $ec; }
+if [ -n "$ZSH_VERSION" ]; then (( ${precmd_functions[(I)__ay_osc7]} )) || precmd_functions=(__ay_osc7 $precmd_functions); else [[ "$PROMPT_COMMAND" == *__ay_osc7* ]] || PROMPT_COMMAND="__ay_osc7${PROMPT_COMMAND:+; $PROMPT_COMMAND}"; fi"#;
+
+/// 把集成脚本拼成一条命令注入当前会话；开头的空格让它不进入shell历史（HISTCONTROL=ignorespace）
+pub fn injection_command(script: &str) -> String {
+    format!(" {}\r", script.lines().collect::<Vec<_>>().join("; "))
+}
+
+/// OSC 7 报告的工作目录
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkingDirectory {
+    pub host: String,
+    pub path: String,
+}
+
+impl WorkingDirectory {
+    /// 解析OSC内容 "7;file://host/path"，路径为百分号编码
+    pub fn from_osc7(osc: &str) -> Option<Self> {
+        Self::from_file_uri(osc.strip_prefix("7;")?)
+    }
+
+    /// 解析 "file://host/path"（OSC 8超链接中的文件也是这种格式）
+    pub fn from_file_uri(uri: &str) -> Option<Self> {
+        let rest = uri.strip_prefix("file://")?;
+        let slash = rest.find('/')?;
+        Some(Self {
+            host: rest[..slash].to_string(),
+            path: percent_decode(&rest[slash..]),
+        })
+    }
+}
+
+/// 百分号解码，无效的转义原样保留
+fn percent_decode(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut i = 0;
+    while i < text.len() {
+        let byte = text.as_bytes()[i];
+        if byte == b'%'
+            && let Some(decoded) = text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            bytes.push(decoded);
+            i += 3;
+        } else {
+            bytes.push(byte);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_osc7_working_directory() {
+        let cwd = WorkingDirectory::from_osc7("7;file://myhost/home/me/a%20dir").unwrap();
+        assert_eq!(cwd.host, "myhost");
+        assert_eq!(cwd.path, "/home/me/a dir");
+        assert_eq!(WorkingDirectory::from_osc7("7;http://x/"), None);
+        assert_eq!(WorkingDirectory::from_osc7("133;A"), None);
+    }
+
+    #[test]
+    fn percent_decode_keeps_invalid_escapes() {
+        assert_eq!(percent_decode("%E4%B8%AD%zz%4"), "中%zz%4");
+    }
+
+    #[test]
+    fn injection_is_a_single_hidden_command() {
+        let command = injection_command("a\nb");
+        assert_eq!(command, " a; b\r");
+    }
+}
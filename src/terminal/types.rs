@@ -0,0 +1,75 @@
+/// 🎨 单元格颜色：调色板下标（0-15由配色方案决定）或真彩色，默认色为None
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellColor {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// 终端输出的格式化片段（同一行内属性相同的连续单元格）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TerminalSegment {
+    pub text: String,
+    pub col: u16,   // 起始列
+    pub width: u16, // 占用的单元格数（宽字符占2格）
+    pub color: Option<CellColor>,
+    pub background_color: Option<CellColor>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub inverse: bool,
+    pub link: Option<String>, // OSC 8超链接的URI
+}
+
+/// 终端行，包含多个格式化片段
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerminalLine {
+    pub segments: Vec<TerminalSegment>,
+}
+
+impl TerminalLine {
+    pub fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+        }
+    }
+}
+
+/// 🖱️ 光标形状（DECSCUSR）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CursorShape {
+    Block,
+    Underline,
+    Bar,
+}
+
+/// 光标样式：形状 + 是否闪烁
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorStyle {
+    pub shape: CursorShape,
+    pub blinking: bool,
+}
+
+impl Default for CursorStyle {
+    fn default() -> Self {
+        Self {
+            shape: CursorShape::Block,
+            blinking: true,
+        }
+    }
+}
+
+impl CursorStyle {
+    /// 按 `CSI Ps SP q` 的参数解析，未知参数返回None
+    pub fn from_decscusr(param: u16) -> Option<Self> {
+        let (shape, blinking) = match param {
+            0 | 1 => (CursorShape::Block, true),
+            2 => (CursorShape::Block, false),
+            3 => (CursorShape::Underline, true),
+            4 => (CursorShape::Underline, false),
+            5 => (CursorShape::Bar, true),
+            6 => (CursorShape::Bar, false),
+            _ => return None,
+        };
+        Some(Self { shape, blinking })
+    }
+}
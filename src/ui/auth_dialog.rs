@@ -0,0 +1,127 @@
+use crate::ssh::auth_prompt::{AuthPrompt, PassphraseReply};
+use eframe::egui;
+
+/// 🔑 认证对话框 - 显示私钥口令和keyboard-interactive提示
+pub struct AuthDialog {
+    prompt: Option<AuthPrompt>,
+    answers: Vec<String>,
+    remember: bool,
+    focus: Option<usize>, // 下一帧获得焦点的输入框，只在打开时和按回车时设置，不能每帧抢焦点
+}
+
+impl AuthDialog {
+    pub fn new() -> Self {
+        Self {
+            prompt: None,
+            answers: Vec::new(),
+            remember: false,
+            focus: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    /// 打开新的提示（连接线程正在等待回答）
+    pub fn open(&mut self, prompt: AuthPrompt) {
+        self.answers = match &prompt {
+            AuthPrompt::Passphrase { .. } => vec![String::new()],
+            AuthPrompt::KeyboardInteractive { prompts, .. } => vec![String::new(); prompts.len()],
+        };
+        self.prompt = Some(prompt);
+        self.focus = Some(0);
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, id: &str) {
+        let Some(prompt) = &self.prompt else {
+            return;
+        };
+
+        let mut submitted = false;
+        let mut cancelled = false;
+
+        let title = match prompt {
+            AuthPrompt::Passphrase { .. } => "🔑 输入私钥口令",
+            AuthPrompt::KeyboardInteractive { .. } => "🔑 服务器验证",
+        };
+
+        egui::Window::new(title)
+            .id(egui::Id::new(("auth_dialog", id)))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                match prompt {
+                    AuthPrompt::Passphrase { key_file, retry, .. } => {
+                        ui.label(format!("私钥 {} 已加密，请输入口令:", key_file));
+                        if *retry {
+                            ui.colored_label(egui::Color32::RED, "口令错误，请重试");
+                        }
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut self.answers[0]).password(true),
+                        );
+                        if self.focus.take().is_some() {
+                            response.request_focus();
+                        }
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            submitted = true;
+                        }
+                        ui.checkbox(&mut self.remember, "本次运行期间记住口令");
+                    }
+                    AuthPrompt::KeyboardInteractive { username, instructions, prompts, .. } => {
+                        if !username.is_empty() {
+                            ui.label(format!("用户: {}", username));
+                        }
+                        if !instructions.is_empty() {
+                            ui.label(instructions);
+                        }
+                        for (i, (text, echo)) in prompts.iter().enumerate() {
+                            ui.label(text);
+                            let response = ui.add(
+                                egui::TextEdit::singleline(&mut self.answers[i]).password(!echo),
+                            );
+                            if self.focus == Some(i) {
+                                response.request_focus();
+                                self.focus = None;
+                            }
+                            // 回车跳到下一个输入框，最后一个输入框回车提交
+                            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                if i + 1 == prompts.len() {
+                                    submitted = true;
+                                } else {
+                                    self.focus = Some(i + 1);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("✅ 确定").clicked() {
+                        submitted = true;
+                    }
+                    if ui.button("❌ 取消").clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if submitted || cancelled {
+            let answers = std::mem::take(&mut self.answers);
+            match self.prompt.take() {
+                Some(AuthPrompt::Passphrase { reply, .. }) => {
+                    let _ = reply.send(submitted.then(|| PassphraseReply {
+                        passphrase: answers.into_iter().next().unwrap_or_default(),
+                        remember: self.remember,
+                    }));
+                }
+                Some(AuthPrompt::KeyboardInteractive { reply, .. }) => {
+                    let _ = reply.send(submitted.then_some(answers));
+                }
+                None => {}
+            }
+        }
+    }
+}
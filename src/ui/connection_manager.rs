@@ -1,12 +1,19 @@
 use crate::config::AppConfig;
-use crate::ui::{AuthType, ConnectionConfig};
+use crate::ssh::known_hosts::{self, StoredHostKey};
+use crate::ui::port_forward_panel;
+use crate::ui::terminal::theme::ThemeLibrary;
+use crate::ui::{AuthType, ClipboardPolicy, ConnectionConfig, ForwardRule, TerminalEncoding};
 use eframe::egui;
 use egui_phosphor::regular;
+use std::collections::HashMap;
 
 pub struct ConnectionManager {
     show_add_dialog: bool,
     edit_connection: Option<ConnectionConfig>,
     selected_connection: Option<usize>,
+    // 主机指纹缓存，避免每帧读取known_hosts文件；信任/重置指纹或发起连接时失效
+    fingerprint_cache: HashMap<(String, u16), Vec<StoredHostKey>>,
+    fingerprint_cache_generation: u64,
 }
 
 impl ConnectionManager {
@@ -15,10 +22,25 @@ impl ConnectionManager {
             show_add_dialog: false,
             edit_connection: None,
             selected_connection: None,
+            fingerprint_cache: HashMap::new(),
+            fingerprint_cache_generation: known_hosts::generation(),
         }
     }
 
-    pub fn show(&mut self, ui: &mut egui::Ui, config: &mut AppConfig) -> Option<ConnectionConfig> {
+    /// 获取主机已保存的指纹（应用信任或重置指纹后自动刷新缓存）
+    fn stored_fingerprints(&mut self, host: &str, port: u16) -> &[StoredHostKey] {
+        let generation = known_hosts::generation();
+        if generation != self.fingerprint_cache_generation {
+            self.fingerprint_cache.clear();
+            self.fingerprint_cache_generation = generation;
+        }
+
+        self.fingerprint_cache
+            .entry((host.to_string(), port))
+            .or_insert_with(|| known_hosts::stored_fingerprints(host, port))
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, config: &mut AppConfig, themes: &ThemeLibrary) -> Option<ConnectionConfig> {
         let mut connection_to_establish = None;
         ui.heading("快速连接");
 
@@ -45,8 +67,13 @@ impl ConnectionManager {
         egui::ScrollArea::vertical().show(ui, |ui| {
             let mut to_remove = None;
             let mut to_connect = None;
+            let mut to_reset_fingerprint = None;
 
             for (i, connection) in config.connections.iter().enumerate() {
+                let fingerprints = self
+                    .stored_fingerprints(&connection.host, connection.port)
+                    .to_vec();
+
                 ui.group(|ui| {
                     ui.horizontal(|ui| {
                         ui.vertical(|ui| {
@@ -58,6 +85,42 @@ impl ConnectionManager {
                             if !connection.description.is_empty() {
                                 ui.small(connection.description.clone());
                             }
+
+                            // 🔀 端口转发
+                            if !connection.forwards.is_empty() {
+                                ui.small(format!(
+                                    "{} {}",
+                                    regular::SHUFFLE,
+                                    connection
+                                        .forwards
+                                        .iter()
+                                        .map(|rule| rule.summary())
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                ));
+                            }
+
+                            // 🔗 跳板机链
+                            if !connection.jump_hosts.is_empty() {
+                                let via = match config.resolve_jump_chain(connection) {
+                                    Ok(chain) => connection.chain_label(&chain),
+                                    Err(e) => e.to_string(),
+                                };
+                                ui.small(format!("{} 经由 {}", regular::PATH, via));
+                            }
+
+                            // 🔐 已保存的主机指纹
+                            if fingerprints.is_empty() {
+                                ui.small(format!("{} 未记录主机指纹", regular::SHIELD));
+                            }
+                            for stored in &fingerprints {
+                                ui.small(format!(
+                                    "{} {} ({})",
+                                    regular::SHIELD_CHECK,
+                                    stored.fingerprint,
+                                    stored.source
+                                ));
+                            }
                         });
 
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -70,6 +133,26 @@ impl ConnectionManager {
                             {
                                 to_remove = Some(i);
                             }
+                            if ui
+                                .add_enabled(
+                                    fingerprints
+                                        .iter()
+                                        .any(|f| f.source == known_hosts::SOURCE_APP),
+                                    egui::Button::new(
+                                        egui::RichText::new(format!(
+                                            "{} 重置指纹",
+                                            regular::SHIELD_SLASH
+                                        ))
+                                        .size(14.0),
+                                    ),
+                                )
+                                .on_hover_text(
+                                    "删除应用保存的主机指纹，下次连接时重新确认\n~/.ssh/known_hosts中的记录不受影响，请用 ssh-keygen -R 删除",
+                                )
+                                .clicked()
+                            {
+                                to_reset_fingerprint = Some(i);
+                            }
                             if ui
                                 .button(
                                     egui::RichText::new(format!("{} 编辑", regular::PENCIL_SIMPLE))
@@ -101,20 +184,30 @@ impl ConnectionManager {
                 config.connections.remove(index);
             }
 
+            // 处理指纹重置
+            if let Some(index) = to_reset_fingerprint {
+                let connection = &config.connections[index];
+                if let Err(e) = known_hosts::reset(&connection.host, connection.port) {
+                    log::error!("重置主机指纹失败: {}", e);
+                }
+            }
+
             // 处理连接
             if let Some(index) = to_connect {
                 log::info!("Connecting to: {:?}", config.connections[index]);
+                // 用户可能在应用外修改了~/.ssh/known_hosts，连接时重新读取
+                self.fingerprint_cache.clear();
                 connection_to_establish = Some(config.connections[index].clone());
             }
         });
 
         // 添加/编辑对话框
-        self.show_add_edit_dialog(ui, config);
+        self.show_add_edit_dialog(ui, config, themes);
 
         connection_to_establish
     }
 
-    fn show_add_edit_dialog(&mut self, ui: &mut egui::Ui, config: &mut AppConfig) {
+    fn show_add_edit_dialog(&mut self, ui: &mut egui::Ui, config: &mut AppConfig, themes: &ThemeLibrary) {
         if self.show_add_dialog {
             let mut connection = if let Some(connection) = self.edit_connection.take() {
                 connection
@@ -151,21 +244,16 @@ impl ConnectionManager {
 
                             ui.label("认证类型:");
                             egui::ComboBox::from_label("")
-                                .selected_text(match connection.auth_type {
-                                    AuthType::Password => "密码",
-                                    AuthType::PublicKey => "公钥",
-                                })
+                                .selected_text(connection.auth_type.label())
                                 .show_ui(ui, |ui| {
-                                    ui.selectable_value(
-                                        &mut connection.auth_type,
-                                        AuthType::Password,
-                                        "密码",
-                                    );
-                                    ui.selectable_value(
-                                        &mut connection.auth_type,
-                                        AuthType::PublicKey,
-                                        "公钥",
-                                    );
+                                    for auth_type in AuthType::ALL {
+                                        let label = auth_type.label();
+                                        ui.selectable_value(
+                                            &mut connection.auth_type,
+                                            auth_type,
+                                            label,
+                                        );
+                                    }
                                 });
                             ui.end_row();
 
@@ -207,8 +295,89 @@ impl ConnectionManager {
                                     connection.key_file = Some(key_file);
                                     ui.end_row();
                                 }
+                                AuthType::KeyboardInteractive => {
+                                    ui.label("密码(可选):");
+                                    let mut password =
+                                        connection.password.clone().unwrap_or_default();
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut password)
+                                            .password(true)
+                                            .hint_text("用于自动回答密码提示，验证码将弹窗输入"),
+                                    );
+                                    connection.password = Some(password);
+                                    ui.end_row();
+                                }
+                                AuthType::Agent => {
+                                    ui.label("Agent:");
+                                    match std::env::var("SSH_AUTH_SOCK") {
+                                        Ok(sock) => ui.small(sock),
+                                        Err(_) => ui.colored_label(
+                                            egui::Color32::RED,
+                                            "未检测到SSH_AUTH_SOCK",
+                                        ),
+                                    };
+                                    ui.end_row();
+                                }
                             }
 
+                            ui.label("回退顺序:");
+                            Self::show_auth_order_editor(ui, &mut connection.auth_order);
+                            ui.end_row();
+
+                            ui.label("跳板机:");
+                            Self::show_jump_host_editor(ui, &mut connection, &config.connections);
+                            ui.end_row();
+
+                            ui.label("端口转发:");
+                            Self::show_forward_rules_editor(ui, &mut connection.forwards);
+                            ui.end_row();
+
+                            ui.label("Agent转发:");
+                            ui.checkbox(&mut connection.forward_agent, "转发本地ssh-agent到远程");
+                            ui.end_row();
+
+                            ui.label("字符编码:");
+                            egui::ComboBox::from_id_salt("connection_encoding")
+                                .selected_text(connection.encoding.label())
+                                .show_ui(ui, |ui| {
+                                    for encoding in TerminalEncoding::ALL {
+                                        ui.selectable_value(&mut connection.encoding, encoding, encoding.label());
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("远程剪贴板:");
+                            ui.horizontal(|ui| {
+                                egui::ComboBox::from_id_salt("connection_clipboard_policy")
+                                    .selected_text(connection.clipboard_policy.label())
+                                    .show_ui(ui, |ui| {
+                                        for policy in ClipboardPolicy::ALL {
+                                            ui.selectable_value(&mut connection.clipboard_policy, policy, policy.label());
+                                        }
+                                    })
+                                    .response
+                                    .on_hover_text("vim/tmux等通过OSC 52写入本地剪贴板");
+                                ui.checkbox(&mut connection.clipboard_read, "允许读取");
+                            });
+                            ui.end_row();
+
+                            ui.label("配色方案:");
+                            let selected = match &connection.theme {
+                                Some(name) => themes.get(name).label().to_string(),
+                                None => "跟随全局".to_string(),
+                            };
+                            egui::ComboBox::from_id_salt("connection_theme")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut connection.theme, None, "跟随全局");
+                                    for theme in themes.themes() {
+                                        ui.selectable_value(&mut connection.theme, Some(theme.name.clone()), theme.label());
+                                    }
+                                })
+                                .response
+                                .on_hover_text("例如为生产环境使用醒目的配色");
+                            ui.end_row();
+
                             ui.label("描述:");
                             ui.text_edit_multiline(&mut connection.description);
                             ui.end_row();
@@ -256,4 +425,147 @@ impl ConnectionManager {
             }
         }
     }
+
+    /// 🔗 跳板机编辑器 - 从已保存的连接中按顺序选择跳板机
+    fn show_jump_host_editor(
+        ui: &mut egui::Ui,
+        connection: &mut ConnectionConfig,
+        saved: &[ConnectionConfig],
+    ) {
+        ui.vertical(|ui| {
+            let mut to_remove = None;
+            for (i, jump_id) in connection.jump_hosts.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    match saved.iter().find(|c| &c.id == jump_id) {
+                        Some(jump) => ui.label(format!("{}. {} ({})", i + 1, jump.name, jump.hop_label())),
+                        None => ui.colored_label(egui::Color32::RED, format!("{}. 已删除的连接", i + 1)),
+                    };
+                    if ui
+                        .small_button(egui::RichText::new(regular::X).size(12.0))
+                        .clicked()
+                    {
+                        to_remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = to_remove {
+                connection.jump_hosts.remove(i);
+            }
+
+            let candidates: Vec<&ConnectionConfig> = saved
+                .iter()
+                .filter(|c| c.id != connection.id && !connection.jump_hosts.contains(&c.id))
+                .collect();
+            if candidates.is_empty() {
+                if connection.jump_hosts.is_empty() {
+                    ui.small("无（直连）");
+                }
+                return;
+            }
+
+            egui::ComboBox::from_id_salt("jump_host_picker")
+                .selected_text(format!("{} 添加跳板机", regular::PLUS))
+                .show_ui(ui, |ui| {
+                    for candidate in candidates {
+                        if ui
+                            .selectable_label(false, format!("{} ({})", candidate.name, candidate.hop_label()))
+                            .clicked()
+                        {
+                            connection.jump_hosts.push(candidate.id.clone());
+                        }
+                    }
+                });
+        });
+    }
+
+    /// 🔀 端口转发规则编辑器 - 勾选的规则在连接时自动启动
+    fn show_forward_rules_editor(ui: &mut egui::Ui, forwards: &mut Vec<ForwardRule>) {
+        ui.vertical(|ui| {
+            let mut to_remove = None;
+            for (i, rule) in forwards.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut rule.enabled, "")
+                        .on_hover_text("连接时自动启动");
+                    ui.monospace(rule.summary());
+                    if ui
+                        .small_button(egui::RichText::new(regular::X).size(12.0))
+                        .clicked()
+                    {
+                        to_remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = to_remove {
+                forwards.remove(i);
+            }
+
+            // 新规则的草稿（和校验错误）保存在egui临时数据中
+            let draft_id = ui.make_persistent_id("forward_rule_draft");
+            let (mut draft, mut error): (ForwardRule, Option<String>) = ui
+                .data_mut(|d| d.get_temp(draft_id))
+                .unwrap_or_default();
+            port_forward_panel::rule_form(ui, &mut draft, "forward_rule_kind");
+            ui.horizontal(|ui| {
+                if ui
+                    .small_button(format!("{} 添加规则", regular::PLUS))
+                    .clicked()
+                {
+                    match draft.validate() {
+                        Ok(_) => {
+                            forwards.push(std::mem::take(&mut draft));
+                            error = None;
+                        }
+                        Err(e) => error = Some(e),
+                    }
+                }
+                if let Some(error) = &error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+            });
+            ui.data_mut(|d| d.insert_temp(draft_id, (draft, error)));
+        });
+    }
+
+    /// 认证回退顺序编辑器 - 勾选启用，箭头调整顺序
+    fn show_auth_order_editor(ui: &mut egui::Ui, auth_order: &mut Vec<AuthType>) {
+        ui.vertical(|ui| {
+            let mut move_up = None;
+            let mut toggled = None;
+
+            // 已启用的方式按顺序在前，未启用的排在后面
+            let disabled: Vec<AuthType> = AuthType::ALL
+                .into_iter()
+                .filter(|method| !auth_order.contains(method))
+                .collect();
+
+            for (i, method) in auth_order.iter().chain(disabled.iter()).enumerate() {
+                let enabled = i < auth_order.len();
+                ui.horizontal(|ui| {
+                    let mut checked = enabled;
+                    if ui.checkbox(&mut checked, method.label()).changed() {
+                        toggled = Some(method.clone());
+                    }
+                    if enabled
+                        && i > 0
+                        && ui
+                            .small_button(egui::RichText::new(regular::ARROW_UP).size(12.0))
+                            .clicked()
+                    {
+                        move_up = Some(i);
+                    }
+                });
+            }
+
+            if let Some(i) = move_up {
+                auth_order.swap(i, i - 1);
+            }
+            if let Some(method) = toggled {
+                if let Some(pos) = auth_order.iter().position(|m| *m == method) {
+                    auth_order.remove(pos);
+                } else {
+                    auth_order.push(method);
+                }
+            }
+        });
+    }
 }
@@ -0,0 +1,233 @@
+// 字体 - 终端等宽字体和CJK/Emoji后备字体链，字体文件从各平台的系统字体目录中查找
+//
+// egui不做文字整形（shaping），连字字体可以使用，但连字本身不会被合成
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use eframe::egui;
+
+use crate::config::AppSettings;
+
+/// 终端字号范围（Ctrl+= / Ctrl+- 调整）
+pub const MIN_FONT_SIZE: u16 = 8;
+pub const MAX_FONT_SIZE: u16 = 36;
+
+/// 查找字体文件时的最大目录深度（/usr/share/fonts/opentype/noto/...）
+const MAX_SCAN_DEPTH: usize = 5;
+
+/// 未指定终端字体时依次尝试的系统等宽字体
+const MONOSPACE_CANDIDATES: &[&str] = &[
+    "consola.ttf",                // Windows: Consolas
+    "cour.ttf",                   // Windows: Courier New
+    "Menlo.ttc",                  // macOS
+    "SFNSMono.ttf",               // macOS
+    "DejaVuSansMono.ttf",         // Linux
+    "LiberationMono-Regular.ttf", // Linux
+    "NotoSansMono-Regular.ttf",   // Linux
+    "UbuntuMono-R.ttf",           // Ubuntu
+];
+
+/// 中日韩后备字体，只加载找到的第一个（这类字体通常有几十MB）
+const CJK_CANDIDATES: &[&str] = &[
+    "msyh.ttc",   // Windows: 微软雅黑
+    "simsun.ttc", // Windows: 宋体
+    "simhei.ttf", // Windows: 黑体
+    "PingFang.ttc",
+    "Hiragino Sans GB.ttc",
+    "STHeiti Light.ttc",
+    "NotoSansMonoCJKsc-Regular.otf",
+    "NotoSansCJK-Regular.ttc",
+    "NotoSansCJKsc-Regular.otf",
+    "SourceHanSansSC-Regular.otf",
+    "wqy-microhei.ttc",
+    "wqy-zenhei.ttc",
+    "DroidSansFallbackFull.ttf",
+];
+
+/// 符号/黑白Emoji后备字体（egui无法渲染彩色位图Emoji字体，内置的NotoEmoji之外再补充符号覆盖）
+const SYMBOL_CANDIDATES: &[&str] = &["seguisym.ttf", "Symbola.ttf", "Apple Symbols.ttf", "DejaVuSans.ttf"];
+
+/// 🔤 系统中的字体文件（启动时扫描一次）
+#[derive(Debug, Default)]
+pub struct SystemFonts {
+    files: Vec<PathBuf>,
+}
+
+impl SystemFonts {
+    pub fn scan() -> Self {
+        let mut files = Vec::new();
+        for directory in font_directories() {
+            collect_font_files(&directory, MAX_SCAN_DEPTH, &mut files);
+        }
+        crate::app_log!(info, "Font", "🔤 找到 {} 个系统字体文件", files.len());
+        Self { files }
+    }
+
+    /// 按文件名查找（不区分大小写）
+    pub fn find(&self, file_name: &str) -> Option<&Path> {
+        self.files
+            .iter()
+            .find(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.eq_ignore_ascii_case(file_name))
+            })
+            .map(PathBuf::as_path)
+    }
+
+    /// 设置中的字体：存在的路径直接使用，否则按系统字体文件名查找
+    pub fn resolve(&self, font: &str) -> Option<PathBuf> {
+        let path = Path::new(font);
+        if path.is_file() {
+            return Some(path.to_path_buf());
+        }
+        self.find(font).map(Path::to_path_buf)
+    }
+
+    /// 文件名看起来是等宽字体的系统字体，供菜单选择
+    pub fn monospace(&self) -> Vec<&Path> {
+        const HINTS: [&str; 6] = ["mono", "code", "consol", "courier", "menlo", "hack"];
+        let mut fonts: Vec<&Path> = self
+            .files
+            .iter()
+            .filter(|path| {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_ascii_lowercase();
+                HINTS.iter().any(|hint| name.contains(hint))
+            })
+            .map(PathBuf::as_path)
+            .collect();
+        fonts.sort_by_key(|path| path.file_name().map(|n| n.to_ascii_lowercase()));
+        fonts.dedup_by_key(|path| path.file_name().map(|n| n.to_ascii_lowercase()));
+        fonts
+    }
+}
+
+/// 字体在菜单中显示的名称（文件名去掉扩展名）
+pub fn font_label(font: &str) -> String {
+    Path::new(font)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(font)
+        .to_string()
+}
+
+/// 🔤 按设置加载字体：终端字体 → egui内置等宽字体 → 用户后备字体 → CJK → 符号
+pub fn apply_fonts(ctx: &egui::Context, settings: &AppSettings, system: &SystemFonts) {
+    let mut fonts = egui::FontDefinitions::default();
+
+    // 添加Phosphor图标字体支持
+    egui_phosphor::add_to_fonts(&mut fonts, egui_phosphor::Variant::Regular);
+
+    // 终端字体：用户指定的，或第一个找到的系统等宽字体
+    let primary = match &settings.font_path {
+        Some(font) => {
+            let path = system.resolve(font);
+            if path.is_none() {
+                crate::app_log!(warn, "Font", "找不到终端字体 {}，使用默认等宽字体", font);
+            }
+            path
+        }
+        None => MONOSPACE_CANDIDATES.iter().find_map(|name| system.find(name)).map(Path::to_path_buf),
+    };
+    if let Some(path) = primary
+        && load_font(&mut fonts, "terminal_font", &path)
+    {
+        monospace_fonts(&mut fonts).insert(0, "terminal_font".to_string());
+    }
+
+    // 后备字体：在所有已加载的字体中都缺字时按顺序查找
+    let mut fallbacks: Vec<PathBuf> = settings
+        .fallback_fonts
+        .iter()
+        .filter_map(|font| {
+            let path = system.resolve(font);
+            if path.is_none() {
+                crate::app_log!(warn, "Font", "找不到后备字体: {}", font);
+            }
+            path
+        })
+        .collect();
+    fallbacks.extend(CJK_CANDIDATES.iter().find_map(|name| system.find(name)).map(Path::to_path_buf));
+    fallbacks.extend(SYMBOL_CANDIDATES.iter().find_map(|name| system.find(name)).map(Path::to_path_buf));
+
+    for (i, path) in fallbacks.iter().enumerate() {
+        let id = format!("fallback_font_{}", i);
+        if !load_font(&mut fonts, &id, path) {
+            continue;
+        }
+        monospace_fonts(&mut fonts).push(id.clone());
+        // 界面文字（中文标签等）同样需要后备字体
+        fonts.families.entry(egui::FontFamily::Proportional).or_default().push(id);
+    }
+
+    ctx.set_fonts(fonts);
+}
+
+fn monospace_fonts(fonts: &mut egui::FontDefinitions) -> &mut Vec<String> {
+    fonts.families.entry(egui::FontFamily::Monospace).or_default()
+}
+
+/// 读取并校验字体文件（egui遇到无法解析的字体会直接panic）
+fn load_font(fonts: &mut egui::FontDefinitions, id: &str, path: &Path) -> bool {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) => {
+            crate::app_log!(warn, "Font", "无法读取字体 {}: {}", path.display(), e);
+            return false;
+        }
+    };
+    if let Err(e) = ab_glyph::FontRef::try_from_slice(&data) {
+        crate::app_log!(warn, "Font", "不是有效的字体文件 {}: {}", path.display(), e);
+        return false;
+    }
+    fonts.font_data.insert(id.to_string(), Arc::new(egui::FontData::from_owned(data)));
+    crate::app_log!(info, "Font", "成功加载字体: {} ({})", id, path.display());
+    true
+}
+
+/// 各平台的系统和用户字体目录
+fn font_directories() -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+    if cfg!(windows) {
+        let windows = std::env::var_os("WINDIR").map_or_else(|| PathBuf::from("C:\\Windows"), PathBuf::from);
+        directories.push(windows.join("Fonts"));
+        if let Some(local) = dirs::data_local_dir() {
+            directories.push(local.join("Microsoft").join("Windows").join("Fonts"));
+        }
+    } else if cfg!(target_os = "macos") {
+        directories.extend(
+            ["/System/Library/Fonts", "/System/Library/Fonts/Supplemental", "/Library/Fonts"].map(PathBuf::from),
+        );
+    } else {
+        // XDG规范：$XDG_DATA_DIRS/fonts，以及旧的 ~/.fonts
+        let data_dirs = std::env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+        directories.extend(data_dirs.split(':').filter(|d| !d.is_empty()).map(|d| Path::new(d).join("fonts")));
+        if let Some(home) = dirs::home_dir() {
+            directories.push(home.join(".fonts"));
+        }
+    }
+    // Linux: $XDG_DATA_HOME/fonts，macOS: ~/Library/Fonts
+    directories.extend(dirs::font_dir());
+    directories
+}
+
+fn collect_font_files(directory: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth > 0 {
+                collect_font_files(&path, depth - 1, files);
+            }
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| ["ttf", "otf", "ttc"].iter().any(|ext| e.eq_ignore_ascii_case(ext)))
+        {
+            files.push(path);
+        }
+    }
+}
@@ -1,9 +1,12 @@
+pub mod auth_dialog;
 pub mod connection_manager;
+pub mod fonts;
 pub mod plugins_panel;
-pub mod terminal_emulator;
+pub mod port_forward_panel;
 pub mod terminal;
 pub mod simple_terminal;
 pub mod tab_system;
+pub mod toast;
 
 use serde::{Deserialize, Serialize};
 
@@ -11,10 +14,13 @@ pub use connection_manager::ConnectionManager;
 pub use plugins_panel::PluginsPanel;
 pub use simple_terminal::SimpleTerminalPanel;
 pub use tab_system::{TabManager, TabEvent, TabObserver};
+pub use crate::terminal::TerminalEncoding;
 
 // SSH 连接配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionConfig {
+    #[serde(default = "ConnectionConfig::new_id")]
+    pub id: String, // 稳定标识，供其他连接引用为跳板机
     pub name: String,
     pub host: String,
     pub port: u16,
@@ -23,12 +29,94 @@ pub struct ConnectionConfig {
     pub password: Option<String>,
     pub key_file: Option<String>,
     pub description: String,
+    #[serde(default)]
+    pub forward_agent: bool, // 是否将本地ssh-agent转发到远程shell
+    #[serde(default = "AuthType::default_order")]
+    pub auth_order: Vec<AuthType>, // 主认证方式失败后的回退顺序
+    #[serde(default)]
+    pub jump_hosts: Vec<String>, // 跳板机（按顺序引用其他已保存连接的id）
+    #[serde(default)]
+    pub forwards: Vec<ForwardRule>, // 端口转发规则，随Tab连接自动启动
+    #[serde(default)]
+    pub encoding: TerminalEncoding, // 远程终端的字符编码
+    #[serde(default)]
+    pub clipboard_policy: ClipboardPolicy, // 远端程序通过OSC 52写入本地剪贴板
+    #[serde(default)]
+    pub clipboard_read: bool, // 是否允许远端读取本地剪贴板（同样按clipboard_policy确认）
+    #[serde(default)]
+    pub theme: Option<String>, // 该连接使用的配色方案，None时跟随全局设置
+}
+
+impl ConnectionConfig {
+    fn new_id() -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+
+    /// 单跳的显示名称
+    pub fn hop_label(&self) -> String {
+        format!("{}@{}", self.username, self.host)
+    }
+
+    /// 完整跳转链的显示名称，例如 `ops@bastion ➜ root@db`
+    pub fn chain_label(&self, jump_chain: &[ConnectionConfig]) -> String {
+        jump_chain
+            .iter()
+            .chain(std::iter::once(self))
+            .map(|hop| hop.hop_label())
+            .collect::<Vec<_>>()
+            .join(" ➜ ")
+    }
+
+    /// 实际尝试的认证顺序：主认证方式优先，然后按回退顺序（去重）
+    pub fn auth_sequence(&self) -> Vec<AuthType> {
+        let mut sequence = vec![self.auth_type.clone()];
+        for method in &self.auth_order {
+            if !sequence.contains(method) {
+                sequence.push(method.clone());
+            }
+        }
+        sequence
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum AuthType {
     Password,
     PublicKey,
+    Agent, // 使用本地ssh-agent（SSH_AUTH_SOCK）中的身份
+    KeyboardInteractive, // 服务器交互式提示（OTP/2FA）
+}
+
+impl AuthType {
+    pub const ALL: [AuthType; 4] = [
+        AuthType::PublicKey,
+        AuthType::Agent,
+        AuthType::KeyboardInteractive,
+        AuthType::Password,
+    ];
+
+    /// 默认的回退顺序
+    pub fn default_order() -> Vec<AuthType> {
+        Self::ALL.to_vec()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuthType::Password => "密码",
+            AuthType::PublicKey => "公钥",
+            AuthType::Agent => "SSH Agent",
+            AuthType::KeyboardInteractive => "交互式验证(2FA)",
+        }
+    }
+
+    /// 对应SSH协议中的认证方法名（auth_methods()返回值）
+    pub fn protocol_name(&self) -> &'static str {
+        match self {
+            AuthType::Password => "password",
+            AuthType::PublicKey | AuthType::Agent => "publickey",
+            AuthType::KeyboardInteractive => "keyboard-interactive",
+        }
+    }
 }
 
 impl Default for AuthType {
@@ -40,6 +128,7 @@ impl Default for AuthType {
 impl Default for ConnectionConfig {
     fn default() -> Self {
         Self {
+            id: Self::new_id(),
             name: String::new(),
             host: String::new(),
             port: 22,
@@ -48,6 +137,130 @@ impl Default for ConnectionConfig {
             password: None,
             key_file: None,
             description: String::new(),
+            forward_agent: false,
+            auth_order: AuthType::default_order(),
+            jump_hosts: Vec::new(),
+            forwards: Vec::new(),
+            encoding: TerminalEncoding::Utf8,
+            clipboard_policy: ClipboardPolicy::Ask,
+            clipboard_read: false,
+            theme: None,
+        }
+    }
+}
+
+/// 📋 远端剪贴板（OSC 52）权限
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum ClipboardPolicy {
+    Allow,
+    #[default]
+    Ask,
+    Deny,
+}
+
+impl ClipboardPolicy {
+    pub const ALL: [ClipboardPolicy; 3] = [ClipboardPolicy::Allow, ClipboardPolicy::Ask, ClipboardPolicy::Deny];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClipboardPolicy::Allow => "允许",
+            ClipboardPolicy::Ask => "每次询问",
+            ClipboardPolicy::Deny => "拒绝",
+        }
+    }
+}
+
+/// 🔀 端口转发类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ForwardKind {
+    Local,   // -L 本地端口 -> 远程可达的目标
+    Remote,  // -R 服务器端口 -> 本地可达的目标
+    Dynamic, // -D 本地SOCKS5代理
+}
+
+impl ForwardKind {
+    pub const ALL: [ForwardKind; 3] = [ForwardKind::Local, ForwardKind::Remote, ForwardKind::Dynamic];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ForwardKind::Local => "本地转发 (-L)",
+            ForwardKind::Remote => "远程转发 (-R)",
+            ForwardKind::Dynamic => "SOCKS5代理 (-D)",
+        }
+    }
+
+    pub fn flag(&self) -> &'static str {
+        match self {
+            ForwardKind::Local => "L",
+            ForwardKind::Remote => "R",
+            ForwardKind::Dynamic => "D",
+        }
+    }
+}
+
+/// 🔀 端口转发规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForwardRule {
+    #[serde(default = "ConnectionConfig::new_id")]
+    pub id: String,
+    pub kind: ForwardKind,
+    pub bind_address: String, // Local/Dynamic为本机地址，Remote为服务器上的地址
+    pub bind_port: u16,       // Remote为0时由服务器分配
+    #[serde(default)]
+    pub target_host: String, // Dynamic不使用
+    #[serde(default)]
+    pub target_port: u16,
+    #[serde(default = "ForwardRule::default_enabled")]
+    pub enabled: bool, // 连接时是否自动启动
+}
+
+impl ForwardRule {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    /// 简短描述，例如 `L 127.0.0.1:5432 ➜ db:5432`
+    pub fn summary(&self) -> String {
+        match self.kind {
+            ForwardKind::Dynamic => format!("D {}:{}", self.bind_address, self.bind_port),
+            kind => format!(
+                "{} {}:{} ➜ {}:{}",
+                kind.flag(),
+                self.bind_address,
+                self.bind_port,
+                self.target_host,
+                self.target_port
+            ),
+        }
+    }
+
+    /// 校验规则是否完整，返回错误描述
+    pub fn validate(&self) -> Result<(), String> {
+        if self.bind_address.trim().is_empty() {
+            return Err("监听地址不能为空".to_string());
+        }
+        if self.kind != ForwardKind::Remote && self.bind_port == 0 {
+            return Err("监听端口不能为0".to_string());
+        }
+        if self.kind != ForwardKind::Dynamic
+            && (self.target_host.trim().is_empty() || self.target_port == 0)
+        {
+            return Err("请填写目标主机和端口".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for ForwardRule {
+    fn default() -> Self {
+        Self {
+            id: ConnectionConfig::new_id(),
+            kind: ForwardKind::Local,
+            bind_address: "127.0.0.1".to_string(),
+            bind_port: 0,
+            target_host: "127.0.0.1".to_string(),
+            target_port: 0,
+            enabled: true,
         }
     }
 }
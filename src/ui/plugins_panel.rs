@@ -1,12 +1,16 @@
 use crate::plugins::{
-    Plugin, file_browser::FileBrowser, software_detector::SoftwareDetector,
+    Plugin,
+    file_browser::{FileBrowser, RemoteTarget, SortKey},
+    software_detector::SoftwareDetector,
     system_monitor::SystemMonitor,
 };
 use crate::utils::{format_bytes, format_percentage, truncate_string};
 use eframe::egui;
 use egui_phosphor::regular;
 use egui_plot::{Line, Plot, PlotPoints};
+use crate::ssh::transfer::TransferCommand;
 use std::collections::VecDeque;
+use std::path::PathBuf;
 
 pub struct PluginsPanel {
     system_monitor: SystemMonitor,
@@ -17,6 +21,10 @@ pub struct PluginsPanel {
     show_system_monitor: bool,
     show_software_list: bool,
     show_file_browser: bool,
+    download_dir: Option<PathBuf>, // 上次选择的下载目录
+    path_to_insert: Option<String>, // 用户选择插入终端命令行的远程路径
+    revealed_entry: Option<(String, String)>, // 从终端链接打开的（目录，文件名），在列表中突出显示
+    scroll_to_revealed: bool,                 // 列表加载后滚动到突出显示的项
 }
 
 impl PluginsPanel {
@@ -30,6 +38,10 @@ impl PluginsPanel {
             show_system_monitor: true,
             show_software_list: false,
             show_file_browser: false,
+            download_dir: None,
+            path_to_insert: None,
+            revealed_entry: None,
+            scroll_to_revealed: false,
         }
     }
 
@@ -105,6 +117,8 @@ impl PluginsPanel {
     }
 
     fn show_system_monitor_panel(&mut self, ui: &mut egui::Ui) {
+        // 展开时按系统监控的更新间隔（1秒）刷新
+        ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
         // 更新系统信息
         if let Ok(runtime) = tokio::runtime::Runtime::new() {
             if let Ok(_) = runtime.block_on(self.system_monitor.update()) {
@@ -284,86 +298,414 @@ impl PluginsPanel {
         }
     }
 
-    fn show_file_browser_panel(&mut self, ui: &mut egui::Ui) {
-        if ui
-            .button(egui::RichText::new(format!("{} 刷新", regular::ARROW_CLOCKWISE)).size(14.0))
-            .clicked()
-        {
-            if let Ok(runtime) = tokio::runtime::Runtime::new() {
-                let _ = runtime.block_on(async {
-                    self.file_browser.initialize().await?;
-                    self.file_browser.update().await
-                });
+    /// 📁 文件浏览器跟随的会话（None为本机）
+    pub fn set_file_browser_target(&mut self, target: Option<RemoteTarget>) {
+        self.file_browser.set_remote(target);
+    }
+
+    pub fn file_browser_session(&self) -> Option<&str> {
+        self.file_browser.remote_session()
+    }
+
+    /// 📂 终端的工作目录变化时，文件浏览器跟随进入该目录
+    pub fn follow_directory(&mut self, path: &str) {
+        crate::app_log!(debug, "FileBrowser", "📂 跟随终端目录: {}", path);
+        self.file_browser.navigate(PathBuf::from(path));
+    }
+
+    /// 🔗 显示远程路径：进入所在目录并突出显示该项（没有目录部分时为登录目录下的文件）
+    pub fn reveal_path(&mut self, path: &str) {
+        let path = path.trim_end_matches('/');
+        let (directory, name) = match path.rsplit_once('/') {
+            Some(("", name)) => ("/", name),
+            Some((directory, name)) => (directory, name),
+            None if path.is_empty() => ("/", ""),
+            None => (".", path),
+        };
+        crate::app_log!(debug, "FileBrowser", "🔗 显示 {} 中的 {}", directory, name);
+        self.file_browser.navigate(PathBuf::from(directory));
+        self.revealed_entry = Some((directory.to_string(), name.to_string()));
+        self.scroll_to_revealed = true;
+    }
+
+    /// 取走等待插入终端命令行的路径
+    pub fn take_path_to_insert(&mut self) -> Option<String> {
+        self.path_to_insert.take()
+    }
+
+    /// 会话关闭时调用，文件浏览器回到本机
+    pub fn forget_session(&mut self, session_id: &str) {
+        self.file_browser.forget_session(session_id);
+    }
+
+    pub fn show_file_browser_panel(&mut self, ui: &mut egui::Ui) {
+        self.file_browser.poll();
+        let data = self.file_browser.render_data();
+        let current_path = data["current_path"].as_str().unwrap_or("/").to_string();
+        let loading = data["loading"].as_bool().unwrap_or(false);
+        let remote = data["mode"] == "remote";
+        let transferring = data["transfers"]
+            .as_array()
+            .is_some_and(|items| items.iter().any(|item| item["state"] == "queued" || item["state"] == "running"));
+
+        // 远程请求或传输进行中时持续刷新，以便及时显示结果
+        if loading {
+            ui.ctx().request_repaint();
+        } else if transferring {
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(200));
+        }
+
+        // 📦 从系统拖入的文件上传到当前远程目录（取走事件，避免同一帧重复上传）
+        if remote {
+            let dropped: Vec<PathBuf> = ui.ctx().input_mut(|i| {
+                std::mem::take(&mut i.raw.dropped_files)
+                    .into_iter()
+                    .filter_map(|file| file.path)
+                    .collect()
+            });
+            if !dropped.is_empty() {
+                self.file_browser.upload(dropped);
             }
         }
 
-        let data = self.file_browser.render_data();
+        ui.horizontal(|ui| {
+            let icon = if data["mode"] == "remote" {
+                regular::CLOUD
+            } else {
+                regular::DESKTOP
+            };
+            ui.label(egui::RichText::new(icon).size(14.0));
+            ui.strong(data["target"].as_str().unwrap_or("本机"));
+            if loading {
+                ui.spinner();
+            }
+        });
 
         ui.horizontal(|ui| {
-            ui.label("当前路径:");
-            ui.small(data["current_path"].as_str().unwrap_or("/"));
+            if ui
+                .small_button(egui::RichText::new(regular::ARROW_UP).size(14.0))
+                .on_hover_text("上级目录")
+                .clicked()
+            {
+                self.file_browser.go_up();
+            }
+            if ui
+                .small_button(egui::RichText::new(regular::ARROW_CLOCKWISE).size(14.0))
+                .on_hover_text("刷新")
+                .clicked()
+            {
+                let _ = self.file_browser.refresh_files();
+            }
+
+            // 路径输入框：回车跳转
+            let path_id = ui.make_persistent_id("file_browser_path");
+            let mut path_input = ui
+                .data_mut(|d| d.get_temp::<String>(path_id))
+                .filter(|_| ui.memory(|m| m.has_focus(path_id)))
+                .unwrap_or_else(|| current_path.clone());
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut path_input)
+                    .id(path_id)
+                    .desired_width(ui.available_width() - 80.0),
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.file_browser.navigate(std::path::PathBuf::from(path_input.trim()));
+            }
+            ui.data_mut(|d| d.insert_temp(path_id, path_input));
+
+            if remote
+                && ui
+                    .small_button(egui::RichText::new(regular::TERMINAL).size(14.0))
+                    .on_hover_text("把当前目录插入命令行")
+                    .clicked()
+            {
+                self.path_to_insert = Some(current_path.clone());
+            }
+
+            let mut show_hidden = data["show_hidden"].as_bool().unwrap_or(false);
+            if ui.checkbox(&mut show_hidden, "隐藏文件").changed() {
+                self.file_browser.set_show_hidden(show_hidden);
+            }
         });
 
+        if remote {
+            ui.horizontal(|ui| {
+                if ui.button(format!("{} 上传文件", regular::UPLOAD_SIMPLE)).clicked()
+                    && let Some(paths) = rfd::FileDialog::new().pick_files()
+                {
+                    self.file_browser.upload(paths);
+                }
+                if ui.button(format!("{} 上传文件夹", regular::FOLDER_SIMPLE_PLUS)).clicked()
+                    && let Some(path) = rfd::FileDialog::new().pick_folder()
+                {
+                    self.file_browser.upload(vec![path]);
+                }
+                if ui.ctx().input(|i| !i.raw.hovered_files.is_empty()) {
+                    ui.colored_label(
+                        egui::Color32::LIGHT_BLUE,
+                        format!("松开鼠标上传到 {}", current_path),
+                    );
+                } else {
+                    ui.weak("也可以把文件拖到窗口中上传");
+                }
+            });
+        }
+
+        if let Some(error) = data["error"].as_str() {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
         ui.separator();
 
-        if let Some(files) = data["files"].as_array() {
-            egui::ScrollArea::vertical()
-                .max_height(200.0)
-                .show(ui, |ui| {
-                    for file in files {
-                        let name = file["name"].as_str().unwrap_or("Unknown");
-                        let is_directory = file["is_directory"].as_bool().unwrap_or(false);
-                        let size = file["size"].as_u64().unwrap_or(0);
-
-                        ui.horizontal(|ui| {
-                            let icon = if is_directory {
-                                regular::FOLDER
+        let Some(files) = data["files"].as_array() else {
+            ui.label("无法读取目录内容");
+            return;
+        };
+
+        let sort_key = data["sort_key"].as_str().unwrap_or("name");
+        let arrow = if data["sort_ascending"].as_bool().unwrap_or(true) {
+            regular::CARET_UP
+        } else {
+            regular::CARET_DOWN
+        };
+        let header = |ui: &mut egui::Ui, title: &str, key: &str| {
+            let text = if sort_key == key {
+                format!("{} {}", title, arrow)
+            } else {
+                title.to_string()
+            };
+            ui.add(egui::Button::new(egui::RichText::new(text).strong()).frame(false))
+                .clicked()
+        };
+
+        let mut sort_by = None;
+        let mut enter_dir = None;
+        let mut download = None;
+        let mut insert = None;
+        let revealed_name = self
+            .revealed_entry
+            .as_ref()
+            .filter(|(directory, _)| *directory == current_path)
+            .map(|(_, name)| name.clone());
+        let scroll_to_revealed = self.scroll_to_revealed;
+        let mut revealed_shown = false;
+
+        egui::ScrollArea::both()
+            .max_height(320.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                egui::Grid::new("file_browser_grid")
+                    .num_columns(if remote { 6 } else { 5 })
+                    .striped(true)
+                    .spacing([12.0, 2.0])
+                    .show(ui, |ui| {
+                        if header(ui, "名称", "name") {
+                            sort_by = Some(SortKey::Name);
+                        }
+                        if header(ui, "大小", "size") {
+                            sort_by = Some(SortKey::Size);
+                        }
+                        ui.strong("权限");
+                        ui.strong("所有者");
+                        if header(ui, "修改时间", "modified") {
+                            sort_by = Some(SortKey::Modified);
+                        }
+                        if remote {
+                            ui.label("");
+                        }
+                        ui.end_row();
+
+                        for file in files {
+                            let name = file["name"].as_str().unwrap_or("Unknown");
+                            let is_directory = file["is_directory"].as_bool().unwrap_or(false);
+                            let is_symlink = file["is_symlink"].as_bool().unwrap_or(false);
+
+                            let icon = match (is_directory, is_symlink) {
+                                (true, false) => regular::FOLDER,
+                                (true, true) => regular::FOLDER_SIMPLE_DASHED,
+                                (false, true) => regular::LINK_SIMPLE,
+                                (false, false) => regular::FILE,
+                            };
+                            let revealed = revealed_name.as_deref() == Some(name);
+                            let mut label = egui::RichText::new(format!("{} {}", icon, truncate_string(name, 32)));
+                            if revealed {
+                                label = label.strong().background_color(ui.visuals().selection.bg_fill);
+                            }
+                            let name_response = if is_directory {
+                                let response = ui.add(egui::Button::new(label).frame(false)).on_hover_text(name);
+                                if response.clicked() {
+                                    enter_dir = Some(name.to_string());
+                                }
+                                response
                             } else {
-                                regular::FILE
+                                ui.label(label).on_hover_text(name)
                             };
+                            if revealed && scroll_to_revealed {
+                                name_response.scroll_to_me(Some(egui::Align::Center));
+                                revealed_shown = true;
+                            }
 
                             if is_directory {
-                                if ui
-                                    .button(
-                                        egui::RichText::new(format!(
-                                            "{} {}",
-                                            icon,
-                                            truncate_string(name, 25)
-                                        ))
-                                        .size(14.0),
-                                    )
-                                    .clicked()
-                                {
-                                    // 导航到目录
-                                    let mut new_path = std::path::PathBuf::from(
-                                        data["current_path"].as_str().unwrap_or("/"),
-                                    );
-                                    new_path.push(name);
-                                    self.file_browser.set_path(new_path);
-                                    if let Ok(runtime) = tokio::runtime::Runtime::new() {
-                                        let _ = runtime.block_on(async { self.file_browser.update().await });
+                                ui.label("");
+                            } else {
+                                ui.label(format_bytes(file["size"].as_u64().unwrap_or(0)));
+                            }
+                            ui.monospace(file["permissions"].as_str().unwrap_or(""));
+                            let owner = file["owner"].as_str().unwrap_or("");
+                            let group = file["group"].as_str().unwrap_or("");
+                            if owner.is_empty() {
+                                ui.label("");
+                            } else {
+                                ui.label(format!("{}:{}", owner, group));
+                            }
+                            ui.label(file["modified"].as_str().unwrap_or(""));
+                            if remote {
+                                ui.horizontal(|ui| {
+                                    if ui
+                                        .small_button(regular::DOWNLOAD_SIMPLE)
+                                        .on_hover_text("下载到本地")
+                                        .clicked()
+                                    {
+                                        download = Some((name.to_string(), is_directory));
+                                    }
+                                    if ui
+                                        .small_button(regular::TERMINAL)
+                                        .on_hover_text("把路径插入命令行")
+                                        .clicked()
+                                    {
+                                        insert = Some(name.to_string());
                                     }
+                                });
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        ui.horizontal(|ui| {
+            ui.label("文件数量:");
+            ui.label(format!("{}", data["file_count"].as_u64().unwrap_or(0)));
+        });
+
+        if revealed_shown {
+            self.scroll_to_revealed = false;
+        }
+        if let Some(key) = sort_by {
+            self.file_browser.sort_by(key);
+        }
+        if let Some(name) = insert {
+            self.path_to_insert = Some(self.file_browser.entry_path(&name));
+        }
+        if let Some(name) = enter_dir {
+            self.file_browser.enter(&name);
+        }
+        if let Some((name, is_dir)) = download {
+            let mut dialog = rfd::FileDialog::new().set_title("选择下载目录");
+            if let Some(dir) = &self.download_dir {
+                dialog = dialog.set_directory(dir);
+            }
+            if let Some(local_dir) = dialog.pick_folder() {
+                self.file_browser.download(&name, is_dir, local_dir.clone());
+                self.download_dir = Some(local_dir);
+            }
+        }
+
+        if remote {
+            self.show_transfer_queue(ui, &data["transfers"]);
+        }
+    }
+
+    /// 📦 传输队列：进度、速度、取消/重试/续传
+    fn show_transfer_queue(&mut self, ui: &mut egui::Ui, transfers: &serde_json::Value) {
+        let Some(items) = transfers.as_array().filter(|items| !items.is_empty()) else {
+            return;
+        };
+
+        ui.separator();
+        let mut command = None;
+        ui.horizontal(|ui| {
+            ui.strong(format!("{} 传输队列", regular::ARROWS_DOWN_UP));
+            if ui.small_button("清除已完成").clicked() {
+                command = Some(TransferCommand::ClearFinished);
+            }
+        });
+
+        egui::ScrollArea::vertical()
+            .id_salt("transfer_queue")
+            .max_height(200.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for item in items {
+                    let id = item["id"].as_u64().unwrap_or(0);
+                    let state = item["state"].as_str().unwrap_or("");
+                    let transferred = item["transferred"].as_u64().unwrap_or(0);
+                    let total = item["total"].as_u64().unwrap_or(0);
+                    let is_dir = item["is_dir"].as_bool().unwrap_or(false);
+                    let icon = if is_dir {
+                        regular::FOLDER_SIMPLE
+                    } else if item["direction"] == "upload" {
+                        regular::UPLOAD_SIMPLE
+                    } else {
+                        regular::DOWNLOAD_SIMPLE
+                    };
+
+                    ui.horizontal(|ui| {
+                        let name = item["name"].as_str().unwrap_or("");
+                        ui.label(format!("{} {}", icon, truncate_string(name, 28))).on_hover_text(format!(
+                            "本地: {}\n远程: {}",
+                            item["local_path"].as_str().unwrap_or(""),
+                            item["remote_path"].as_str().unwrap_or("")
+                        ));
+
+                        match state {
+                            "queued" | "running"
+                                if ui.small_button(regular::X).on_hover_text("取消").clicked() =>
+                            {
+                                command = Some(TransferCommand::Cancel(id));
+                            }
+                            "failed" | "cancelled" => {
+                                if ui.small_button(regular::ARROW_CLOCKWISE).on_hover_text("重新传输").clicked() {
+                                    command = Some(TransferCommand::Retry { id, resume: false });
+                                }
+                                if ui.small_button(regular::PLAY).on_hover_text("从中断处续传").clicked() {
+                                    command = Some(TransferCommand::Retry { id, resume: true });
                                 }
-                            } else {
-                                ui.label(egui::RichText::new(icon).size(14.0));
-                                ui.label(truncate_string(name, 25));
-                                ui.with_layout(
-                                    egui::Layout::right_to_left(egui::Align::Center),
-                                    |ui| {
-                                        ui.small(format!("{} bytes", size));
-                                    },
-                                );
                             }
-                        });
-                    }
-                });
+                            _ => {}
+                        }
+                    });
 
-            ui.horizontal(|ui| {
-                ui.label("文件数量:");
-                ui.label(format!("{}", data["file_count"].as_u64().unwrap_or(0)));
+                    let text = match state {
+                        "queued" => "等待中".to_string(),
+                        "done" if is_dir => format!("已展开 {} 项", total),
+                        "skipped" => "已跳过".to_string(),
+                        "running" => format!(
+                            "{} / {}  {}/s",
+                            format_bytes(transferred),
+                            format_bytes(total),
+                            format_bytes(item["speed"].as_f64().unwrap_or(0.0) as u64)
+                        ),
+                        "done" => format!("完成 {}", format_bytes(total)),
+                        "cancelled" => format!("已取消 {} / {}", format_bytes(transferred), format_bytes(total)),
+                        _ => format!("失败 {} / {}", format_bytes(transferred), format_bytes(total)),
+                    };
+                    ui.add(
+                        egui::ProgressBar::new(item["progress"].as_f64().unwrap_or(0.0) as f32)
+                            .text(text)
+                            .desired_height(14.0),
+                    );
+                    if let Some(error) = item["error"].as_str() {
+                        if state == "skipped" {
+                            ui.weak(error);
+                        } else {
+                            ui.colored_label(egui::Color32::RED, error);
+                        }
+                    }
+                }
             });
-        } else {
-            ui.label("无法读取目录内容");
+
+        if let Some(command) = command {
+            self.file_browser.transfer(command);
         }
     }
 }
@@ -0,0 +1,186 @@
+use crate::config::AppConfig;
+use crate::ssh::Ssh2Manager;
+use crate::ssh::port_forward::TunnelState;
+use crate::ui::{ForwardKind, ForwardRule};
+use crate::utils::format_bytes;
+use eframe::egui;
+use egui_phosphor::regular;
+
+/// 🔀 端口转发面板 - 显示运行中的隧道，支持在会话上增删转发
+pub struct PortForwardPanel {
+    open: bool,
+    draft: ForwardRule,
+    persist: bool, // 新增的规则是否同时保存到连接配置
+    error: Option<String>,
+}
+
+impl PortForwardPanel {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            draft: ForwardRule::default(),
+            persist: false,
+            error: None,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        tab_id: &str,
+        ssh_manager: &Ssh2Manager,
+        connection_id: &str,
+        config: &mut AppConfig,
+    ) {
+        if !self.open {
+            return;
+        }
+
+        let tunnels = ssh_manager.forward_status(tab_id);
+        // 连接数和流量由Actor更新，窗口打开时每秒刷新一次
+        if !tunnels.is_empty() {
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+        let mut to_stop = None;
+        let mut to_add = None;
+        let mut open = self.open;
+
+        egui::Window::new(format!("{} 端口转发", regular::SHUFFLE))
+            .id(egui::Id::new(("port_forward_panel", tab_id)))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                if tunnels.is_empty() {
+                    ui.label("当前会话没有运行中的转发");
+                } else {
+                    egui::Grid::new(("port_forward_grid", tab_id))
+                        .num_columns(6)
+                        .striped(true)
+                        .spacing([12.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.strong("规则");
+                            ui.strong("状态");
+                            ui.strong("连接");
+                            ui.strong("发送");
+                            ui.strong("接收");
+                            ui.label("");
+                            ui.end_row();
+
+                            for tunnel in &tunnels {
+                                ui.monospace(tunnel.rule.summary());
+                                match &tunnel.state {
+                                    TunnelState::Listening => {
+                                        ui.colored_label(
+                                            egui::Color32::DARK_GREEN,
+                                            format!("监听中 :{}", tunnel.bound_port),
+                                        );
+                                    }
+                                    TunnelState::Failed(e) => {
+                                        ui.colored_label(egui::Color32::RED, "失败")
+                                            .on_hover_text(e);
+                                    }
+                                }
+                                ui.label(format!(
+                                    "{} / {}",
+                                    tunnel.active_connections, tunnel.total_connections
+                                ));
+                                ui.label(format_bytes(tunnel.bytes_sent));
+                                ui.label(format_bytes(tunnel.bytes_received));
+                                if ui
+                                    .small_button(format!("{} 停止", regular::STOP))
+                                    .clicked()
+                                {
+                                    to_stop = Some(tunnel.rule.id.clone());
+                                }
+                                ui.end_row();
+
+                                let error = match &tunnel.state {
+                                    TunnelState::Failed(e) => Some(e),
+                                    TunnelState::Listening => tunnel.last_error.as_ref(),
+                                };
+                                if let Some(error) = error {
+                                    ui.label("");
+                                    ui.colored_label(egui::Color32::RED, error);
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                }
+
+                ui.separator();
+                ui.strong("新增转发");
+                rule_form(ui, &mut self.draft, ("port_forward_form", tab_id));
+                ui.checkbox(&mut self.persist, "同时保存到连接配置");
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                if ui.button(format!("{} 启动", regular::PLAY)).clicked() {
+                    to_add = Some(self.draft.clone());
+                }
+            });
+        self.open = open;
+
+        if let Some(rule_id) = to_stop
+            && let Err(e) = ssh_manager.remove_forward(tab_id, &rule_id)
+        {
+            crate::app_log!(error, "PortForward", "停止转发失败: {}", e);
+        }
+
+        if let Some(rule) = to_add {
+            if let Err(e) = rule.validate() {
+                self.error = Some(e);
+                return;
+            }
+            if let Err(e) = ssh_manager.add_forward(tab_id, rule.clone()) {
+                self.error = Some(e.to_string());
+                return;
+            }
+            self.error = None;
+
+            if self.persist
+                && let Some(connection) = config.connections.iter_mut().find(|c| c.id == connection_id)
+            {
+                connection.forwards.push(rule);
+                if let Err(e) = config.save() {
+                    crate::app_log!(error, "PortForward", "保存转发规则失败: {}", e);
+                }
+            }
+            self.draft = ForwardRule {
+                kind: self.draft.kind,
+                ..ForwardRule::default()
+            };
+        }
+    }
+}
+
+/// 转发规则表单（面板和连接编辑对话框共用）
+pub fn rule_form(ui: &mut egui::Ui, rule: &mut ForwardRule, id_salt: impl std::hash::Hash) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt(id_salt)
+            .selected_text(rule.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in ForwardKind::ALL {
+                    ui.selectable_value(&mut rule.kind, kind, kind.label());
+                }
+            });
+
+        ui.label(if rule.kind == ForwardKind::Remote { "服务器监听:" } else { "本地监听:" });
+        ui.add(egui::TextEdit::singleline(&mut rule.bind_address).desired_width(100.0));
+        ui.add(egui::DragValue::new(&mut rule.bind_port).range(0..=65535));
+
+        if rule.kind != ForwardKind::Dynamic {
+            ui.label("➜");
+            ui.add(
+                egui::TextEdit::singleline(&mut rule.target_host)
+                    .desired_width(120.0)
+                    .hint_text("目标主机"),
+            );
+            ui.add(egui::DragValue::new(&mut rule.target_port).range(0..=65535));
+        }
+    });
+}
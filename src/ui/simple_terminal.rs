@@ -1,25 +1,67 @@
+use crate::config::AppSettings;
 use crate::ssh::ssh2_client::Ssh2Manager;
-use crate::ui::terminal::{TerminalEmulator, TerminalLine};
-use crate::ui::ConnectionConfig;
+use crate::terminal::blocks::CommandBlock;
+use crate::terminal::clipboard::{clipboard_reply, ClipboardRequest};
+use crate::terminal::links::{detect_link, LinkTarget};
+use crate::terminal::search::TerminalSearch;
+use crate::terminal::selection::{CellPos, Selection, SelectionMode};
+use crate::terminal::shell_integration::{
+    injection_command, WorkingDirectory, BASH_INTEGRATION, CWD_HOOK, ZSH_INTEGRATION,
+};
+use crate::terminal::TerminalEmulator;
+use crate::ui::terminal::theme::TerminalTheme;
+use crate::ui::terminal::{
+    BlockMarker, BlockStatus, Highlight, HighlightKind, SelectionGesture, TerminalFrame, TerminalView,
+};
+use crate::ui::toast::Toasts;
+use crate::ui::{ClipboardPolicy, ConnectionConfig, TerminalEncoding};
+use crate::utils::shell_quote;
 
 use eframe::egui;
 
 use std::collections::VecDeque;
 use std::sync::Arc;
 
+/// 终端初始字号（显示时使用全局设置）
+const FONT_SIZE: f32 = 14.0;
+
+/// 命令块面板宽度
+const BLOCKS_PANEL_WIDTH: f32 = 280.0;
+/// 命令块面板中展开输出时最多显示的行数
+const BLOCK_PREVIEW_LINES: usize = 200;
+/// 视觉响铃的闪烁时长（秒）
+const VISUAL_BELL_DURATION: f64 = 0.15;
+
 /// 真正简单的终端面板 - 直接读取SSH输出
 pub struct SimpleTerminalPanel {
     pub title: String,
     pub connection_info: String,
-    pub output_buffer: VecDeque<TerminalLine>,
-    input_buffer: String,
-    scroll_to_bottom: bool,
     pub is_connected: bool,
     ssh_manager: Option<Arc<Ssh2Manager>>,
     pub tab_id: Option<String>,
-    current_prompt: String,
     terminal_emulator: TerminalEmulator,
+    view: TerminalView,
     has_ssh_initial_output: bool,
+    pty_size: Option<(u16, u16)>, // 已通知远程PTY的尺寸（列，行），断开后需重新发送
+    search: TerminalSearch,
+    search_dirty: bool, // 查询或终端内容变化后需要重新搜索
+    selection: Option<Selection>,
+    copy_on_select: bool,
+    confirm_multiline_paste: bool,
+    pending_paste: Option<String>, // 等待用户确认的多行粘贴
+    show_blocks: bool,             // 显示命令块面板
+    session_host: Option<String>,  // 会话中第一次通过OSC 7报告的主机名
+    hovered_link: Vec<Highlight>,  // 按住Ctrl时鼠标下的链接
+    path_to_reveal: Option<String>, // Ctrl+单击的远程路径，由Tab交给文件浏览器
+    clipboard_policy: ClipboardPolicy, // 远端OSC 52剪贴板请求的处理方式（对话框中可改为本次会话记住）
+    clipboard_read: bool,
+    clipboard_requests: VecDeque<ClipboardRequest>, // 待处理的请求，询问模式下逐个确认
+    clipboard_query: Option<String>, // 正在读取剪贴板以回复的请求（选择区参数）
+    remember_clipboard_choice: bool,
+    toasts: Toasts,
+    theme: TerminalTheme,
+    activity: bool,   // 自上次取走以来收到过远端输出（后台Tab的活动标记）
+    flash_until: f64, // 视觉响铃持续到的时间（egui时间，秒）
 }
 
 impl std::fmt::Debug for SimpleTerminalPanel {
@@ -37,18 +79,94 @@ impl SimpleTerminalPanel {
         Self {
             title,
             connection_info,
-            output_buffer: VecDeque::new(),
-            input_buffer: String::new(),
-            scroll_to_bottom: false,
             is_connected: false,
             ssh_manager: None,
             tab_id: None,
-            current_prompt: "❯".to_string(),
             terminal_emulator: TerminalEmulator::new(120, 30),
+            view: TerminalView::new(FONT_SIZE),
             has_ssh_initial_output: false,
+            pty_size: None,
+            search: TerminalSearch::default(),
+            search_dirty: false,
+            selection: None,
+            copy_on_select: false,
+            confirm_multiline_paste: true,
+            pending_paste: None,
+            show_blocks: false,
+            session_host: None,
+            hovered_link: Vec::new(),
+            path_to_reveal: None,
+            clipboard_policy: ClipboardPolicy::Ask,
+            clipboard_read: false,
+            clipboard_requests: VecDeque::new(),
+            clipboard_query: None,
+            remember_clipboard_choice: false,
+            toasts: Toasts::default(),
+            theme: TerminalTheme::default(),
+            activity: false,
+            flash_until: 0.0,
+        }
+    }
+
+    /// 🔤 设置远程终端编码（每次建立连接前调用，同时丢弃上个会话残留的半个字符）
+    pub fn set_encoding(&mut self, encoding: TerminalEncoding) {
+        crate::app_log!(debug, "UI", "终端编码: {}", encoding.label());
+        self.terminal_emulator.set_encoding(encoding);
+    }
+
+    /// 📋 设置远端剪贴板权限（每次建立连接前调用，丢弃上个会话未处理的请求）
+    pub fn set_clipboard_policy(&mut self, policy: ClipboardPolicy, allow_read: bool) {
+        self.clipboard_policy = policy;
+        self.clipboard_read = allow_read;
+        self.clipboard_requests.clear();
+        self.clipboard_query = None;
+    }
+
+    /// 🎨 切换配色方案（每帧由Tab调用，未变化时不做任何事）
+    pub fn set_theme(&mut self, theme: &TerminalTheme) {
+        if self.theme != *theme {
+            self.theme = theme.clone();
         }
     }
 
+    /// 🔤 调整字号（每帧由Tab调用），行列数变化后同步调整远程PTY
+    pub fn set_font_size(&mut self, font_size: f32) {
+        self.view.set_font_size(font_size);
+    }
+
+    /// 远端程序通过OSC 0/2设置的标题，没有设置时为空
+    pub fn remote_title(&self) -> &str {
+        self.terminal_emulator.title()
+    }
+
+    /// 🔔 自上次调用以来是否响过铃
+    pub fn take_bell(&mut self) -> bool {
+        self.terminal_emulator.take_bell()
+    }
+
+    /// 自上次调用以来是否收到过远端输出
+    pub fn take_activity(&mut self) -> bool {
+        std::mem::take(&mut self.activity)
+    }
+
+    /// 自上次调用以来结束的命令（需要Shell集成）
+    pub fn take_finished_commands(&mut self) -> Vec<CommandBlock> {
+        self.terminal_emulator.take_finished_commands()
+    }
+
+    /// 🔔 视觉响铃：终端闪一下
+    pub fn flash(&mut self, ctx: &egui::Context) {
+        self.flash_until = ctx.input(|i| i.time) + VISUAL_BELL_DURATION;
+        ctx.request_repaint();
+    }
+
+    /// ⚙️ 应用全局终端设置（建立连接前调用，回滚行数在产生输出后无法更改）
+    pub fn apply_settings(&mut self, settings: &AppSettings) {
+        self.terminal_emulator.set_scrollback_limit(settings.scrollback_lines);
+        self.copy_on_select = settings.copy_on_select;
+        self.confirm_multiline_paste = settings.confirm_multiline_paste;
+    }
+
     /// 设置SSH管理器并启动直接通信
     pub fn set_ssh_manager(&mut self, ssh_manager: Arc<Ssh2Manager>, tab_id: String) {
         self.ssh_manager = Some(ssh_manager.clone());
@@ -64,8 +182,10 @@ impl SimpleTerminalPanel {
     pub fn connect(&mut self, tab_id: String, config: &ConnectionConfig) -> anyhow::Result<()> {
         crate::app_log!(info, "UI", "开始连接SSH2: {}", tab_id);
         
+        self.set_encoding(config.encoding);
+        self.set_clipboard_policy(config.clipboard_policy, config.clipboard_read);
         let mut ssh_manager = Ssh2Manager::new();
-        ssh_manager.create_connection(tab_id.clone(), config)?;
+        ssh_manager.create_connection(tab_id.clone(), config, Vec::new(), None)?;
         
         self.ssh_manager = Some(Arc::new(ssh_manager));
         self.tab_id = Some(tab_id);
@@ -95,11 +215,16 @@ impl SimpleTerminalPanel {
     pub fn show(&mut self, ui: &mut egui::Ui) {
         
         // 🔑 恢复到单次调用，看看是否还有重复
-        self.receive_ssh_output();
+        self.receive_ssh_output(ui.ctx());
+        self.handle_clipboard_requests(ui.ctx());
         
-        // 设置终端样式
-        ui.style_mut().visuals.panel_fill = egui::Color32::WHITE;
-        ui.style_mut().visuals.window_fill = egui::Color32::WHITE;
+        // 🔍 Ctrl+F 打开搜索栏（不发往远端）
+        if ui.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::F)) {
+            self.search.open = true;
+            self.search.invalidate();
+            self.search_dirty = true;
+            ui.memory_mut(|m| m.request_focus(self.search_input_id()));
+        }
 
         ui.vertical(|ui| {
             // 连接信息
@@ -110,35 +235,60 @@ impl SimpleTerminalPanel {
                 } else {
                     ui.colored_label(egui::Color32::RED, &self.connection_info);
                 }
+                if let Some(directory) = self.terminal_emulator.working_directory() {
+                    ui.separator();
+                    ui.label(format!("📂 {}", directory.path))
+                        .on_hover_text(format!("{}:{}", directory.host, directory.path));
+                }
+                let offset = self.terminal_emulator.scroll_offset();
+                if offset > 0 {
+                    ui.separator();
+                    ui.label(format!("📜 已回滚 {} 行", offset));
+                    if ui.small_button("回到底部").clicked() {
+                        self.terminal_emulator.scroll_to_bottom();
+                    }
+                }
+                ui.separator();
+                self.show_shell_integration_menu(ui);
             });
 
-            ui.separator();
-
-            // 终端输出区域
-            self.render_terminal_output(ui);
+            if self.search.open {
+                self.show_search_bar(ui);
+            }
 
             ui.separator();
 
-            // 🎯 输入已集成到终端内容中，不再需要单独的输入区域
+            // 终端网格：显示vt100屏幕，按键直接发往PTY；命令块面板在右侧
+            if self.show_blocks && self.terminal_emulator.command_blocks().is_active() {
+                let available = ui.available_rect_before_wrap();
+                let (terminal_rect, panel_rect) = available.split_left_right_at_x(available.right() - BLOCKS_PANEL_WIDTH);
+                ui.scope_builder(egui::UiBuilder::new().max_rect(panel_rect), |ui| self.show_blocks_panel(ui));
+                ui.scope_builder(egui::UiBuilder::new().max_rect(terminal_rect), |ui| self.render_terminal_output(ui));
+            } else {
+                self.render_terminal_output(ui);
+            }
         });
     }
     
-    /// 🔑 批量读取SSH输出，避免重复处理
-    fn receive_ssh_output(&mut self) {
+    /// 🔑 批量读取SSH输出，避免重复处理；一帧读不完时请求下一帧继续读
+    ///
+    /// 后台Tab也每帧调用，以便检测响铃和命令结束。
+    pub fn receive_ssh_output(&mut self, ctx: &egui::Context) {
         if let (Some(ssh_manager), Some(tab_id)) = (&self.ssh_manager, &self.tab_id) {
             // 🔑 关键改进：批量读取所有可用数据，避免分帧处理导致重复
-            let mut all_data = String::new();
+            let mut all_data = Vec::new();
             let mut read_count = 0;
             
             // 一次性读取所有可用数据
             loop {
                 match ssh_manager.read_output(tab_id) {
                     Ok(data) if !data.is_empty() => {
-                        all_data.push_str(&data);
+                        all_data.extend_from_slice(&data);
                         read_count += 1;
                         
-                        // 防止无限循环，最多读取10次
+                        // 防止无限循环，最多读取10次，剩下的留到下一帧
                         if read_count >= 10 {
+                            ctx.request_repaint();
                             break;
                         }
                     }
@@ -165,613 +315,772 @@ impl SimpleTerminalPanel {
                     crate::app_log!(info, "UI", "🎉 收到SSH初始连接输出");
                 }
                 
-                // 🔑 关键：在显示到UI之前，先记录到日志
-                if all_data.contains("连接已断开") {
-                    crate::app_log!(error, "UI", "🚨 SSH2连接断开，可能是认证失败");
-                    self.is_connected = false;
-                    self.connection_info = "连接已断开（可能是认证失败）".to_string();
-                }
-                
                 // 📢 关键：一次性处理所有数据，避免重复处理
-                self.process_ssh_data(all_data);
+                self.process_ssh_data(&all_data);
             }
         }
     }
 
-    /// 渲染终端输出 + 内嵌式输入（完全重写版）
-    fn render_terminal_output(&mut self, ui: &mut egui::Ui) {
-        let available_height = ui.available_height();
-        let mut should_execute_command = false;
-        let mut special_key_to_send: Option<String> = None;
-        
-        // 🎯 关键修复：先复制所有需要的数据，避免借用冲突
-        let lines: Vec<_> = self.output_buffer.iter().cloned().collect();
-        let current_prompt = self.current_prompt.clone();
-        let is_connected = self.is_connected;
-        
-        // 🔑 关键改进：获取VT100解析器的光标位置信息
-        let cursor_position = self.terminal_emulator.cursor_position();
-        // 🔑 重要修复：VT100坐标与数组索引的对应关系
-        // VT100行1列12 -> 应该对应数组索引[1][11]（即第1行第12个字符）
-        let cursor_row = cursor_position.0.saturating_sub(1) as usize; // VT100行号从1开始，数组从0开始
-        let cursor_col = cursor_position.1.saturating_sub(1) as usize; // VT100列号从1开始，数组从0开始
-        
-        // crate::app_log!(debug, "UI", "📍 VT100光标原始位置: 行{}，列{} -> 数组索引: 行{}，列{}", 
-            // cursor_position.0, cursor_position.1, cursor_row, cursor_col);
-        
-        // 🔍 调试信息：打印终端内容情况
-        // crate::app_log!(debug, "UI", "📊 终端内容总行数: {}", lines.len());
-        for (i, line) in lines.iter().enumerate() {
-            if !line.is_empty() {
-                let line_text = line.text();
-                // crate::app_log!(debug, "UI", "📝 第{}行: '{}'", i, line_text.chars().take(50).collect::<String>());
-            }
+    /// 📐 根据可用区域和字体度量计算行列数，同步到vt100解析器和远程PTY
+    fn update_grid_size(&mut self, ui: &egui::Ui) {
+        let (rows, cols) = self.view.grid_size(ui, ui.available_size());
+
+        self.terminal_emulator.resize(rows, cols);
+
+        if !self.is_connected {
+            self.pty_size = None;
+            return;
         }
-        
-        // 找到最后一行非空内容
-        let mut last_non_empty_index = None;
-        for (index, line) in lines.iter().enumerate().rev() {
-            if !line.is_empty() {
-                last_non_empty_index = Some(index);
-                break;
+        if self.pty_size != Some((cols, rows))
+            && let (Some(ssh_manager), Some(tab_id)) = (&self.ssh_manager, &self.tab_id)
+        {
+            match ssh_manager.resize_terminal(tab_id, cols, rows) {
+                Ok(_) => {
+                    crate::app_log!(debug, "UI", "📐 终端尺寸: {}列 x {}行", cols, rows);
+                    self.pty_size = Some((cols, rows));
+                }
+                Err(e) => crate::app_log!(debug, "UI", "调整终端尺寸失败: {}", e),
             }
         }
-        
-        // 🎯 检测是否在全屏应用模式（如vim）
-        let in_fullscreen_app = self.is_in_fullscreen_app(&lines);
-        // crate::app_log!(debug, "UI", "🔍 全屏应用检测结果: {}", in_fullscreen_app);
-        
-        egui::ScrollArea::vertical()
-            .max_height(available_height)
-            .auto_shrink([false, false])
-            .stick_to_bottom(self.scroll_to_bottom)
-            .show(ui, |ui| {
-                ui.set_min_width(ui.available_width());
-                
-                // 🔑 关键修复：使用语义化的输入框显示逻辑
-                // 不完全依赖VT100报告的光标位置，而是基于终端内容的语义来判断
-                for (index, line) in lines.iter().enumerate() {
-                    let line_text = line.text();
-                    
-                    // 检查是否是包含提示符的行（语义判断）
-                    let is_prompt_line = line_text.contains("➜") || 
-                                        line_text.contains("$") || 
-                                        line_text.contains("#") ||
-                                        line_text.starts_with("(") && line_text.contains(")") && line_text.contains("~");
-                    
-                    // 如果是提示符行且是最后一个提示符行，显示输入框
-                    let should_show_input = is_prompt_line && is_connected && !in_fullscreen_app && {
-                        // 检查是否是最后一个提示符行
-                        let mut is_last_prompt = true;
-                        for (later_index, later_line) in lines.iter().enumerate().skip(index + 1) {
-                            let later_text = later_line.text();
-                            if later_text.contains("➜") || later_text.contains("$") || later_text.contains("#") {
-                                is_last_prompt = false;
-                                break;
-                            }
-                        }
-                        is_last_prompt
-                    };
-                    
-                    if should_show_input {
-                        // crate::app_log!(debug, "UI", "📝 在提示符行({}): '{}' 显示输入框", index, line_text.chars().take(30).collect::<String>());
-                        let (exec_cmd, special_key) = Self::render_line_with_input_static_enhanced(ui, line, &mut self.input_buffer);
-                        should_execute_command = exec_cmd;
-                        special_key_to_send = special_key;
-                    } else {
-                        // 普通行：只显示内容
-                        Self::render_terminal_line_static(ui, line);
-                        
-                        if is_prompt_line {
-                            // crate::app_log!(debug, "UI", "ℹ️ 提示符行({})但不是最后一个: '{}'", index, line_text.chars().take(30).collect::<String>());
-                        }
-                    }
-                }
-                
-                // 如果没有任何提示符行，显示单独输入行（备用）
-                if lines.is_empty() && is_connected && !in_fullscreen_app {
-                    // crate::app_log!(info, "UI", "📝 无终端内容，显示单独输入行");
-                    let (exec_cmd, special_key) = Self::render_integrated_input_line_static_enhanced(ui, &current_prompt, &mut self.input_buffer);
-                    should_execute_command = exec_cmd;
-                    special_key_to_send = special_key;
-                }
-            });
+    }
 
-        if self.scroll_to_bottom {
-            self.scroll_to_bottom = false;
+    /// 渲染终端网格并把本帧的键盘输入发往PTY
+    fn render_terminal_output(&mut self, ui: &mut egui::Ui) {
+        self.update_grid_size(ui);
+
+        let modes = self.terminal_emulator.modes();
+        let scrolled_back = self.terminal_emulator.scroll_offset() > 0;
+        let cursor = (!modes.cursor_hidden && !scrolled_back).then(|| self.terminal_emulator.cursor_position());
+        let mut highlights = self.search_highlights();
+        highlights.extend(self.selection_highlights());
+        highlights.extend(self.hovered_link.iter().copied());
+        let blocks = self.block_markers();
+        let damage = self.terminal_emulator.take_damage();
+        let now = ui.input(|i| i.time);
+        if now < self.flash_until {
+            ui.ctx().request_repaint_after(std::time::Duration::from_secs_f64(self.flash_until - now));
         }
-        
-        // 处理命令执行
-        if should_execute_command {
-            crate::app_log!(info, "UI", "🚀 检测到回车键，准备执行命令");
-            self.execute_command();
+        let id = ui.id().with(("terminal_view", &self.tab_id));
+        let frame = TerminalFrame {
+            lines: self.terminal_emulator.screen(),
+            cursor,
+            cursor_style: modes.cursor_style,
+            key_modes: modes.keys,
+            // 翻看历史时屏幕坐标与远端不对应，鼠标留给本地选择
+            mouse: modes.mouse.filter(|_| !scrolled_back),
+            highlights: &highlights,
+            blocks: &blocks,
+            has_selection: self.selection.is_some_and(|s| !s.is_empty()),
+            theme: &self.theme,
+            damage: &damage,
+            flash: now < self.flash_until,
+        };
+        let output = self.view.show(ui, id, &frame);
+
+        // 📜 滚轮/Shift+PageUp翻看历史；键盘输入时回到底部
+        let scroll = if output.input.is_empty() {
+            output.scroll
+        } else {
+            -(self.terminal_emulator.scroll_offset() as isize)
+        };
+        if scroll != 0 {
+            self.terminal_emulator.scroll_by(scroll);
         }
-        
-        // 🎯 关键新增：处理特殊按键发送（统一通道）
-        if let Some(special_key) = special_key_to_send {
-            self.send_special_key(&special_key);
+
+        if let Some(gesture) = output.selection {
+            self.handle_selection(ui.ctx(), gesture);
+        }
+        if output.copy {
+            self.copy_selection(ui.ctx());
         }
+        if let Some(text) = output.paste {
+            self.paste(text);
+        }
+
+        // 🔗 按住Ctrl悬停的链接加下划线，单击打开
+        let hovered = output.link_hover.and_then(|(row, col)| self.link_at(row, col));
+        if hovered.is_some() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        }
+        let hovered_link = hovered.as_ref().map(|(_, spans)| spans.clone()).unwrap_or_default();
+        if hovered_link != self.hovered_link {
+            self.hovered_link = hovered_link;
+            ui.ctx().request_repaint();
+        }
+        if output.link_click.is_some()
+            && let Some((target, _)) = hovered
+        {
+            self.open_link(ui.ctx(), target);
+        }
+
+        for data in output.input {
+            self.send_input(&data);
+        }
+        for data in output.mouse {
+            self.send_bytes(data);
+        }
+        if let Some(text) = output.clipboard
+            && let Some(selection) = self.clipboard_query.take()
+        {
+            self.send_input(&clipboard_reply(&selection, &text));
+        }
+        self.show_paste_confirm(ui.ctx());
+        self.show_clipboard_confirm(ui.ctx());
+        self.toasts.show(ui.ctx(), egui::Id::new(("terminal_toasts", &self.tab_id)));
     }
 
-    /// 渲染单行终端内容（静态版本）
-    fn render_terminal_line_static(ui: &mut egui::Ui, line: &TerminalLine) {
-        ui.horizontal(|ui| {
-            ui.spacing_mut().item_spacing.x = 0.0;
-            
-            for segment in &line.segments {
-                if segment.text.is_empty() {
-                    continue;
-                }
-                
-                let mut rich_text = egui::RichText::new(&segment.text)
-                    .font(egui::FontId::monospace(14.0));
-                
-                if let Some(color) = segment.color {
-                    rich_text = rich_text.color(color);
-                } else {
-                    rich_text = rich_text.color(egui::Color32::BLACK);
-                }
-                
-                if let Some(bg_color) = segment.background_color {
-                    rich_text = rich_text.background_color(bg_color);
-                }
-                
-                ui.add(egui::Label::new(rich_text).selectable(true));
+    /// 视图中第row行的行号
+    fn line_at_row(&self, row: u16) -> usize {
+        self.terminal_emulator.top_line() + row as usize
+    }
+
+    /// 鼠标所在单元格按选择方式扩展后的区间
+    fn selection_range(&mut self, mode: SelectionMode, row: u16, col: u16) -> (CellPos, CellPos) {
+        let line = self.line_at_row(row);
+        match mode {
+            SelectionMode::Normal | SelectionMode::Block => ((line, col), (line, col + 1)),
+            SelectionMode::Word => {
+                let (start, end) = self.terminal_emulator.visible_line_text(row).word_bounds(col);
+                ((line, start), (line, end))
             }
-        });
+            SelectionMode::Line => ((line, 0), (line, self.terminal_emulator.size().1)),
+        }
     }
-    
-    /// 🎯 渲染带输入框的行（增强版 - 支持特殊按键处理和实时字符发送）
-    fn render_line_with_input_static_enhanced(ui: &mut egui::Ui, line: &TerminalLine, input_buffer: &mut String) -> (bool, Option<String>) {
-        let mut should_execute = false;
-        let mut special_key_to_send = None;
-        
-        // 📝 记录输入前的内容，用于检测变化
-        let previous_content = input_buffer.clone();
-        
-        ui.horizontal(|ui| {
-            ui.spacing_mut().item_spacing.x = 0.0;
-            
-            // 先渲染行内容
-            for segment in &line.segments {
-                if segment.text.is_empty() {
-                    continue;
-                }
-                
-                let mut rich_text = egui::RichText::new(&segment.text)
-                    .font(egui::FontId::monospace(14.0));
-                
-                if let Some(color) = segment.color {
-                    rich_text = rich_text.color(color);
-                } else {
-                    rich_text = rich_text.color(egui::Color32::BLACK);
-                }
-                
-                if let Some(bg_color) = segment.background_color {
-                    rich_text = rich_text.background_color(bg_color);
+
+    /// 🖱️ 处理鼠标选择
+    fn handle_selection(&mut self, ctx: &egui::Context, gesture: SelectionGesture) {
+        match gesture {
+            SelectionGesture::Start { row, col, mode } => {
+                let (start, end) = self.selection_range(mode, row, col);
+                self.selection = Some(Selection::new(mode, start, end));
+                // 双击/三击没有拖动过程，立即完成
+                if matches!(mode, SelectionMode::Word | SelectionMode::Line) {
+                    self.finish_selection(ctx);
                 }
-                
-                ui.add(egui::Label::new(rich_text).selectable(true));
             }
-            
-            // 在同一行后面添加输入框
-            let response = ui.add(
-                egui::TextEdit::singleline(input_buffer)
-                    .font(egui::FontId::monospace(14.0))
-                    .desired_width(ui.available_width())
-                    .frame(false)
-            );
-            
-            // 🎯 关键修复：使用更可靠的按键检测方式
-            // 强制获取焦点
-            response.request_focus();
-            
-            if response.has_focus() {
-                // 🔑 特殊按键检测（优先级最高）
-                special_key_to_send = Self::detect_special_keys(ui);
-                
-                // 回车键检测
-                let enter_pressed = ui.input(|i| i.key_pressed(egui::Key::Enter));
-                if enter_pressed {
-                    should_execute = true;
-                    crate::app_log!(info, "UI", "🚀 检测到回车键按下！");
+            SelectionGesture::Extend { row, col } => {
+                if let Some(mode) = self.selection.map(|s| s.mode) {
+                    let (start, end) = self.selection_range(mode, row, col);
+                    if let Some(selection) = &mut self.selection {
+                        selection.extend(start, end);
+                    }
                 }
             }
-            
-            // 方法2：检测文本变化中的回车（备用）
-            if response.changed() && input_buffer.ends_with('\n') {
-                input_buffer.pop(); // 移除换行符
-                should_execute = true;
+            SelectionGesture::Finish => self.finish_selection(ctx),
+            SelectionGesture::Clear => self.selection = None,
+        }
+    }
+
+    fn finish_selection(&mut self, ctx: &egui::Context) {
+        if self.copy_on_select {
+            self.copy_selection(ctx);
+        }
+    }
+
+    /// 📋 复制选中的文本（选区可以跨越回滚历史）
+    fn copy_selection(&mut self, ctx: &egui::Context) {
+        let Some(selection) = self.selection.filter(|s| !s.is_empty()) else {
+            return;
+        };
+        let first = selection.lines().start.max(self.terminal_emulator.first_line());
+        let lines = self.terminal_emulator.lines_in(selection.lines());
+        let text = selection.text(&lines, first, self.terminal_emulator.size().1);
+        if !text.is_empty() {
+            crate::app_log!(debug, "UI", "📋 复制 {} 个字符", text.chars().count());
+            ctx.copy_text(text);
+        }
+    }
+
+    /// 视图内的选区（屏幕坐标）
+    fn selection_highlights(&self) -> Vec<Highlight> {
+        let Some(selection) = self.selection else {
+            return Vec::new();
+        };
+        let top = self.line_at_row(0);
+        let cols = self.terminal_emulator.size().1;
+        (0..self.terminal_emulator.screen().len() as u16)
+            .filter_map(|row| {
+                let (start_col, end_col) = selection.columns(top + row as usize, cols)?;
+                Some(Highlight { row, start_col, end_col, kind: HighlightKind::Selection })
+            })
+            .collect()
+    }
+
+    /// 🔗 视图中（行，列）处的链接及其在屏幕上的范围：OSC 8超链接优先，其次从文本中识别
+    fn link_at(&self, row: u16, col: u16) -> Option<(LinkTarget, Vec<Highlight>)> {
+        let uri = self
+            .terminal_emulator
+            .screen()
+            .get(row as usize)?
+            .segments
+            .iter()
+            .find(|s| s.col <= col && col < s.col + s.width)
+            .and_then(|s| s.link.clone());
+        if let Some(uri) = uri {
+            let spans = self
+                .terminal_emulator
+            .screen()
+                .iter()
+                .enumerate()
+                .flat_map(|(row, line)| {
+                    line.segments.iter().filter(|s| s.link.as_ref() == Some(&uri)).map(move |s| Highlight {
+                        row: row as u16,
+                        start_col: s.col,
+                        end_col: s.col + s.width,
+                        kind: HighlightKind::Link,
+                    })
+                })
+                .collect();
+            return Some((LinkTarget::from_uri(&uri), spans));
+        }
+
+        let lines: Vec<_> = (0..self.terminal_emulator.screen().len() as u16)
+            .map(|row| self.terminal_emulator.visible_line_text(row))
+            .collect();
+        let link = detect_link(&lines, row as usize, col)?;
+        let spans = link
+            .spans
+            .iter()
+            .map(|&(row, start_col, end_col)| Highlight {
+                row: row as u16,
+                start_col,
+                end_col,
+                kind: HighlightKind::Link,
+            })
+            .collect();
+        Some((link.target, spans))
+    }
+
+    /// 🔗 打开链接：URL交给系统浏览器，远程路径在文件浏览器中显示
+    fn open_link(&mut self, ctx: &egui::Context, target: LinkTarget) {
+        crate::app_log!(info, "UI", "🔗 打开链接: {}", target);
+        match target {
+            LinkTarget::Url(url) => ctx.open_url(egui::OpenUrl::new_tab(url)),
+            LinkTarget::Path { path, .. } => match self.resolve_remote_path(&path) {
+                Some(path) => self.path_to_reveal = Some(path),
+                None => crate::app_log!(warn, "UI", "无法在文件浏览器中打开 {}：未连接，或shell已切换到其他主机", path),
+            },
+        }
+    }
+
+    /// 远程路径：相对路径按shell当前目录（OSC 7）解析，没有目录信息时相对于登录目录
+    fn resolve_remote_path(&self, path: &str) -> Option<String> {
+        if !self.is_connected {
+            return None;
+        }
+        let directory = match self.working_directory() {
+            // 在终端里ssh到其他主机后，路径不在文件浏览器所连的主机上
+            Some(_) => Some(self.file_browser_directory()?),
+            None => None,
+        };
+        if path.starts_with('/') {
+            return Some(path.to_string());
+        }
+        if let Some(relative) = path.strip_prefix("~/") {
+            return Some(relative.to_string());
+        }
+        let relative = path.trim_start_matches("./");
+        Some(match directory {
+            Some(directory) => format!("{}/{}", directory.trim_end_matches('/'), relative),
+            None => relative.to_string(),
+        })
+    }
+
+    /// 取走等待在文件浏览器中显示的远程路径
+    pub fn take_path_to_reveal(&mut self) -> Option<String> {
+        self.path_to_reveal.take()
+    }
+
+    /// 📋 粘贴：多行内容按设置先确认
+    fn paste(&mut self, text: String) {
+        let text = text.replace("\r\n", "\n");
+        if self.confirm_multiline_paste && text.trim_end_matches('\n').contains('\n') {
+            self.pending_paste = Some(text);
+        } else {
+            self.send_paste(&text);
+        }
+    }
+
+    /// 远端开启括号粘贴模式时用 `ESC[200~ … ESC[201~` 包裹，使shell不会逐行执行
+    fn send_paste(&mut self, text: &str) {
+        let text = text.replace('\n', "\r");
+        if self.terminal_emulator.modes().bracketed_paste {
+            // 去掉内容中的结束标记，防止粘贴内容提前结束括号粘贴
+            let text = text.replace("\x1b[201~", "");
+            self.send_input(&format!("\x1b[200~{}\x1b[201~", text));
+        } else {
+            self.send_input(&text);
+        }
+    }
+
+    /// ⚠️ 多行粘贴确认对话框
+    fn show_paste_confirm(&mut self, ctx: &egui::Context) {
+        let Some(text) = &self.pending_paste else {
+            return;
+        };
+
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("⚠️ 粘贴多行内容")
+            .id(egui::Id::new(("paste_confirm", &self.tab_id)))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("即将粘贴 {} 行内容，每一行都可能被shell立即执行：", text.lines().count()));
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    ui.add(egui::Label::new(egui::RichText::new(text.as_str()).monospace()).wrap());
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("粘贴").clicked() {
+                        confirmed = true;
+                    }
+                    if ui.button("取消").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        if confirmed && let Some(text) = self.pending_paste.take() {
+            self.send_paste(&text);
+        } else if cancelled {
+            self.pending_paste = None;
+        }
+    }
+
+    /// 📋 处理远端的OSC 52请求：按连接的权限直接执行或拒绝，询问模式下留给确认对话框
+    fn handle_clipboard_requests(&mut self, ctx: &egui::Context) {
+        while let Some(request) = self.clipboard_requests.front() {
+            let policy = if request.is_query() && !self.clipboard_read {
+                ClipboardPolicy::Deny
+            } else {
+                self.clipboard_policy
+            };
+            if policy == ClipboardPolicy::Ask {
+                return;
             }
-        });
-        
-        // 🔑 核心新增：检测输入内容变化，实时发送新字符
-        if previous_content != *input_buffer && special_key_to_send.is_none() {
-            // 找出新增的字符
-            if input_buffer.len() > previous_content.len() {
-                let new_chars = &input_buffer[previous_content.len()..];
-                crate::app_log!(debug, "UI", "🔤 检测到新输入字符: {:?}", new_chars);
-                
-                // 实时发送新字符（作为特殊键处理）
-                special_key_to_send = Some(new_chars.to_string());
-                
-                // 🔑 关键修复：实时发送后，清空输入缓冲区，避免重复发送
-                // SSH服务器会回显字符，我们不需要在本地保存
-                input_buffer.clear();
-                crate::app_log!(debug, "UI", "🧹 实时发送后清空输入缓冲区");
-                
-            } else if input_buffer.len() < previous_content.len() {
-                // 检测到删除操作（Backspace）
-                let deleted_count = previous_content.len() - input_buffer.len();
-                crate::app_log!(debug, "UI", "⬅️ 检测到删除操作: {} 个字符", deleted_count);
-                
-                // 发送对应数量的退格键
-                let backspace_chars = "\x08".repeat(deleted_count);
-                special_key_to_send = Some(backspace_chars);
+            if let Some(request) = self.clipboard_requests.pop_front() {
+                self.apply_clipboard_request(ctx, request, policy == ClipboardPolicy::Allow);
             }
         }
-        
-        (should_execute, special_key_to_send)
     }
-    
-    /// 🎯 渲染内嵌式输入行（增强版 - 支持特殊按键处理和实时字符发送）
-    fn render_integrated_input_line_static_enhanced(ui: &mut egui::Ui, current_prompt: &str, input_buffer: &mut String) -> (bool, Option<String>) {
-        crate::app_log!(info, "UI", "📝 render_integrated_input_line_static_enhanced() 被调用");
-        
-        let mut should_execute = false;
-        let mut special_key_to_send = None;
-        
-        // 📝 记录输入前的内容，用于检测变化
-        let previous_content = input_buffer.clone();
-        
+
+    /// 执行或拒绝一个剪贴板请求，都会显示提示，远端改动剪贴板时用户能看到
+    fn apply_clipboard_request(&mut self, ctx: &egui::Context, request: ClipboardRequest, allowed: bool) {
+        let message = match (request, allowed) {
+            (ClipboardRequest::Set(text), true) => {
+                let message = format!("📋 远端程序写入了剪贴板（{} 个字符）", text.chars().count());
+                ctx.copy_text(text);
+                message
+            }
+            (ClipboardRequest::Query { selection }, true) => {
+                self.clipboard_query = Some(selection);
+                self.view.request_clipboard(ctx);
+                "📋 远端程序读取了剪贴板".to_string()
+            }
+            (ClipboardRequest::Set(_), false) => "🚫 已阻止远端程序写入剪贴板".to_string(),
+            (ClipboardRequest::Query { .. }, false) => "🚫 已阻止远端程序读取剪贴板".to_string(),
+        };
+        crate::app_log!(info, "UI", "{}", message);
+        self.toasts.push(ctx, message);
+    }
+
+    /// ⚠️ 询问模式下的剪贴板请求确认对话框
+    fn show_clipboard_confirm(&mut self, ctx: &egui::Context) {
+        let Some(request) = self.clipboard_requests.front() else {
+            return;
+        };
+
+        let mut choice = None;
+        egui::Window::new("📋 远端剪贴板请求")
+            .id(egui::Id::new(("clipboard_confirm", &self.tab_id)))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                match request {
+                    ClipboardRequest::Set(text) => {
+                        ui.label(format!("远端程序请求写入本地剪贴板（{} 个字符）：", text.chars().count()));
+                        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                            ui.add(egui::Label::new(egui::RichText::new(text.as_str()).monospace()).wrap());
+                        });
+                    }
+                    ClipboardRequest::Query { .. } => {
+                        ui.label("远端程序请求读取本地剪贴板的内容，剪贴板中可能有密码等敏感信息。");
+                    }
+                }
+                ui.checkbox(&mut self.remember_clipboard_choice, "本次会话记住选择");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("允许").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("拒绝").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        choice = Some(false);
+                    }
+                });
+            });
+
+        let Some(allowed) = choice else {
+            return;
+        };
+        if std::mem::take(&mut self.remember_clipboard_choice) {
+            self.clipboard_policy = if allowed { ClipboardPolicy::Allow } else { ClipboardPolicy::Deny };
+        }
+        if let Some(request) = self.clipboard_requests.pop_front() {
+            self.apply_clipboard_request(ctx, request, allowed);
+        }
+    }
+
+    fn search_input_id(&self) -> egui::Id {
+        egui::Id::new(("terminal_search", &self.tab_id))
+    }
+
+    /// 🔍 搜索栏：查询框、大小写/正则开关、匹配计数、上一个/下一个
+    fn show_search_bar(&mut self, ui: &mut egui::Ui) {
+        let mut step = None;
+        let mut changed = false;
+        let input_id = self.search_input_id();
+
         ui.horizontal(|ui| {
-            ui.spacing_mut().item_spacing.x = 0.0;
-            
-            ui.add(egui::Label::new(
-                egui::RichText::new(current_prompt)
-                    .font(egui::FontId::monospace(14.0))
-                    .color(egui::Color32::BLUE)
-            ));
-            
-            ui.add(egui::Label::new(
-                egui::RichText::new(" ")
-                    .font(egui::FontId::monospace(14.0))
-            ));
-            
+            ui.label("🔍");
             let response = ui.add(
-                egui::TextEdit::singleline(input_buffer)
-                    .font(egui::FontId::monospace(14.0))
-                    .desired_width(ui.available_width())
-                    .frame(false)
+                egui::TextEdit::singleline(&mut self.search.query)
+                    .id(input_id)
+                    .hint_text("搜索终端输出")
+                    .desired_width(240.0),
             );
-            
-            // 🎯 关键修复：使用更可靠的按键检测方式
-            if response.has_focus() {
-                // 🔑 特殊按键检测（优先级最高）
-                special_key_to_send = Self::detect_special_keys(ui);
-                
-                // 回车键检测
-                let enter_pressed = ui.input(|i| i.key_pressed(egui::Key::Enter));
-                if enter_pressed {
-                    should_execute = true;
-                    crate::app_log!(debug, "UI", "🔑 检测到回车键按下（集成输入行）");
+            changed |= response.changed();
+            if response.lost_focus() {
+                let (enter, escape, shift) =
+                    ui.input(|i| (i.key_pressed(egui::Key::Enter), i.key_pressed(egui::Key::Escape), i.modifiers.shift));
+                if enter {
+                    // Enter下一个，Shift+Enter上一个，焦点留在搜索框
+                    step = Some(!shift);
+                    response.request_focus();
+                } else if escape {
+                    self.search.open = false;
                 }
             }
-            
-            // 方法2：检测文本变化中的回车（备用）
-            if response.changed() && input_buffer.ends_with('\n') {
-                input_buffer.pop(); // 移除换行符
-                should_execute = true;
-                crate::app_log!(debug, "UI", "🔑 通过文本变化检测到回车（集成输入行）");
+
+            changed |= ui
+                .toggle_value(&mut self.search.options.case_sensitive, "Aa")
+                .on_hover_text("区分大小写")
+                .changed();
+            changed |= ui
+                .toggle_value(&mut self.search.options.regex, ".*")
+                .on_hover_text("正则表达式")
+                .changed();
+
+            if let Some(error) = &self.search.error {
+                ui.colored_label(egui::Color32::RED, "正则表达式无效").on_hover_text(error);
+            } else if !self.search.query.is_empty() {
+                match self.search.current {
+                    Some(index) => ui.label(format!("{}/{}", index + 1, self.search.matches.len())),
+                    None => ui.label("无匹配"),
+                };
             }
-            
-            // 自动获取焦点
-            if !response.has_focus() {
-                response.request_focus();
+
+            if ui.button("⬆").on_hover_text("上一个 (Shift+Enter)").clicked() {
+                step = Some(false);
+            }
+            if ui.button("⬇").on_hover_text("下一个 (Enter)").clicked() {
+                step = Some(true);
+            }
+            if ui.button("✖").on_hover_text("关闭 (Esc)").clicked() {
+                self.search.open = false;
             }
         });
-        
-        // 🔑 核心新增：检测输入内容变化，实时发送新字符
-        if previous_content != *input_buffer && special_key_to_send.is_none() {
-            // 找出新增的字符
-            if input_buffer.len() > previous_content.len() {
-                let new_chars = &input_buffer[previous_content.len()..];
-                crate::app_log!(debug, "UI", "🔤 检测到新输入字符: {:?}", new_chars);
-                
-                // 实时发送新字符（作为特殊键处理）
-                special_key_to_send = Some(new_chars.to_string());
-                
-                // 🔑 关键修复：实时发送后，清空输入缓冲区，避免重复发送
-                // SSH服务器会回显字符，我们不需要在本地保存
-                input_buffer.clear();
-                crate::app_log!(debug, "UI", "🧹 实时发送后清空输入缓冲区");
-                
-            } else if input_buffer.len() < previous_content.len() {
-                // 检测到删除操作（Backspace）
-                let deleted_count = previous_content.len() - input_buffer.len();
-                crate::app_log!(debug, "UI", "⬅️ 检测到删除操作: {} 个字符", deleted_count);
-                
-                // 发送对应数量的退格键
-                let backspace_chars = "\x08".repeat(deleted_count);
-                special_key_to_send = Some(backspace_chars);
+
+        if !self.search.open {
+            ui.memory_mut(|m| m.surrender_focus(input_id));
+            return;
+        }
+        if changed || self.search_dirty {
+            self.search_dirty = false;
+            self.refresh_search(changed);
+            // 有新输出时只更新匹配，不移动视图
+            if changed {
+                self.reveal_search_match();
             }
         }
-        
-        (should_execute, special_key_to_send)
+        if let Some(forward) = step {
+            self.search.step(forward);
+            self.reveal_search_match();
+        }
     }
 
-    /// 🔑 新增：特殊按键检测方法
-    fn detect_special_keys(ui: &mut egui::Ui) -> Option<String> {
-        ui.input(|i| {
-            // Tab 键 - 自动补全
-            if i.key_pressed(egui::Key::Tab) {
-                crate::app_log!(debug, "UI", "🎯 检测到Tab键");
-                return Some("\t".to_string());
-            }
-            
-            // 方向键 - 光标移动和历史记录
-            if i.key_pressed(egui::Key::ArrowUp) {
-                crate::app_log!(debug, "UI", "⬆️ 检测到上箭头键");
-                return Some("\x1b[A".to_string()); // ANSI 上箭头序列
-            }
-            if i.key_pressed(egui::Key::ArrowDown) {
-                crate::app_log!(debug, "UI", "⬇️ 检测到下箭头键");
-                return Some("\x1b[B".to_string()); // ANSI 下箭头序列
-            }
-            if i.key_pressed(egui::Key::ArrowLeft) {
-                crate::app_log!(debug, "UI", "⬅️ 检测到左箭头键");
-                return Some("\x1b[D".to_string()); // ANSI 左箭头序列
+    /// 🔍 重新搜索：查询变化时全部重搜；有新输出时只搜索屏幕上和新滚入历史的行，避免每帧扫描整个回滚历史
+    fn refresh_search(&mut self, query_changed: bool) {
+        let emulator = &mut self.terminal_emulator;
+        let first = emulator.first_line();
+        let stable_until = first + emulator.history_len();
+        let alternate = emulator.modes().alternate_screen;
+        match self.search.rescan_from(first, alternate).filter(|_| !query_changed) {
+            Some(from) => {
+                let lines = emulator.lines_in(from..usize::MAX);
+                self.search.update(&lines, from, first, stable_until, alternate);
             }
-            if i.key_pressed(egui::Key::ArrowRight) {
-                crate::app_log!(debug, "UI", "➡️ 检测到右箭头键");
-                return Some("\x1b[C".to_string()); // ANSI 右箭头序列
+            None => {
+                let lines = emulator.searchable_lines();
+                self.search.refresh(&lines, first, stable_until, alternate);
             }
-            
-            // Home/End 键
-            if i.key_pressed(egui::Key::Home) {
-                crate::app_log!(debug, "UI", "🏠 检测到Home键");
-                return Some("\x1b[H".to_string()); // ANSI Home序列
-            }
-            if i.key_pressed(egui::Key::End) {
-                crate::app_log!(debug, "UI", "🏁 检测到End键");
-                return Some("\x1b[F".to_string()); // ANSI End序列
-            }
-            
-            // Page Up/Down 键
-            if i.key_pressed(egui::Key::PageUp) {
-                crate::app_log!(debug, "UI", "🔼 检测到PageUp键");
-                return Some("\x1b[5~".to_string()); // ANSI PageUp序列
-            }
-            if i.key_pressed(egui::Key::PageDown) {
-                crate::app_log!(debug, "UI", "🔽 检测到PageDown键");
-                return Some("\x1b[6~".to_string()); // ANSI PageDown序列
-            }
-            
-            // Delete/Backspace 键
-            if i.key_pressed(egui::Key::Delete) {
-                crate::app_log!(debug, "UI", "🗑️ 检测到Delete键");
-                return Some("\x1b[3~".to_string()); // ANSI Delete序列
-            }
-            
-            // Ctrl 组合键
-            if i.modifiers.ctrl {
-                if i.key_pressed(egui::Key::C) {
-                    crate::app_log!(debug, "UI", "⚠️ 检测到Ctrl+C");
-                    return Some("\x03".to_string()); // Ctrl+C 中断信号
-                }
-                if i.key_pressed(egui::Key::D) {
-                    crate::app_log!(debug, "UI", "📝 检测到Ctrl+D");
-                    return Some("\x04".to_string()); // Ctrl+D EOF信号
+        }
+    }
+
+    /// 当前匹配不在视图内时，回滚到使其位于屏幕中部
+    fn reveal_search_match(&mut self) {
+        if let Some(found) = self.search.current_match() {
+            self.reveal_line(found.line);
+        }
+    }
+
+    /// 第line行不在视图内时，回滚到使其位于屏幕中部
+    fn reveal_line(&mut self, line: usize) {
+        let rows = self.terminal_emulator.size().0 as usize;
+        let top = self.terminal_emulator.top_line();
+        if (top..top + rows).contains(&line) {
+            return;
+        }
+        // 回到底部时视图第一行的行号
+        let bottom_top = top + self.terminal_emulator.scroll_offset();
+        self.terminal_emulator.scroll_to((bottom_top + rows / 2).saturating_sub(line));
+    }
+
+    /// 🧩 Shell集成：命令块面板开关，向当前会话注入或复制bash/zsh集成脚本
+    fn show_shell_integration_menu(&mut self, ui: &mut egui::Ui) {
+        let blocks = self.terminal_emulator.command_blocks();
+        if blocks.is_active() {
+            let count = blocks.blocks().iter().filter(|b| b.has_command()).count();
+            ui.toggle_value(&mut self.show_blocks, format!("🧱 命令块 ({})", count));
+        }
+        ui.menu_button("🧩 Shell集成", |ui| {
+            for (shell, script) in [("bash", BASH_INTEGRATION), ("zsh", ZSH_INTEGRATION)] {
+                if ui.add_enabled(self.is_connected, egui::Button::new(format!("注入 {} 集成到当前会话", shell))).clicked() {
+                    crate::app_log!(info, "UI", "🧩 注入{}集成脚本", shell);
+                    self.send_input(&injection_command(script));
+                    ui.close();
                 }
-                if i.key_pressed(egui::Key::Z) {
-                    crate::app_log!(debug, "UI", "⏸️ 检测到Ctrl+Z");
-                    return Some("\x1a".to_string()); // Ctrl+Z 暂停信号
+                if ui.button(format!("复制 {} 集成脚本（追加到 ~/.{}rc 永久生效）", shell, shell)).clicked() {
+                    ui.ctx().copy_text(format!("{}\n", script));
+                    ui.close();
                 }
             }
-            
-            None
-        })
+            ui.weak("集成后按OSC 133标记划分命令块；没有标记时按光标所在行推测提示符");
+            ui.separator();
+            if ui.add_enabled(self.is_connected, egui::Button::new("注入目录跟踪到当前会话")).clicked() {
+                crate::app_log!(info, "UI", "📂 注入目录跟踪脚本");
+                self.send_input(&injection_command(CWD_HOOK));
+                ui.close();
+            }
+            if ui.button("复制目录跟踪脚本（bash/zsh通用）").clicked() {
+                ui.ctx().copy_text(format!("{}\n", CWD_HOOK));
+                ui.close();
+            }
+            ui.weak("目录跟踪通过OSC 7报告当前目录，文件浏览器和状态栏随之切换");
+        });
     }
-    fn execute_command(&mut self) {
-        crate::app_log!(debug, "UI", "🎯 execute_command 被调用，输入缓冲区内容: '{}'", self.input_buffer);
-        
-        // 🔑 关键变化：实时字符发送模式下，输入缓冲区可能为空
-        // 因为所有字符都已经实时发送了，这里只需要发送回车符
-        
-        if self.is_connected {
-            if let (Some(ssh_manager), Some(tab_id)) = (&mut self.ssh_manager, &self.tab_id) {
-                // 🔑 方案1：如果输入缓冲区不为空，说明是旧模式，发送完整命令
-                if !self.input_buffer.trim().is_empty() {
-                    let command = self.input_buffer.clone();
-                    self.input_buffer.clear();
-                    
-                    crate::app_log!(info, "UI", "📝 旧模式：发送完整命令: '{}'", command.trim());
-                    
-                    if command.trim() == "clear" {
-                        self.output_buffer.clear();
-                        crate::app_log!(info, "UI", "🧹 执行本地clear命令");
-                        return;
-                    }
-                    
-                    match ssh_manager.execute_command(tab_id, command.trim()) {
-                        Ok(_) => {
-                            crate::app_log!(info, "UI", "✅ 命令发送成功: {}", command.trim());
+
+    /// 🧱 命令块面板：最新的在最上面，可展开查看输出、复制输出、定位到终端中的位置
+    fn show_blocks_panel(&mut self, ui: &mut egui::Ui) {
+        let rect = ui.max_rect();
+        ui.painter().vline(rect.left(), rect.y_range(), ui.visuals().widgets.noninteractive.bg_stroke);
+
+        let mut copy = None;
+        let mut reveal = None;
+        egui::ScrollArea::vertical()
+            .id_salt(("command_blocks", &self.tab_id))
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                let blocks = self.terminal_emulator.command_blocks_mut().blocks_mut();
+                for block in blocks.iter_mut().rev().filter(|b| b.has_command()) {
+                    ui.horizontal(|ui| {
+                        let icon = if block.collapsed { "▶" } else { "▼" };
+                        if ui.small_button(icon).on_hover_text("展开/折叠输出").clicked() {
+                            block.collapsed = !block.collapsed;
                         }
-                        Err(e) => {
-                            crate::app_log!(error, "UI", "❌ 命令发送失败: {}", e);
-                            self.insert_text(format!("命令执行失败: {}", e));
+                        ui.colored_label(Self::block_status(block).color(), block.status_label());
+                    });
+                    ui.add(egui::Label::new(egui::RichText::new(&block.command).monospace()).truncate());
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!block.is_running(), egui::Button::new("📋 复制输出").small()).clicked() {
+                            copy = Some(block.output.clone());
                         }
-                    }
-                } else {
-                    // 🔑 方案2：输入缓冲区为空，说明是实时模式，只发送回车符
-                    crate::app_log!(info, "UI", "🔄 实时模式：发送回车符");
-                    
-                    match ssh_manager.send_raw(tab_id, "\n") {
-                        Ok(_) => {
-                            crate::app_log!(info, "UI", "✅ 回车符发送成功");
+                        if ui.small_button("📍 定位").clicked() {
+                            reveal = Some(block.prompt_start.0);
                         }
-                        Err(e) => {
-                            crate::app_log!(error, "UI", "❌ 回车符发送失败: {}", e);
+                    });
+                    if !block.collapsed {
+                        if block.is_running() {
+                            ui.weak("命令执行中…");
+                        } else if block.output.is_empty() {
+                            ui.weak("（无输出）");
+                        } else {
+                            let total = block.output.lines().count();
+                            let preview: Vec<_> = block.output.lines().take(BLOCK_PREVIEW_LINES).collect();
+                            ui.add(egui::Label::new(egui::RichText::new(preview.join("\n")).monospace()).wrap());
+                            if total > BLOCK_PREVIEW_LINES {
+                                ui.weak(format!("… 共 {} 行，完整内容请复制", total));
+                            }
                         }
                     }
+                    ui.separator();
                 }
-            } else {
-                self.insert_text("错误: SSH连接不存在".to_string());
-            }
-        } else {
-            crate::app_log!(error, "UI", "❌ 连接状态: 未连接");
-            self.insert_text("错误: 未连接到远程主机".to_string());
+            });
+
+        if let Some(text) = copy {
+            ui.ctx().copy_text(text);
+        }
+        if let Some(line) = reveal {
+            self.reveal_line(line);
         }
-        
-        // 清空输入缓冲区（防止遭留）
-        self.input_buffer.clear();
-        self.scroll_to_bottom = true;
     }
-    
-    /// 🎯 新增：发送特殊按键序列（统一的特殊按键处理通道）
-    fn send_special_key(&mut self, key_sequence: &str) {
-        if !self.is_connected {
-            crate::app_log!(warn, "UI", "⚠️ 未连接，无法发送特殊按键");
-            return;
+
+    fn block_status(block: &CommandBlock) -> BlockStatus {
+        if block.is_running() {
+            return BlockStatus::Running;
         }
-        
-        if let (Some(ssh_manager), Some(tab_id)) = (&mut self.ssh_manager, &self.tab_id) {
-            match ssh_manager.send_raw(tab_id, key_sequence) {
-                Ok(_) => {
-                    // 根据不同的按键类型记录不同的日志
-                    match key_sequence {
-                        "\t" => crate::app_log!(debug, "UI", "🎯 Tab补全发送成功"),
-                        "\x1b[A" => crate::app_log!(debug, "UI", "⬆️ 上箭头发送成功"),
-                        "\x1b[B" => crate::app_log!(debug, "UI", "⬇️ 下箭头发送成功"),
-                        "\x1b[C" => crate::app_log!(debug, "UI", "➡️ 右箭头发送成功"),
-                        "\x1b[D" => crate::app_log!(debug, "UI", "⬅️ 左箭头发送成功"),
-                        "\x1b[H" => crate::app_log!(debug, "UI", "🏠 Home键发送成功"),
-                        "\x1b[F" => crate::app_log!(debug, "UI", "🏁 End键发送成功"),
-                        "\x1b[5~" => crate::app_log!(debug, "UI", "🔼 PageUp发送成功"),
-                        "\x1b[6~" => crate::app_log!(debug, "UI", "🔽 PageDown发送成功"),
-                        "\x1b[3~" => crate::app_log!(debug, "UI", "🗑️ Delete发送成功"),
-                        "\x03" => crate::app_log!(debug, "UI", "⚠️ Ctrl+C中断信号发送成功"),
-                        "\x04" => crate::app_log!(debug, "UI", "📝 Ctrl+D EOF信号发送成功"),
-                        "\x1a" => crate::app_log!(debug, "UI", "⏸️ Ctrl+Z暂停信号发送成功"),
-                        s if s.starts_with("\x08") => {
-                            let count = s.len();
-                            crate::app_log!(debug, "UI", "⬅️ 退格键发送成功: {} 个", count);
-                        },
-                        s if s.chars().all(|c| c.is_ascii_graphic() || c.is_ascii_whitespace()) => {
-                            // 普通字符（实时输入）
-                            crate::app_log!(debug, "UI", "🔤 实时字符发送成功: {:?}", s);
-                        },
-                        _ => crate::app_log!(debug, "UI", "🔑 特殊按键发送成功: {:?}", key_sequence),
-                    }
-                }
-                Err(e) => {
-                    crate::app_log!(error, "UI", "❌ 特殊按键发送失败: {:?}, 错误: {}", key_sequence, e);
-                }
-            }
-        } else {
-            crate::app_log!(error, "UI", "❌ SSH管理器或Tab ID不存在，无法发送特殊按键");
+        match block.succeeded() {
+            Some(true) => BlockStatus::Success,
+            Some(false) => BlockStatus::Failure,
+            None => BlockStatus::Unknown,
         }
     }
 
-
-
-    /// 🔑 核心方法：终端内容插入（唯一插入接口）
-    fn insert_line(&mut self, line: TerminalLine) {
-        self.output_buffer.push_back(line);
-        
-        // 限制缓冲区大小
-        while self.output_buffer.len() > 1000 {
-            self.output_buffer.pop_front();
+    /// 视图内的命令块（屏幕坐标）
+    fn block_markers(&self) -> Vec<BlockMarker> {
+        if !self.terminal_emulator.command_blocks().is_active() || self.terminal_emulator.modes().alternate_screen {
+            return Vec::new();
         }
-        
-        self.scroll_to_bottom = true;
+        let top = self.line_at_row(0);
+        let bottom = top + self.terminal_emulator.screen().len();
+        let cursor_line = self.terminal_emulator.cursor_line();
+        self.terminal_emulator
+            .command_blocks()
+            .blocks()
+            .iter()
+            .filter(|b| b.has_command())
+            .filter_map(|block| {
+                let start = block.prompt_start.0;
+                // D标记通常位于输出之后新一行的开头；执行中的块延伸到光标所在行
+                let end = match block.end {
+                    Some((line, 0)) => line,
+                    Some((line, _)) => line + 1,
+                    None => cursor_line + 1,
+                };
+                let (first, last) = (start.max(top), end.min(bottom));
+                (first < last).then(|| BlockMarker {
+                    start_row: (first - top) as u16,
+                    end_row: (last - top) as u16,
+                    header: start >= top,
+                    status: Self::block_status(block),
+                    label: block.status_label(),
+                })
+            })
+            .collect()
     }
-    
-    /// 手动插入文本（不经过VT100）
-    fn insert_text(&mut self, text: String) {
-        let mut line = TerminalLine::new();
-        line.segments.push(crate::ui::terminal::TerminalSegment {
-            text,
-            color: Some(egui::Color32::BLACK),
-            background_color: None,
-            bold: false,
-            italic: false,
-            underline: false,
-            inverse: false,
-        });
-        
-        self.insert_line(line);
+
+    /// 视图内的搜索匹配（屏幕坐标）
+    fn search_highlights(&self) -> Vec<Highlight> {
+        if !self.search.open || self.search.matches.is_empty() {
+            return Vec::new();
+        }
+        let top = self.terminal_emulator.top_line();
+        let rows = self.terminal_emulator.screen().len();
+        self.search
+            .matches
+            .iter()
+            .enumerate()
+            .filter(|(_, found)| (top..top + rows).contains(&found.line))
+            .map(|(index, found)| Highlight {
+                row: (found.line - top) as u16,
+                start_col: found.start_col,
+                end_col: found.end_col,
+                kind: if self.search.current == Some(index) {
+                    HighlightKind::CurrentMatch
+                } else {
+                    HighlightKind::Match
+                },
+            })
+            .collect()
     }
 
-    /// SSH数据处理入口：VT100解析 + 屏幕状态更新（修复版）
-    pub fn process_ssh_data(&mut self, data: String) {
-        // 🔍 打印SSH返回的原文
-        crate::app_log!(info, "SSH_RAW", "📥 SSH原文: {:?}", data);
-        
-        // 🔑 关键：VT100解析在这里完成
-        let result = self.terminal_emulator.process_pty_output(&data);
-        
-        // 🎯 关键修复：直接使用VT100屏幕状态，不做增量处理
-        self.output_buffer.clear();
-        for line in result.lines {
-            // 🔑 重要：保留所有行，包括空行（VT100屏幕状态是完整的）
-            self.output_buffer.push_back(line);
+    /// ⌨️ 把按键/粘贴内容原样发往PTY（回显由远端负责）
+    fn send_input(&mut self, data: &str) {
+        if !self.is_connected {
+            return;
         }
-        
-        // 更新提示符
-        if let Some(prompt) = result.prompt_update {
-            if !prompt.trim().is_empty() && !prompt.contains("Last login") {
-                self.current_prompt = prompt.trim().to_string();
+        if let (Some(ssh_manager), Some(tab_id)) = (&self.ssh_manager, &self.tab_id) {
+            match ssh_manager.send_raw(tab_id, data) {
+                Ok(_) => crate::app_log!(debug, "UI", "⌨️ 发送输入: {:?}", data),
+                Err(e) => crate::app_log!(error, "UI", "❌ 输入发送失败: {:?}, 错误: {}", data, e),
             }
         }
-        
-        self.scroll_to_bottom = true;
-        crate::app_log!(debug, "UI", "📺 VT100屏幕状态更新完成: {} 行", self.output_buffer.len());
     }
-    
-    /// 检测是否在全屏应用模式（如vim、nano等）
-    fn is_in_fullscreen_app(&self, lines: &[TerminalLine]) -> bool {
-        // 🔑 改进的检测逻辑：基于终端内容的特征来判断
-        
-        // 如果行数很少（≤3行），通常不是全屏应用
-        if lines.len() <= 3 {
-            return false;
+
+    /// 🖱️ 发送鼠标报告等不经过字符编码转换的字节
+    fn send_bytes(&mut self, data: Vec<u8>) {
+        if !self.is_connected {
+            return;
         }
-        
-        // 检查是否有明显的全屏应用特征
-        for line in lines {
-            let text = line.text();
-            
-            // 常见的全屏应用特征
-            if text.contains("~") && text.contains("VIM") { // vim界面
-                return true;
-            }
-            if text.contains("GNU nano") { // nano编辑器
-                return true;
-            }
-            if text.contains("File:") && text.contains("Modified") { // 编辑器状态
-                return true;
-            }
-            
-            // 如果有明显的终端提示符，说明不是全屏应用
-            if text.contains("➜") || text.contains("$") || text.contains("#") {
-                return false;
-            }
+        if let (Some(ssh_manager), Some(tab_id)) = (&self.ssh_manager, &self.tab_id)
+            && let Err(e) = ssh_manager.send_bytes(tab_id, data)
+        {
+            crate::app_log!(error, "UI", "❌ 鼠标报告发送失败: {}", e);
         }
+    }
+
+    /// 📂 远端shell报告的当前目录
+    pub fn working_directory(&self) -> Option<&WorkingDirectory> {
+        self.terminal_emulator.working_directory()
+    }
+
+    /// 文件浏览器应跟随的目录：只跟随会话所在主机，在终端里再ssh到其他主机后不跟随
+    pub fn file_browser_directory(&self) -> Option<&str> {
+        self.working_directory()
+            .filter(|directory| self.session_host.as_ref() == Some(&directory.host))
+            .map(|directory| directory.path.as_str())
+    }
+
+    /// 把路径插入命令行（按需加引号，后跟一个空格，不回车）
+    pub fn insert_path(&mut self, path: &str) {
+        self.send_input(&format!("{} ", shell_quote(path)));
+    }
+
+    /// 插入一行本地文本（如连接错误），经过VT100写到当前光标处
+    pub fn insert_text(&mut self, text: String) {
+        self.terminal_emulator.feed_text(&format!("{}\r\n", text.replace('\n', "\r\n")));
+        self.handle_screen_update();
+    }
+
+    /// 经过VT100插入一行提示（如重连分隔线），与远端输出一起保留在回滚历史中
+    pub fn print_notice(&mut self, text: &str) {
+        self.terminal_emulator.feed_text(&format!("\r\n\x1b[33m{}\x1b[0m\r\n", text));
+        self.handle_screen_update();
+    }
+
+    /// SSH数据处理入口：按连接编码解码后交给终端模拟器
+    pub fn process_ssh_data(&mut self, data: &[u8]) {
+        // 🔍 只记录字节数：大量输出（如cat大文件）时逐块打印原文会占满CPU和日志文件
+        crate::app_log!(debug, "SSH_RAW", "📥 SSH输出: {} 字节", data.len());
         
-        // 检查最后一行是否像提示符
-        if let Some(last_line) = lines.last() {
-            let last_text = last_line.text();
-            // 如果最后一行包含提示符特征，不是全屏应用
-            if last_text.contains("➜") || 
-               last_text.contains("$") || 
-               last_text.contains("#") ||
-               last_text.starts_with('(') { // conda环境等
-                return false;
-            }
+        // 🔑 关键：VT100解析在这里完成，不完整的多字节字符留到下一批
+        self.terminal_emulator.feed(data);
+        self.activity = true;
+        self.handle_screen_update();
+    }
+
+    /// 终端有新输出后：重新搜索，收集剪贴板请求，记下会话主机
+    fn handle_screen_update(&mut self) {
+        self.search_dirty = true;
+        self.clipboard_requests.extend(self.terminal_emulator.take_clipboard_requests());
+        if self.session_host.is_none()
+            && let Some(directory) = self.terminal_emulator.working_directory()
+        {
+            self.session_host = Some(directory.host.clone());
         }
         
-        // 默认不是全屏应用
-        false
+        crate::app_log!(debug, "UI", "📺 VT100屏幕状态更新完成: {} 行", self.terminal_emulator.screen().len());
     }
 }
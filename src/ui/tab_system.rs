@@ -1,10 +1,22 @@
 use eframe::egui;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, AppSettings};
+use crate::plugins::file_browser::RemoteTarget;
 use crate::ssh::Ssh2Manager;
+use crate::ssh::ssh2_client::SessionEnd;
+use crate::ssh::auth_prompt::{AuthPrompt, AuthPrompter};
+use crate::ssh::known_hosts::{self, HostKeyError, HostKeyInfo};
+use crate::ui::auth_dialog::AuthDialog;
+use crate::ui::fonts::{self, SystemFonts, MAX_FONT_SIZE, MIN_FONT_SIZE};
+use crate::ui::port_forward_panel::PortForwardPanel;
+use crate::ui::terminal::theme::{TerminalTheme, ThemeLibrary};
+use crate::ui::toast::Toasts;
 use crate::ui::{ConnectionConfig, ConnectionManager, PluginsPanel, SimpleTerminalPanel};
 
 /// Tab系统的核心trait - Strategy Pattern
@@ -32,6 +44,7 @@ pub struct TabContext {
     pub config: AppConfig,
     pub connection_manager: ConnectionManager,
     pub plugins_panel: PluginsPanel,
+    pub themes: ThemeLibrary, // 内置和导入的终端配色方案
     pub pending_connection: Option<ConnectionConfig>, // 新增：待处理的连接请求
 }
 
@@ -66,6 +79,8 @@ impl TabContent for WelcomeTab {
                 ui.set_width(ui.available_width() * 0.4);
                 ui.heading("🖥️ 系统监控");
                 context.plugins_panel.show(ui);
+                // 欢迎页没有命令行，丢弃插入路径的请求
+                context.plugins_panel.take_path_to_insert();
             });
 
             ui.separator();
@@ -84,7 +99,7 @@ impl TabContent for WelcomeTab {
                         egui::ScrollArea::vertical()
                             .max_height(380.0) // 留一点边距
                             .show(ui, |ui| {
-                                if let Some(connection_config) = context.connection_manager.show(ui, &mut context.config) {
+                                if let Some(connection_config) = context.connection_manager.show(ui, &mut context.config, &context.themes) {
                                     // 将连接请求存储到上下文中，TabManager会处理它
                                     crate::app_log!(info, "Tab", "请求创建新的终端连接: {}@{}", 
                                         connection_config.username, connection_config.host);
@@ -114,12 +129,56 @@ impl TabContent for WelcomeTab {
     }
 }
 
+/// 自动重连的最大尝试次数
+const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+
+/// 连接过程中检查认证提示和连接结果的间隔
+const CONNECT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Tab标题最多显示的字符数（远端标题可能很长，如完整路径）
+const MAX_TITLE_CHARS: usize = 40;
+
+/// 🔌 终端Tab的连接状态机
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    /// 尚未连接（或用户取消了连接）
+    Idle,
+    Connecting,
+    Connected,
+    /// 连接中断后自动重连；retry_at为None表示本次尝试正在进行
+    Reconnecting { attempt: u32, retry_at: Option<Instant> },
+    Failed(String),
+    /// 远端正常结束了会话，不自动重连
+    Closed,
+}
+
+impl ConnectionState {
+    /// 第attempt次重连前的等待时间：1s、2s、4s…，最长60s
+    fn backoff(attempt: u32) -> Duration {
+        Duration::from_secs((1u64 << attempt.saturating_sub(1).min(6)).min(60))
+    }
+}
+
 /// 终端Tab - 包装SimpleTerminalPanel
 pub struct TerminalTab {
     id: String,
     title: String,
     terminal: SimpleTerminalPanel,
     connection_config: Option<ConnectionConfig>,
+    jump_chain: Vec<ConnectionConfig>, // 已解析的跳板机链（按连接顺序）
+    ssh_manager: Option<Arc<Ssh2Manager>>,
+    pending_host_key: Option<HostKeyInfo>, // 等待用户确认的主机指纹（首次连接）
+    connect_result: Option<Receiver<anyhow::Result<()>>>, // 后台连接线程的结果
+    auth_prompts: Option<Receiver<AuthPrompt>>,           // 连接线程发来的认证提示
+    auth_dialog: AuthDialog,
+    port_forward_panel: PortForwardPanel,
+    show_file_panel: bool, // 右侧显示远程文件浏览器
+    state: ConnectionState,
+    connected_once: bool, // 曾经连接成功过，之后的成功连接都算重连
+    followed_directory: Option<String>, // 文件浏览器最近一次跟随的终端目录
+    custom_title: Option<String>, // 用户重命名的标题，优先于远端标题
+    bell: bool,     // 在后台时响过铃
+    activity: bool, // 在后台时有新输出
 }
 
 impl TerminalTab {
@@ -129,6 +188,20 @@ impl TerminalTab {
             title: title.clone(),
             terminal: SimpleTerminalPanel::new(title, "未连接".to_string()),
             connection_config: None,
+            jump_chain: Vec::new(),
+            ssh_manager: None,
+            pending_host_key: None,
+            connect_result: None,
+            auth_prompts: None,
+            auth_dialog: AuthDialog::new(),
+            port_forward_panel: PortForwardPanel::new(),
+            show_file_panel: false,
+            state: ConnectionState::Idle,
+            connected_once: false,
+            followed_directory: None,
+            custom_title: None,
+            bell: false,
+            activity: false,
         }
     }
 
@@ -147,20 +220,428 @@ impl TerminalTab {
             title,
             terminal,
             connection_config: Some(connection_config),
+            jump_chain: Vec::new(),
+            ssh_manager: None,
+            pending_host_key: None,
+            connect_result: None,
+            auth_prompts: None,
+            auth_dialog: AuthDialog::new(),
+            port_forward_panel: PortForwardPanel::new(),
+            show_file_panel: false,
+            state: ConnectionState::Idle,
+            connected_once: false,
+            followed_directory: None,
+            custom_title: None,
+            bell: false,
+            activity: false,
         }
     }
 
     pub fn get_connection_config(&self) -> Option<&ConnectionConfig> {
         self.connection_config.as_ref()
     }
+
+    /// 🔗 设置跳板机链，Tab标题显示完整的跳转路径
+    pub fn set_jump_chain(&mut self, jump_chain: Vec<ConnectionConfig>) {
+        if let Some(config) = &self.connection_config {
+            self.title = config.chain_label(&jump_chain);
+        }
+        self.jump_chain = jump_chain;
+    }
+
+    /// 设置SSH管理器（终端面板和Tab共用同一个管理器）
+    pub fn set_ssh_manager(&mut self, ssh_manager: Arc<Ssh2Manager>) {
+        self.terminal.set_ssh_manager(Arc::clone(&ssh_manager), self.id.clone());
+        self.ssh_manager = Some(ssh_manager);
+    }
+
+    /// 🔑 在后台线程建立SSH连接，认证提示通过通道交给UI显示
+    pub fn connect(&mut self) {
+        let (Some(ssh_manager), Some(config)) = (&self.ssh_manager, &self.connection_config) else {
+            return;
+        };
+
+        let (prompt_tx, prompt_rx) = mpsc::channel();
+        let (result_tx, result_rx) = mpsc::channel();
+        let ssh_manager = Arc::clone(ssh_manager);
+        let config = config.clone();
+        let jump_chain = self.jump_chain.clone();
+        let id = self.id.clone();
+
+        self.terminal.connection_info = format!("正在连接到 {}:{}...", 
+            config.chain_label(&jump_chain), config.port);
+        self.terminal.set_encoding(config.encoding);
+        self.terminal.set_clipboard_policy(config.clipboard_policy, config.clipboard_read);
+        self.state = match self.state {
+            ConnectionState::Reconnecting { attempt, .. } => ConnectionState::Reconnecting { attempt, retry_at: None },
+            _ => ConnectionState::Connecting,
+        };
+
+        thread::spawn(move || {
+            let result = ssh_manager.create_connection(
+                id.clone(),
+                &config,
+                jump_chain,
+                Some(AuthPrompter::new(prompt_tx)),
+            );
+            // Tab已关闭：丢弃刚建立的连接
+            if result_tx.send(result).is_err() {
+                let _ = ssh_manager.disconnect(&id);
+            }
+        });
+
+        self.connect_result = Some(result_rx);
+        self.auth_prompts = Some(prompt_rx);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.terminal.is_connected
+    }
+
+    /// 📁 文件浏览器跟随本Tab时使用的远程目标
+    pub fn remote_target(&self) -> Option<RemoteTarget> {
+        let ssh_manager = self.ssh_manager.as_ref().filter(|_| self.is_connected())?;
+        Some(RemoteTarget {
+            ssh_manager: Arc::clone(ssh_manager),
+            session_id: self.id.clone(),
+            label: self.title.clone(),
+        })
+    }
+
+    /// 📂 终端工作目录变化后返回新目录，每次变化只返回一次
+    pub fn take_directory_change(&mut self) -> Option<String> {
+        let directory = self.terminal.file_browser_directory()?;
+        if self.followed_directory.as_deref() == Some(directory) {
+            return None;
+        }
+        self.followed_directory = Some(directory.to_string());
+        self.followed_directory.clone()
+    }
+
+    /// 每帧推进连接状态机（后台Tab同样需要检测断线和重连），并读取终端输出
+    pub fn poll(&mut self, ctx: &egui::Context, active: bool, settings: &AppSettings) {
+        self.poll_connection();
+        // 连接线程没有UI句柄：连接过程中定时检查认证提示和连接结果
+        if self.connect_result.is_some() {
+            ctx.request_repaint_after(CONNECT_POLL_INTERVAL);
+        }
+        self.poll_session(ctx);
+        self.terminal.receive_ssh_output(ctx);
+        self.poll_terminal_events(ctx, active, settings);
+    }
+
+    /// 🔔 响铃、新输出和命令结束：后台Tab显示标记，长命令结束时发送桌面通知
+    fn poll_terminal_events(&mut self, ctx: &egui::Context, active: bool, settings: &AppSettings) {
+        let bell = self.terminal.take_bell();
+        let activity = self.terminal.take_activity();
+        let finished = self.terminal.take_finished_commands();
+        if active {
+            self.bell = false;
+            self.activity = false;
+            if bell && settings.visual_bell {
+                self.terminal.flash(ctx);
+            }
+            return;
+        }
+
+        self.bell |= bell;
+        self.activity |= activity;
+        if !settings.notify_long_commands {
+            return;
+        }
+        let threshold = Duration::from_secs(settings.long_command_secs);
+        for block in finished.iter().filter(|block| block.duration.is_some_and(|d| d >= threshold)) {
+            crate::app_log!(info, "Tab", "🔔 后台命令结束: {} ({})", block.command, block.status_label());
+            crate::utils::notify::desktop_notification(
+                &self.display_title(),
+                &format!("{}\n{}", block.command, block.status_label()),
+            );
+            ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+                egui::UserAttentionType::Informational,
+            ));
+        }
+    }
+
+    /// 显示的标题：用户重命名的标题 → 远端通过OSC 0/2设置的标题 → 连接名称
+    fn display_title(&self) -> String {
+        let remote = self.terminal.remote_title().trim();
+        let title = match &self.custom_title {
+            Some(title) => title.as_str(),
+            None if !remote.is_empty() => remote,
+            None => &self.title,
+        };
+        if title.chars().count() > MAX_TITLE_CHARS {
+            format!("{}…", title.chars().take(MAX_TITLE_CHARS - 1).collect::<String>())
+        } else {
+            title.to_string()
+        }
+    }
+
+    /// ✏️ 重命名（None恢复为跟随远端标题）
+    pub fn set_custom_title(&mut self, title: Option<String>) {
+        self.custom_title = title;
+    }
+
+    pub fn custom_title(&self) -> Option<&str> {
+        self.custom_title.as_deref()
+    }
+
+    /// 🔌 检测已建立的会话是否结束，并在重连等待到期时发起重连
+    fn poll_session(&mut self, ctx: &egui::Context) {
+        match &self.state {
+            ConnectionState::Connected => {
+                let Some(end) = self.ssh_manager.as_ref().and_then(|manager| manager.session_end(&self.id)) else {
+                    return;
+                };
+                self.terminal.is_connected = false;
+                match end {
+                    SessionEnd::Closed => {
+                        crate::app_log!(info, "Tab", "🔌 会话已结束: {}", self.title);
+                        self.terminal.connection_info = "会话已结束".to_string();
+                        self.terminal.print_notice("──── 会话已结束 ────");
+                        self.state = ConnectionState::Closed;
+                    }
+                    SessionEnd::Lost(reason) => {
+                        crate::app_log!(warn, "Tab", "🔌 连接中断: {}: {}", self.title, reason);
+                        self.terminal.print_notice(&format!("──── 连接中断: {} ────", reason));
+                        self.schedule_reconnect(1);
+                    }
+                }
+            }
+            ConnectionState::Reconnecting { retry_at: Some(retry_at), .. } => {
+                let retry_at = *retry_at;
+                let now = Instant::now();
+                if now >= retry_at {
+                    self.connect();
+                } else {
+                    // 每秒刷新一次倒计时
+                    ctx.request_repaint_after((retry_at - now).min(Duration::from_secs(1)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 安排第attempt次重连，超过上限则进入失败状态
+    fn schedule_reconnect(&mut self, attempt: u32) {
+        if attempt > MAX_RECONNECT_ATTEMPTS {
+            self.terminal.connection_info = "重连失败".to_string();
+            self.terminal.print_notice(&format!("──── 已重试 {} 次，停止自动重连 ────", MAX_RECONNECT_ATTEMPTS));
+            self.state = ConnectionState::Failed("多次重连失败".to_string());
+            return;
+        }
+        let delay = ConnectionState::backoff(attempt);
+        self.terminal.connection_info = format!("连接中断，{}秒后第{}次重连...", delay.as_secs(), attempt);
+        self.state = ConnectionState::Reconnecting {
+            attempt,
+            retry_at: Some(Instant::now() + delay),
+        };
+    }
+
+    /// 用户手动重连（失败或会话结束后）
+    fn reconnect_now(&mut self) {
+        crate::app_log!(info, "Tab", "🔌 手动重连: {}", self.title);
+        self.state = ConnectionState::Reconnecting { attempt: 1, retry_at: None };
+        self.connect();
+    }
+
+    /// 检查后台连接的进度：认证提示和最终结果
+    fn poll_connection(&mut self) {
+        if !self.auth_dialog.is_open()
+            && let Some(prompt) = self.auth_prompts.as_ref().and_then(|rx| rx.try_recv().ok())
+        {
+            self.auth_dialog.open(prompt);
+        }
+
+        let Some(result) = self.connect_result.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.connect_result = None;
+        self.auth_prompts = None;
+
+        let Some(config) = &self.connection_config else {
+            return;
+        };
+        match result {
+            Ok(_) => {
+                crate::app_log!(info, "TabManager", "SSH连接创建成功: {}", 
+                    config.chain_label(&self.jump_chain));
+                self.terminal.is_connected = true;
+                // 🎯 立即更新连接信息
+                self.terminal.connection_info = format!("{}@{}:{} - 已连接", 
+                    config.username, config.host, config.port);
+                // 保留之前的屏幕和回滚历史，只插入一条分隔线
+                if self.connected_once {
+                    self.terminal.print_notice(&format!(
+                        "──── 已重新连接 {} ────",
+                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+                    ));
+                }
+                self.connected_once = true;
+                self.state = ConnectionState::Connected;
+            }
+            Err(e) => {
+                crate::app_log!(error, "TabManager", "SSH连接创建失败: {:#}", e);
+                // 🔁 重连过程中的网络错误继续按退避重试；主机密钥问题需要用户处理
+                if let ConnectionState::Reconnecting { attempt, .. } = self.state
+                    && e.downcast_ref::<HostKeyError>().is_none()
+                {
+                    crate::app_log!(warn, "Tab", "第{}次重连失败: {:#}", attempt, e);
+                    self.schedule_reconnect(attempt + 1);
+                    return;
+                }
+                self.state = ConnectionState::Failed(format!("{:#}", e));
+                match e.downcast_ref::<HostKeyError>() {
+                    // 🔐 首次连接：弹出指纹确认框，用户确认后重新连接
+                    Some(HostKeyError::Unknown(info)) => {
+                        self.terminal.connection_info = "等待确认主机指纹...".to_string();
+                        self.pending_host_key = Some(info.clone());
+                        self.state = ConnectionState::Connecting;
+                    }
+                    // 🚨 密钥变更：直接拒绝，并在终端中显示醒目的警告
+                    Some(HostKeyError::Changed { .. }) => {
+                        self.terminal.connection_info = "主机密钥已改变，已拒绝连接".to_string();
+                        for line in format!("{:#}", e).lines() {
+                            self.terminal.insert_text(line.to_string());
+                        }
+                    }
+                    None => {
+                        self.terminal.connection_info = "连接失败".to_string();
+                        for line in format!("连接失败: {:#}", e).lines() {
+                            self.terminal.insert_text(line.to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// 终端上方的工具栏
+    fn show_toolbar(&mut self, ui: &mut egui::Ui) {
+        let tunnel_count = self
+            .ssh_manager
+            .as_ref()
+            .map_or(0, |manager| manager.forward_status(&self.id).len());
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    self.terminal.is_connected,
+                    egui::Button::new(format!("🔀 端口转发 ({})", tunnel_count)),
+                )
+                .clicked()
+            {
+                self.port_forward_panel.toggle();
+            }
+            ui.toggle_value(&mut self.show_file_panel, "📁 文件");
+
+            match &self.state {
+                ConnectionState::Reconnecting { retry_at: Some(_), .. } => {
+                    if ui.button("🔄 立即重连").clicked() {
+                        self.connect();
+                    }
+                    if ui.button("⏹ 停止重连").clicked() {
+                        self.terminal.connection_info = "已停止重连".to_string();
+                        self.state = ConnectionState::Failed("已停止重连".to_string());
+                    }
+                }
+                ConnectionState::Failed(_) | ConnectionState::Closed
+                    if self.connection_config.is_some()
+                        && self.pending_host_key.is_none()
+                        && ui.button("🔄 重新连接").clicked() =>
+                {
+                    self.reconnect_now();
+                }
+                _ => {}
+            }
+        });
+    }
+
+    /// 🔐 首次连接的主机指纹确认对话框
+    fn show_host_key_dialog(&mut self, ctx: &egui::Context) {
+        let Some(info) = self.pending_host_key.clone() else {
+            return;
+        };
+
+        let mut trusted = false;
+        let mut rejected = false;
+
+        egui::Window::new("🔐 确认主机指纹")
+            .id(egui::Id::new(("host_key_dialog", &self.id)))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("首次连接到 {}:{}，无法确认该主机的真实性。", info.host, info.port));
+                ui.add_space(4.0);
+                egui::Grid::new(("host_key_grid", &self.id))
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("密钥类型:");
+                        ui.monospace(info.key_type_name());
+                        ui.end_row();
+
+                        ui.label("指纹:");
+                        ui.monospace(&info.fingerprint);
+                        ui.end_row();
+                    });
+                ui.add_space(4.0);
+                ui.label("请与服务器管理员核对指纹，确认后该指纹将被保存。");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("✅ 信任并连接").clicked() {
+                        trusted = true;
+                    }
+                    if ui.button("❌ 取消").clicked() {
+                        rejected = true;
+                    }
+                });
+            });
+
+        if trusted {
+            self.pending_host_key = None;
+            match known_hosts::trust(&info) {
+                Ok(_) => {
+                    self.connect();
+                }
+                Err(e) => {
+                    crate::app_log!(error, "Tab", "保存主机指纹失败: {}", e);
+                    self.terminal.connection_info = format!("保存主机指纹失败: {}", e);
+                }
+            }
+        } else if rejected {
+            self.pending_host_key = None;
+            self.state = ConnectionState::Failed("主机指纹未被信任".to_string());
+            self.terminal.connection_info = "已取消连接：主机指纹未被信任".to_string();
+            crate::app_log!(info, "Tab", "用户拒绝信任主机指纹: {}:{}", info.host, info.port);
+        }
+    }
 }
 
 impl TabContent for TerminalTab {
     fn get_title(&self) -> String {
-        if self.terminal.is_connected {
-            format!("🟢 {} (已连接)", self.title)
+        // 🔔 后台Tab的响铃/新输出标记
+        let title = if self.bell {
+            format!("🔔 {}", self.display_title())
+        } else if self.activity {
+            format!("● {}", self.display_title())
         } else {
-            format!("🔴 {} (未连接)", self.title)
+            self.display_title()
+        };
+        match &self.state {
+            ConnectionState::Idle => format!("⚪ {} (未连接)", title),
+            ConnectionState::Connecting => format!("🟡 {} (连接中)", title),
+            ConnectionState::Connected => format!("🟢 {} (已连接)", title),
+            ConnectionState::Reconnecting { attempt, retry_at: Some(retry_at) } => {
+                let remaining = retry_at.saturating_duration_since(Instant::now()).as_secs() + 1;
+                format!("🟠 {} (重连中 #{}，{}s)", title, attempt, remaining)
+            }
+            ConnectionState::Reconnecting { attempt, retry_at: None } => {
+                format!("🟠 {} (重连中 #{})", title, attempt)
+            }
+            ConnectionState::Failed(_) => format!("🔴 {} (失败)", title),
+            ConnectionState::Closed => format!("⚫ {} (已断开)", title),
         }
     }
 
@@ -168,8 +649,40 @@ impl TabContent for TerminalTab {
         self.id.clone()
     }
 
-    fn show(&mut self, ui: &mut egui::Ui, _context: &mut TabContext) {
+    fn show(&mut self, ui: &mut egui::Ui, context: &mut TabContext) {
+        // 🎨 连接单独指定的配色优先于全局设置
+        let theme_name = self
+            .connection_config
+            .as_ref()
+            .and_then(|config| config.theme.as_deref())
+            .unwrap_or(&context.config.settings.theme);
+        self.terminal.set_theme(context.themes.get(theme_name));
+        self.terminal.set_font_size(context.config.settings.font_size as f32);
+
+        self.show_toolbar(ui);
+        if self.show_file_panel {
+            egui::SidePanel::right(egui::Id::new(("file_panel", &self.id)))
+                .resizable(true)
+                .default_width(420.0)
+                .show_inside(ui, |ui| {
+                    context.plugins_panel.show_file_browser_panel(ui);
+                });
+        }
+        if let Some(path) = context.plugins_panel.take_path_to_insert() {
+            self.terminal.insert_path(&path);
+        }
         self.terminal.show(ui);
+        // 🔗 终端里Ctrl+单击的远程路径在文件浏览器中显示
+        if let Some(path) = self.terminal.take_path_to_reveal() {
+            self.show_file_panel = true;
+            context.plugins_panel.reveal_path(&path);
+        }
+        self.show_host_key_dialog(ui.ctx());
+        self.auth_dialog.show(ui.ctx(), &self.id);
+
+        if let (Some(ssh_manager), Some(config)) = (&self.ssh_manager, &self.connection_config) {
+            self.port_forward_panel.show(ui.ctx(), &self.id, ssh_manager, &config.id, &mut context.config);
+        }
     }
 
     fn can_close(&self) -> bool {
@@ -178,6 +691,9 @@ impl TabContent for TerminalTab {
 
     fn on_close(&mut self) {
         crate::app_log!(info, "Tab", "关闭终端Tab: {}", self.title);
+        if let Some(ssh_manager) = &self.ssh_manager {
+            let _ = ssh_manager.disconnect(&self.id);
+        }
         self.terminal.disconnect();
     }
 
@@ -210,7 +726,7 @@ impl TabFactory {
 /// Tab事件系统 - Observer Pattern
 #[derive(Debug, Clone)]
 pub enum TabEvent {
-    CreateTerminal(ConnectionConfig),
+    CreateTerminal(Box<ConnectionConfig>),
     CloseTab(String),
     SwitchTab(String),
     RenameTab(String, String),
@@ -220,6 +736,10 @@ pub trait TabObserver {
     fn on_tab_event(&mut self, event: TabEvent);
 }
 
+/// Tab栏按钮信息：ID、标题、是否活跃、能否关闭，
+/// 最后一项是终端Tab的当前标题（不含状态）和是否已重命名，其他Tab不能重命名
+type TabButtonInfo = (String, String, bool, bool, Option<(String, bool)>);
+
 /// Tab管理器 - 管理所有Tab的生命周期
 pub struct TabManager {
     tabs: HashMap<String, Box<dyn TabContent>>,
@@ -227,10 +747,14 @@ pub struct TabManager {
     observers: Vec<Box<dyn TabObserver>>,
     context: TabContext,
     ssh_manager: Arc<Ssh2Manager>, // SSH2管理器
+    system_fonts: SystemFonts,     // 启动时扫描的系统字体文件
+    toasts: Toasts,
+    renaming: Option<(String, String)>, // 正在重命名的Tab ID和输入中的名称
 }
 
 impl TabManager {
-    pub fn new(config: AppConfig) -> Self {
+    /// ctx交给SSH管理器，SSH输出到达时唤醒UI重绘
+    pub fn new(config: AppConfig, ctx: &egui::Context) -> Self {
         let mut tabs = HashMap::new();
         
         // 创建默认的欢迎Tab
@@ -238,7 +762,7 @@ impl TabManager {
         let welcome_id = welcome_tab.get_id();
         tabs.insert(welcome_id.clone(), welcome_tab);
 
-        let ssh_manager = Arc::new(Ssh2Manager::new());
+        let ssh_manager = Arc::new(Ssh2Manager::new().with_repaint(ctx.clone()));
         
         Self {
             tabs,
@@ -248,9 +772,13 @@ impl TabManager {
                 config,
                 connection_manager: ConnectionManager::new(),
                 plugins_panel: PluginsPanel::new(),
+                themes: ThemeLibrary::load(AppConfig::themes_dir().ok()),
                 pending_connection: None, // 初始化为None
             },
             ssh_manager,
+            system_fonts: SystemFonts::scan(),
+            toasts: Toasts::default(),
+            renaming: None,
         }
     }
 
@@ -270,26 +798,20 @@ impl TabManager {
         
         // 如果是TerminalTab，设置SSH管理器并尝试连接
         if let Some(terminal_tab) = tab.as_any_mut().downcast_mut::<TerminalTab>() {
-            terminal_tab.terminal.set_ssh_manager(Arc::clone(&self.ssh_manager), tab_id.clone());
-            
-            // 尝试创建SSH连接
-            if let Some(config) = terminal_tab.connection_config.as_ref() {
-                match self.ssh_manager.create_connection(tab_id.clone(), config) {
-                    Ok(_) => {
-                        crate::app_log!(info, "TabManager", "SSH连接创建成功: {}@{}", 
-                            config.username, config.host);
-                        terminal_tab.terminal.is_connected = true;
-                        // 🎯 立即更新连接信息
-                        terminal_tab.terminal.connection_info = format!("{}@{}:{} - 已连接", 
-                            config.username, config.host, config.port);
-                        
-                        // 🔑 关键：连接成功后立即读取初始输出（登录信息和提示符）
-                        self.read_initial_ssh_output(&tab_id);
-                    }
-                    Err(e) => {
-                        crate::app_log!(error, "TabManager", "SSH连接创建失败: {}", e);
-                        terminal_tab.terminal.connection_info = format!("连接失败: {}", e);
-                    }
+            terminal_tab.terminal.apply_settings(&self.context.config.settings);
+            terminal_tab.set_ssh_manager(Arc::clone(&self.ssh_manager));
+
+            // 🔗 解析跳板机链，配置有误时不发起连接
+            match self.context.config.resolve_jump_chain(&connection_config) {
+                Ok(jump_chain) => {
+                    terminal_tab.set_jump_chain(jump_chain);
+                    // 🔑 后台连接，初始输出（登录信息和提示符）在UI循环中读取
+                    terminal_tab.connect();
+                }
+                Err(e) => {
+                    crate::app_log!(error, "TabManager", "跳板机配置错误: {}", e);
+                    terminal_tab.terminal.connection_info = "连接失败".to_string();
+                    terminal_tab.terminal.insert_text(format!("连接失败: {}", e));
                 }
             }
         }
@@ -298,13 +820,16 @@ impl TabManager {
         self.active_tab_id = Some(tab_id.clone());
         
         crate::app_log!(info, "TabManager", "创建新终端Tab: {}", tab_id);
-        self.notify_observers(TabEvent::CreateTerminal(connection_config));
+        self.notify_observers(TabEvent::CreateTerminal(Box::new(connection_config)));
     }
 
     pub fn create_empty_terminal_tab(&mut self) {
         let tab_count = self.tabs.len();
-        let tab = TabFactory::create_terminal_tab(format!("终端 {}", tab_count));
+        let mut tab = TabFactory::create_terminal_tab(format!("终端 {}", tab_count));
         let tab_id = tab.get_id();
+        if let Some(terminal_tab) = tab.as_any_mut().downcast_mut::<TerminalTab>() {
+            terminal_tab.terminal.apply_settings(&self.context.config.settings);
+        }
         
         self.tabs.insert(tab_id.clone(), tab);
         self.active_tab_id = Some(tab_id.clone());
@@ -316,6 +841,7 @@ impl TabManager {
         if let Some(mut tab) = self.tabs.remove(tab_id) {
             if tab.can_close() {
                 tab.on_close();
+                self.context.plugins_panel.forget_session(tab_id);
                 crate::app_log!(info, "TabManager", "关闭Tab: {}", tab_id);
                 
                 // 如果关闭的是当前活跃Tab，切换到其他Tab
@@ -338,6 +864,20 @@ impl TabManager {
         }
     }
 
+    /// ✏️ 重命名终端Tab，空名称恢复为跟随远端标题
+    pub fn rename_tab(&mut self, tab_id: &str, name: &str) {
+        let Some(terminal_tab) = self
+            .tabs
+            .get_mut(tab_id)
+            .and_then(|tab| tab.as_any_mut().downcast_mut::<TerminalTab>())
+        else {
+            return;
+        };
+        let name = name.trim();
+        terminal_tab.set_custom_title((!name.is_empty()).then(|| name.to_string()));
+        self.notify_observers(TabEvent::RenameTab(tab_id.to_string(), name.to_string()));
+    }
+
     pub fn get_active_tab(&mut self) -> Option<&mut Box<dyn TabContent>> {
         if let Some(active_id) = &self.active_tab_id {
             self.tabs.get_mut(active_id)
@@ -358,21 +898,25 @@ impl TabManager {
         // 收集需要执行的操作，避免借用检查问题
         let mut tab_to_switch: Option<String> = None;
         let mut tab_to_close: Option<String> = None;
+        let mut tab_to_rename: Option<(String, String)> = None;
         let mut create_new_tab = false;
         
         ui.horizontal(|ui| {
             // 收集Tab信息，避免在循环中修改self
-            let tab_info: Vec<(String, String, bool, bool)> = self.tabs.iter()
+            let tab_info: Vec<TabButtonInfo> = self.tabs.iter_mut()
                 .map(|(id, tab)| (
                     id.clone(),
                     tab.get_title(),
                     self.active_tab_id.as_ref() == Some(id),
-                    tab.can_close()
+                    tab.can_close(),
+                    tab.as_any_mut()
+                        .downcast_mut::<TerminalTab>()
+                        .map(|terminal_tab| (terminal_tab.display_title(), terminal_tab.custom_title().is_some())),
                 ))
                 .collect();
             
             // 渲染所有Tab按钮
-            for (tab_id, title, is_active, can_close) in tab_info {
+            for (tab_id, title, is_active, can_close, rename) in tab_info {
                 ui.horizontal(|ui| {
                     // 🎨 改进：活跃Tab使用更明显的视觉样式
                     let button_response = if is_active {
@@ -384,16 +928,30 @@ impl TabManager {
                                 .stroke(egui::Stroke::new(2.0, egui::Color32::from_rgb(100, 160, 210)))
                         )
                     } else {
-                        ui.add(
-                            egui::Button::new(&title)
-                                .fill(egui::Color32::from_gray(240))
-                        )
+                        ui.add(egui::Button::new(&title))
                     };
                     
                     if button_response.clicked() {
                         tab_to_switch = Some(tab_id.clone());
                         crate::app_log!(info, "TabManager", "点击切换到Tab: {} ({})", title, tab_id);
                     }
+
+                    // ✏️ 双击或右键菜单重命名
+                    if let Some((current, renamed)) = rename {
+                        if button_response.double_clicked() {
+                            self.renaming = Some((tab_id.clone(), current.clone()));
+                        }
+                        button_response.context_menu(|ui| {
+                            if ui.button("✏ 重命名…").clicked() {
+                                self.renaming = Some((tab_id.clone(), current.clone()));
+                                ui.close();
+                            }
+                            if ui.add_enabled(renamed, egui::Button::new("↺ 恢复自动标题")).clicked() {
+                                tab_to_rename = Some((tab_id.clone(), String::new()));
+                                ui.close();
+                            }
+                        });
+                    }
                     
                     // 显示关闭按钮（如果Tab可以关闭）
                     if can_close {
@@ -410,7 +968,15 @@ impl TabManager {
             if ui.button("➕ 新终端").clicked() {
                 create_new_tab = true;
             }
+
+            self.show_theme_menu(ui);
+            self.show_font_menu(ui);
+            self.show_bell_menu(ui);
         });
+
+        if let Some(rename) = self.show_rename_window(ui.ctx()) {
+            tab_to_rename = Some(rename);
+        }
         
         // 执行收集的操作
         if let Some(tab_id) = tab_to_switch {
@@ -422,12 +988,261 @@ impl TabManager {
             self.close_tab(&tab_id);
         }
         
+        if let Some((tab_id, name)) = tab_to_rename {
+            self.rename_tab(&tab_id, &name);
+        }
+        
         if create_new_tab {
             self.create_empty_terminal_tab();
         }
     }
 
+    /// ✏️ 重命名输入窗口，确认时返回Tab ID和新名称
+    fn show_rename_window(&mut self, ctx: &egui::Context) -> Option<(String, String)> {
+        let (_, name) = self.renaming.as_mut()?;
+        let mut confirmed = false;
+        let mut cancelled = false;
+        egui::Window::new("✏ 重命名Tab")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .show(ctx, |ui| {
+                ui.label("留空则恢复为远端设置的标题");
+                let response = ui.text_edit_singleline(name);
+                response.request_focus();
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    confirmed = true;
+                }
+                ui.horizontal(|ui| {
+                    confirmed |= ui.button("确定").clicked();
+                    cancelled |= ui.button("取消").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape));
+                });
+            });
+
+        if confirmed {
+            self.renaming.take()
+        } else {
+            if cancelled {
+                self.renaming = None;
+            }
+            None
+        }
+    }
+
+    /// 🔔 视觉响铃和后台长命令通知
+    fn show_bell_menu(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.context.config.settings;
+        let mut changed = false;
+        ui.menu_button("🔔 通知", |ui| {
+            changed |= ui.checkbox(&mut settings.visual_bell, "响铃时闪烁终端").changed();
+            changed |= ui
+                .checkbox(&mut settings.notify_long_commands, "后台长命令结束时发送桌面通知")
+                .on_hover_text("需要远端Shell启用OSC 133集成")
+                .changed();
+            ui.add_enabled_ui(settings.notify_long_commands, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("运行超过");
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut settings.long_command_secs).range(1..=86400).suffix(" 秒"))
+                        .changed();
+                    ui.label("的命令");
+                });
+            });
+        });
+        if changed {
+            self.save_config();
+        }
+    }
+
+    /// 全局终端配色方案
+    pub fn theme(&self) -> &TerminalTheme {
+        self.context.themes.get(&self.context.config.settings.theme)
+    }
+
+    /// 🎨 切换全局配色方案（已打开的终端立即生效）或导入配色文件
+    fn show_theme_menu(&mut self, ui: &mut egui::Ui) {
+        let mut selected = self.context.config.settings.theme.clone();
+        ui.menu_button("🎨 主题", |ui| {
+            for theme in self.context.themes.themes() {
+                if ui.radio_value(&mut selected, theme.name.clone(), theme.label()).clicked() {
+                    ui.close();
+                }
+            }
+            ui.separator();
+            if ui.button("导入配色方案…").on_hover_text("iTerm2 (.itermcolors) 或 Windows Terminal (.json)").clicked() {
+                ui.close();
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("配色方案", &["itermcolors", "json"])
+                    .pick_file()
+                {
+                    match self.context.themes.import(&path) {
+                        Ok(names) => {
+                            self.toasts.push(ui.ctx(), format!("🎨 已导入配色方案: {}", names.join(", ")));
+                            if let Some(name) = names.into_iter().next() {
+                                selected = name;
+                            }
+                        }
+                        Err(e) => {
+                            crate::app_log!(error, "TabManager", "导入配色方案失败: {:#}", e);
+                            self.toasts.push(ui.ctx(), format!("导入配色方案失败: {:#}", e));
+                        }
+                    }
+                }
+            }
+        });
+
+        if selected != self.context.config.settings.theme {
+            crate::app_log!(info, "TabManager", "🎨 切换配色方案: {}", selected);
+            self.context.config.settings.theme = selected;
+            ui.ctx().set_visuals(self.theme().visuals());
+            self.save_config();
+        }
+    }
+
+    /// 🔤 按设置加载终端字体和后备字体
+    pub fn apply_fonts(&self, ctx: &egui::Context) {
+        fonts::apply_fonts(ctx, &self.context.config.settings, &self.system_fonts);
+    }
+
+    /// 🔤 Ctrl+= / Ctrl+- / Ctrl+0 调整终端字号（在终端读取键盘输入之前处理）
+    fn handle_zoom_keys(&mut self, ctx: &egui::Context) {
+        use egui::gui_zoom::kb_shortcuts;
+
+        let font_size = self.context.config.settings.font_size;
+        let new_size = ctx.input_mut(|i| {
+            if i.consume_shortcut(&kb_shortcuts::ZOOM_RESET) {
+                AppSettings::default().font_size
+            } else if i.consume_shortcut(&kb_shortcuts::ZOOM_IN) || i.consume_shortcut(&kb_shortcuts::ZOOM_IN_SECONDARY) {
+                font_size + 1
+            } else if i.consume_shortcut(&kb_shortcuts::ZOOM_OUT) {
+                font_size.saturating_sub(1)
+            } else {
+                font_size
+            }
+        });
+        self.set_font_size(new_size);
+    }
+
+    fn set_font_size(&mut self, font_size: u16) {
+        let font_size = font_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+        if font_size != self.context.config.settings.font_size {
+            crate::app_log!(debug, "TabManager", "🔤 终端字号: {}", font_size);
+            self.context.config.settings.font_size = font_size;
+            self.save_config();
+        }
+    }
+
+    /// 🔤 字号、终端字体和后备字体
+    fn show_font_menu(&mut self, ui: &mut egui::Ui) {
+        let settings = &self.context.config.settings;
+        let mut font_size = settings.font_size;
+        let mut font_path = settings.font_path.clone();
+        let mut fallback_fonts = settings.fallback_fonts.clone();
+
+        ui.menu_button("🔤 字体", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("字号:");
+                if ui.small_button("－").on_hover_text("Ctrl+-").clicked() {
+                    font_size = font_size.saturating_sub(1);
+                }
+                ui.label(font_size.to_string());
+                if ui.small_button("＋").on_hover_text("Ctrl+=").clicked() {
+                    font_size += 1;
+                }
+                if ui.small_button("重置").on_hover_text("Ctrl+0").clicked() {
+                    font_size = AppSettings::default().font_size;
+                }
+            });
+            ui.separator();
+
+            let current = font_path.as_deref().map_or("自动".to_string(), fonts::font_label);
+            ui.menu_button(format!("终端字体: {}", current), |ui| {
+                ui.radio_value(&mut font_path, None, "自动（系统等宽字体）");
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for path in self.system_fonts.monospace() {
+                        let path = path.to_string_lossy().to_string();
+                        let label = fonts::font_label(&path);
+                        ui.radio_value(&mut font_path, Some(path), label);
+                    }
+                });
+                ui.separator();
+                if ui.button("选择字体文件…").clicked()
+                    && let Some(path) = pick_font_file()
+                {
+                    font_path = Some(path);
+                }
+                ui.separator();
+                // egui不做文字整形，见fonts.rs
+                ui.weak("不支持连字：Fira Code等连字字体可以使用，但 -> != 等连字不会合成显示");
+            });
+
+            ui.menu_button(format!("后备字体 ({})", fallback_fonts.len()), |ui| {
+                ui.label("终端字体缺字时按顺序使用，之后是自动找到的中文字体");
+                let mut removed = None;
+                for (i, font) in fallback_fonts.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("✕").clicked() {
+                            removed = Some(i);
+                        }
+                        ui.label(fonts::font_label(font)).on_hover_text(font);
+                    });
+                }
+                if let Some(i) = removed {
+                    fallback_fonts.remove(i);
+                }
+                if ui.button("添加字体文件…").clicked()
+                    && let Some(path) = pick_font_file()
+                {
+                    fallback_fonts.push(path);
+                }
+            });
+        });
+
+        self.set_font_size(font_size);
+        let settings = &mut self.context.config.settings;
+        if font_path != settings.font_path || fallback_fonts != settings.fallback_fonts {
+            settings.font_path = font_path;
+            settings.fallback_fonts = fallback_fonts;
+            self.apply_fonts(ui.ctx());
+            self.save_config();
+        }
+    }
+
+    /// 📁 文件浏览器跟随当前活跃的已连接终端Tab
+    fn sync_file_browser_target(&mut self) {
+        let Some(active_id) = self.active_tab_id.clone() else {
+            return;
+        };
+        let Some(terminal_tab) = self
+            .tabs
+            .get_mut(&active_id)
+            .and_then(|tab| tab.as_any_mut().downcast_mut::<TerminalTab>())
+        else {
+            return;
+        };
+
+        let target = terminal_tab.remote_target();
+        let following = self.context.plugins_panel.file_browser_session() == Some(active_id.as_str());
+        let connected = target.is_some();
+        if connected != following {
+            self.context.plugins_panel.set_file_browser_target(target);
+        }
+        // 📂 终端通过OSC 7报告了新目录时，文件浏览器跟随进入
+        if connected && let Some(directory) = terminal_tab.take_directory_change() {
+            self.context.plugins_panel.follow_directory(&directory);
+        }
+    }
+
     pub fn render_active_tab(&mut self, ui: &mut egui::Ui) {
+        self.handle_zoom_keys(ui.ctx());
+        let settings = &self.context.config.settings;
+        for (id, tab) in self.tabs.iter_mut() {
+            if let Some(terminal_tab) = tab.as_any_mut().downcast_mut::<TerminalTab>() {
+                let active = self.active_tab_id.as_ref() == Some(id);
+                terminal_tab.poll(ui.ctx(), active, settings);
+            }
+        }
+        self.sync_file_browser_target();
         if let Some(active_id) = self.active_tab_id.clone() {
             if let Some(active_tab) = self.tabs.get_mut(&active_id) {
                 active_tab.show(ui, &mut self.context);
@@ -440,6 +1255,7 @@ impl TabManager {
                 connection_config.username, connection_config.host);
             self.create_terminal_tab(connection_config);
         }
+        self.toasts.show(ui.ctx(), egui::Id::new("tab_manager_toasts"));
     }
 
     pub fn save_config(&mut self) {
@@ -447,29 +1263,12 @@ impl TabManager {
             crate::app_log!(error, "TabManager", "保存配置失败: {}", e);
         }
     }
-    
-    /// 🔑 读取SSH连接的初始输出（登录信息和提示符）- 简化版本
-    fn read_initial_ssh_output(&mut self, tab_id: &str) {
-        crate::app_log!(info, "TabManager", "尝试读取SSH初始输出: {}", tab_id);
-        
-        // 立即尝试读取一次，不使用阻塞等待
-        match self.ssh_manager.read_output(tab_id) {
-            Ok(data) if !data.is_empty() => {
-                crate::app_log!(info, "TabManager", "读取到SSH初始输出: {} 字节", data.len());
-                
-                // 将初始输出传递给终端Tab
-                if let Some(terminal_tab) = self.tabs.get_mut(tab_id) {
-                    if let Some(term_tab) = terminal_tab.as_any_mut().downcast_mut::<TerminalTab>() {
-                        term_tab.terminal.process_ssh_data(data);
-                    }
-                }
-            }
-            Ok(_) => {
-                crate::app_log!(debug, "TabManager", "SSH初始输出暂时为空，稍后在UI循环中读取");
-            }
-            Err(e) => {
-                crate::app_log!(warn, "TabManager", "SSH初始输出读取错误: {}", e);
-            }
-        }
-    }
+}
+
+/// 选择字体文件
+fn pick_font_file() -> Option<String> {
+    rfd::FileDialog::new()
+        .add_filter("字体", &["ttf", "otf", "ttc"])
+        .pick_file()
+        .map(|path| path.to_string_lossy().to_string())
 }
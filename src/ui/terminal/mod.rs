@@ -1,9 +1,7 @@
-// 终端模块 - 模块化的终端模拟器实现
+// 终端界面 - 按配色方案绘制终端核心（crate::terminal）的屏幕，并收集键盘和鼠标输入
 
-pub mod types;
-pub mod vt100_handler;
-pub mod emulator;
+pub mod theme;
+pub mod view;
 
 // 重新导出公共接口
-pub use types::{TerminalSegment, TerminalLine};
-pub use emulator::TerminalEmulator;
+pub use view::{BlockMarker, BlockStatus, Highlight, HighlightKind, SelectionGesture, TerminalFrame, TerminalView};
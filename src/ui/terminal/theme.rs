@@ -0,0 +1,306 @@
+// 终端配色方案 - 内置主题，以及iTerm2 (.itermcolors) 和Windows Terminal (JSON) 配色的导入
+
+use anyhow::{anyhow, Context, Result};
+use eframe::egui::{self, Color32};
+use regex::Regex;
+
+use crate::terminal::CellColor;
+use std::path::{Path, PathBuf};
+
+/// 默认主题（白底黑字，与设置中的 "default" 对应）
+pub const DEFAULT_THEME: &str = "default";
+
+/// 🎨 终端配色：16色ANSI调色板、默认前景/背景、光标和选区
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerminalTheme {
+    pub name: String,
+    pub foreground: Color32,
+    pub background: Color32,
+    pub cursor: Color32,
+    pub selection: Color32,
+    pub ansi: [Color32; 16], // 0-7为基本色，8-15为亮色
+}
+
+const fn rgb(hex: u32) -> Color32 {
+    Color32::from_rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+}
+
+/// VS Code深色终端的调色板，深色主题共用
+const DARK_ANSI: [Color32; 16] = [
+    rgb(0x000000), rgb(0xcd3131), rgb(0x0dbc79), rgb(0xe5e510),
+    rgb(0x2472c8), rgb(0xbc3fbc), rgb(0x11a8cd), rgb(0xe5e5e5),
+    rgb(0x666666), rgb(0xf14c4c), rgb(0x23d18b), rgb(0xf5f543),
+    rgb(0x3b8eea), rgb(0xd670d6), rgb(0x29b8db), rgb(0xe5e5e5),
+];
+
+const SOLARIZED_ANSI: [Color32; 16] = [
+    rgb(0x073642), rgb(0xdc322f), rgb(0x859900), rgb(0xb58900),
+    rgb(0x268bd2), rgb(0xd33682), rgb(0x2aa198), rgb(0xeee8d5),
+    rgb(0x002b36), rgb(0xcb4b16), rgb(0x586e75), rgb(0x657b83),
+    rgb(0x839496), rgb(0x6c71c4), rgb(0x93a1a1), rgb(0xfdf6e3),
+];
+
+impl TerminalTheme {
+    /// 内置主题，第一个为默认主题
+    pub fn builtin() -> Vec<TerminalTheme> {
+        let theme = |name: &str, foreground, background, cursor, selection, ansi| TerminalTheme {
+            name: name.to_string(),
+            foreground,
+            background,
+            cursor,
+            selection,
+            ansi,
+        };
+        vec![
+            theme(DEFAULT_THEME, Color32::BLACK, Color32::WHITE, Color32::BLACK, rgb(0xadd6ff), [
+                Color32::BLACK,
+                rgb(0x800000),
+                rgb(0x008000),
+                rgb(0x808000),
+                rgb(0x000080),
+                rgb(0x800080),
+                rgb(0x008080),
+                Color32::LIGHT_GRAY,
+                Color32::DARK_GRAY,
+                Color32::RED,
+                Color32::GREEN,
+                Color32::YELLOW,
+                Color32::BLUE,
+                rgb(0xff00ff),
+                rgb(0x00ffff),
+                Color32::WHITE,
+            ]),
+            theme("Dark", rgb(0xcccccc), rgb(0x1e1e1e), rgb(0xdcdcdc), rgb(0x264f78), DARK_ANSI),
+            theme("Solarized Light", rgb(0x657b83), rgb(0xfdf6e3), rgb(0x586e75), rgb(0xeee8d5), SOLARIZED_ANSI),
+            theme("Solarized Dark", rgb(0x839496), rgb(0x002b36), rgb(0x93a1a1), rgb(0x073642), SOLARIZED_ANSI),
+            theme("Dracula", rgb(0xf8f8f2), rgb(0x282a36), rgb(0xf8f8f2), rgb(0x44475a), [
+                rgb(0x21222c), rgb(0xff5555), rgb(0x50fa7b), rgb(0xf1fa8c),
+                rgb(0xbd93f9), rgb(0xff79c6), rgb(0x8be9fd), rgb(0xf8f8f2),
+                rgb(0x6272a4), rgb(0xff6e6e), rgb(0x69ff94), rgb(0xffffa5),
+                rgb(0xd6acff), rgb(0xff92df), rgb(0xa4ffff), rgb(0xffffff),
+            ]),
+            theme("Gruvbox Dark", rgb(0xebdbb2), rgb(0x282828), rgb(0xebdbb2), rgb(0x504945), [
+                rgb(0x282828), rgb(0xcc241d), rgb(0x98971a), rgb(0xd79921),
+                rgb(0x458588), rgb(0xb16286), rgb(0x689d6a), rgb(0xa89984),
+                rgb(0x928374), rgb(0xfb4934), rgb(0xb8bb26), rgb(0xfabd2f),
+                rgb(0x83a598), rgb(0xd3869b), rgb(0x8ec07c), rgb(0xebdbb2),
+            ]),
+            // 生产环境：红色背景，一眼就能认出
+            theme("Production Red", rgb(0xf5e6e6), rgb(0x4a0d0d), rgb(0xffffff), rgb(0x8a2b2b), DARK_ANSI),
+        ]
+    }
+
+    /// 界面显示的名称
+    pub fn label(&self) -> &str {
+        if self.name == DEFAULT_THEME { "默认（浅色）" } else { &self.name }
+    }
+
+    /// 背景较暗时界面使用深色样式
+    pub fn is_dark(&self) -> bool {
+        let [r, g, b, _] = self.background.to_array();
+        (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000 < 128
+    }
+
+    /// 与主题明暗一致的egui界面样式
+    pub fn visuals(&self) -> egui::Visuals {
+        if self.is_dark() { egui::Visuals::dark() } else { egui::Visuals::light() }
+    }
+
+    /// 单元格颜色的实际显示色
+    pub fn color(&self, color: CellColor) -> Color32 {
+        match color {
+            CellColor::Indexed(idx) => self.indexed_color(idx),
+            CellColor::Rgb(r, g, b) => Color32::from_rgb(r, g, b),
+        }
+    }
+
+    /// 256色中的第idx个：0-15取主题调色板，16-231为6x6x6色立方，232-255为灰阶
+    pub fn indexed_color(&self, idx: u8) -> Color32 {
+        match idx {
+            0..=15 => self.ansi[idx as usize],
+            16..=231 => {
+                const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+                let i = idx - 16;
+                Color32::from_rgb(LEVELS[(i / 36) as usize], LEVELS[(i / 6 % 6) as usize], LEVELS[(i % 6) as usize])
+            }
+            232..=255 => {
+                let gray = 8 + (idx - 232) * 10;
+                Color32::from_rgb(gray, gray, gray)
+            }
+        }
+    }
+
+    /// 解析iTerm2的 .itermcolors（plist XML，颜色分量为0-1的实数）
+    pub fn from_itermcolors(name: &str, xml: &str) -> Result<Self> {
+        let entry = Regex::new(r"(?s)<key>([^<]+)</key>\s*<dict>(.*?)</dict>").expect("itermcolors正则");
+        let component = Regex::new(r"<key>(Red|Green|Blue) Component</key>\s*<(?:real|integer)>([^<]+)</").expect("颜色分量正则");
+
+        let mut colors = std::collections::HashMap::new();
+        for captures in entry.captures_iter(xml) {
+            let mut rgb = [0u8; 3];
+            for part in component.captures_iter(&captures[2]) {
+                let value: f32 = part[2].trim().parse().with_context(|| format!("颜色分量无效: {}", &part[2]))?;
+                let index = match &part[1] {
+                    "Red" => 0,
+                    "Green" => 1,
+                    _ => 2,
+                };
+                rgb[index] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+            colors.insert(captures[1].trim().to_string(), Color32::from_rgb(rgb[0], rgb[1], rgb[2]));
+        }
+
+        let color = |key: &str| colors.get(key).copied().ok_or_else(|| anyhow!("缺少颜色: {}", key));
+        let mut ansi = [Color32::BLACK; 16];
+        for (i, slot) in ansi.iter_mut().enumerate() {
+            *slot = color(&format!("Ansi {} Color", i))?;
+        }
+        let foreground = color("Foreground Color")?;
+        Ok(Self {
+            name: name.to_string(),
+            foreground,
+            background: color("Background Color")?,
+            cursor: color("Cursor Color").unwrap_or(foreground),
+            selection: color("Selection Color").unwrap_or(ansi[8]),
+            ansi,
+        })
+    }
+
+    /// 解析Windows Terminal配色：单个scheme对象、scheme数组，或含 "schemes" 的settings.json
+    pub fn from_windows_terminal(json: &str) -> Result<Vec<Self>> {
+        let value: serde_json::Value = serde_json::from_str(json).context("JSON格式无效")?;
+        let schemes = match &value {
+            serde_json::Value::Array(items) => items.clone(),
+            serde_json::Value::Object(object) => match object.get("schemes") {
+                Some(serde_json::Value::Array(items)) => items.clone(),
+                _ => vec![value.clone()],
+            },
+            _ => return Err(anyhow!("不是Windows Terminal配色方案")),
+        };
+        if schemes.is_empty() {
+            return Err(anyhow!("文件中没有配色方案"));
+        }
+        schemes.iter().map(Self::from_windows_terminal_scheme).collect()
+    }
+
+    fn from_windows_terminal_scheme(scheme: &serde_json::Value) -> Result<Self> {
+        const KEYS: [&str; 16] = [
+            "black", "red", "green", "yellow", "blue", "purple", "cyan", "white",
+            "brightBlack", "brightRed", "brightGreen", "brightYellow", "brightBlue", "brightPurple", "brightCyan", "brightWhite",
+        ];
+        let name = scheme["name"].as_str().ok_or_else(|| anyhow!("配色方案缺少name"))?;
+        let color = |key: &str| {
+            let hex = scheme[key].as_str().ok_or_else(|| anyhow!("{} 缺少颜色: {}", name, key))?;
+            parse_hex_color(hex).ok_or_else(|| anyhow!("{} 的颜色无效: {} = {}", name, key, hex))
+        };
+        let mut ansi = [Color32::BLACK; 16];
+        for (slot, key) in ansi.iter_mut().zip(KEYS) {
+            *slot = color(key)?;
+        }
+        let foreground = color("foreground")?;
+        let background = color("background")?;
+        Ok(Self {
+            name: name.to_string(),
+            foreground,
+            background,
+            cursor: color("cursorColor").unwrap_or(foreground),
+            // Windows Terminal半透明地叠加选区颜色，这里预先与背景混合
+            selection: color("selectionBackground").map_or(ansi[8], |c| background.lerp_to_gamma(c, 0.4)),
+            ansi,
+        })
+    }
+
+    /// 按扩展名解析配色文件：.itermcolors 或 .json
+    pub fn load_file(path: &Path) -> Result<Vec<Self>> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("无法读取 {}", path.display()))?;
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+        match extension.as_str() {
+            "itermcolors" => {
+                let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("iTerm2");
+                Ok(vec![Self::from_itermcolors(name, &content)?])
+            }
+            "json" => Self::from_windows_terminal(&content),
+            _ => Err(anyhow!("不支持的配色文件: {}（支持 .itermcolors 和 Windows Terminal 的 .json）", path.display())),
+        }
+    }
+}
+
+impl Default for TerminalTheme {
+    fn default() -> Self {
+        Self::builtin().remove(0)
+    }
+}
+
+/// "#rrggbb" 或 "#rgb"
+fn parse_hex_color(hex: &str) -> Option<Color32> {
+    let hex = hex.trim().strip_prefix('#')?;
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some(rgb(value)),
+        3 => {
+            let expand = |v: u32| (v & 0xf) as u8 * 17;
+            Some(Color32::from_rgb(expand(value >> 8), expand(value >> 4), expand(value)))
+        }
+        _ => None,
+    }
+}
+
+/// 📚 可选的主题：内置主题 + 主题目录中导入的配色文件（同名时导入的覆盖内置的）
+#[derive(Debug)]
+pub struct ThemeLibrary {
+    themes: Vec<TerminalTheme>,
+    directory: Option<PathBuf>,
+}
+
+impl ThemeLibrary {
+    /// 加载内置主题和directory中的配色文件，无法解析的文件记录日志后跳过
+    pub fn load(directory: Option<PathBuf>) -> Self {
+        let mut library = Self {
+            themes: TerminalTheme::builtin(),
+            directory,
+        };
+        let entries = library
+            .directory
+            .as_ref()
+            .and_then(|dir| std::fs::read_dir(dir).ok())
+            .into_iter()
+            .flatten()
+            .flatten();
+        for entry in entries {
+            match TerminalTheme::load_file(&entry.path()) {
+                Ok(themes) => themes.into_iter().for_each(|theme| library.insert(theme)),
+                Err(e) => crate::app_log!(warn, "Theme", "跳过配色文件 {}: {}", entry.path().display(), e),
+            }
+        }
+        library
+    }
+
+    pub fn themes(&self) -> &[TerminalTheme] {
+        &self.themes
+    }
+
+    /// 按名称查找，找不到时（如主题文件被删除）使用默认主题
+    pub fn get(&self, name: &str) -> &TerminalTheme {
+        self.themes.iter().find(|t| t.name == name).unwrap_or(&self.themes[0])
+    }
+
+    fn insert(&mut self, theme: TerminalTheme) {
+        match self.themes.iter_mut().find(|t| t.name == theme.name) {
+            Some(existing) => *existing = theme,
+            None => self.themes.push(theme),
+        }
+    }
+
+    /// 🎨 导入配色文件：解析成功后复制到主题目录，下次启动仍然可用。返回导入的主题名
+    pub fn import(&mut self, path: &Path) -> Result<Vec<String>> {
+        let themes = TerminalTheme::load_file(path)?;
+        if let (Some(dir), Some(file_name)) = (&self.directory, path.file_name()) {
+            std::fs::create_dir_all(dir)?;
+            std::fs::copy(path, dir.join(file_name)).context("无法保存到主题目录")?;
+        }
+        let names: Vec<String> = themes.iter().map(|t| t.name.clone()).collect();
+        themes.into_iter().for_each(|theme| self.insert(theme));
+        crate::app_log!(info, "Theme", "🎨 导入配色: {}", names.join(", "));
+        Ok(names)
+    }
+}
@@ -0,0 +1,750 @@
+use std::sync::Arc;
+
+use eframe::egui;
+
+use super::theme::TerminalTheme;
+use crate::terminal::emulator::Damage;
+use crate::terminal::keys::{encode_key, KeyModes, KeyModifiers, TermKey};
+use crate::terminal::mouse::{encode_mouse, MouseButton, MouseEvent, MouseEventKind, MouseReporting};
+use crate::terminal::selection::SelectionMode;
+use crate::terminal::types::{CursorShape, CursorStyle, TerminalLine, TerminalSegment};
+
+/// 搜索匹配的高亮色（当前匹配更醒目）
+const MATCH_BG: egui::Color32 = egui::Color32::from_rgb(255, 235, 120);
+const CURRENT_MATCH_BG: egui::Color32 = egui::Color32::from_rgb(255, 150, 50);
+/// 按住Ctrl悬停时链接的下划线颜色
+const LINK_UNDERLINE: egui::Color32 = egui::Color32::from_rgb(30, 100, 220);
+
+/// 命令块左侧色条：执行中/成功/失败/未报告退出码
+const BLOCK_RUNNING: egui::Color32 = egui::Color32::from_rgb(100, 150, 230);
+const BLOCK_SUCCESS: egui::Color32 = egui::Color32::from_rgb(60, 170, 90);
+const BLOCK_FAILURE: egui::Color32 = egui::Color32::from_rgb(220, 70, 60);
+const BLOCK_UNKNOWN: egui::Color32 = egui::Color32::GRAY;
+
+/// 光标闪烁半周期（秒）
+const BLINK_INTERVAL: f64 = 0.5;
+
+/// egui的字体图集，更换字体或图集写满重建后，之前排版的文字不能再使用
+type FontAtlas = Arc<egui::epaint::mutex::Mutex<egui::epaint::TextureAtlas>>;
+
+/// 🧩 排版好的一个屏幕行，坐标相对于行的左上角
+#[derive(Default)]
+struct RowLayout {
+    backgrounds: Vec<(egui::Rect, egui::Color32)>,
+    texts: Vec<(egui::Vec2, Arc<egui::Galley>, egui::Color32, bool)>, // （偏移，文字，颜色，粗体）
+}
+
+/// 行排版依赖的外部状态，任何一项变化都要重排所有行
+struct LayoutKey {
+    cell: egui::Vec2,
+    theme: TerminalTheme,
+    atlas: FontAtlas,
+}
+
+/// 🖥️ 终端网格视图 - 按单元格直接绘制vt100屏幕，并把键盘输入转换为发往PTY的字节序列
+pub struct TerminalView {
+    font_size: f32,
+    blink_epoch: f64, // 光标闪烁相位起点，有输入时重置，保证打字时光标常亮
+    scroll_remainder: f32, // 触控板等不足一行的滚动量，累积到下一帧
+    menu_paste: bool,      // 右键菜单请求了粘贴，下一个Paste事件按粘贴处理
+    clipboard_query: bool, // 远端请求读取剪贴板，下一个Paste事件作为回复内容
+    mouse_button: Option<MouseButton>, // 鼠标报告模式下在终端内按住的按键
+    mouse_cell: Option<(u16, u16)>,    // 上次报告的鼠标位置，只在移到新单元格时报告移动
+    rows: Vec<Option<RowLayout>>,      // 各屏幕行的排版，只有damage中的行在下一帧重排
+    layout_key: Option<LayoutKey>,
+}
+
+/// 一帧要绘制的终端状态
+pub struct TerminalFrame<'a> {
+    pub lines: &'a [TerminalLine],
+    pub cursor: Option<(u16, u16)>, // 0起始的（行，列），光标隐藏或正在回滚时为None
+    pub cursor_style: CursorStyle,
+    pub key_modes: KeyModes,
+    pub mouse: Option<MouseReporting>, // 远端程序开启的鼠标报告，None时鼠标用于本地选择和回滚
+    pub highlights: &'a [Highlight],
+    pub blocks: &'a [BlockMarker],
+    pub has_selection: bool,
+    pub theme: &'a TerminalTheme, // 默认前景/背景、光标和选区的颜色
+    pub damage: &'a Damage,       // 自上一帧以来变化的行和光标
+    pub flash: bool,              // 视觉响铃：整个终端蒙上一层前景色
+}
+
+/// 命令块执行状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockStatus {
+    Running,
+    Success,
+    Failure,
+    Unknown, // shell没有报告退出码
+}
+
+impl BlockStatus {
+    pub fn color(self) -> egui::Color32 {
+        match self {
+            BlockStatus::Running => BLOCK_RUNNING,
+            BlockStatus::Success => BLOCK_SUCCESS,
+            BlockStatus::Failure => BLOCK_FAILURE,
+            BlockStatus::Unknown => BLOCK_UNKNOWN,
+        }
+    }
+}
+
+/// 屏幕上可见的命令块（Shell集成），行区间为 [start_row, end_row)
+#[derive(Debug, Clone)]
+pub struct BlockMarker {
+    pub start_row: u16,
+    pub end_row: u16,
+    pub header: bool, // 块的第一行（提示符）在屏幕上，绘制分隔线和状态
+    pub status: BlockStatus,
+    pub label: String,
+}
+
+/// 高亮种类
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HighlightKind {
+    Match,
+    CurrentMatch,
+    Selection,
+    Link, // 按住Ctrl悬停的链接，画下划线
+}
+
+/// 屏幕上的高亮区域（搜索匹配、选区、链接），列区间为 [start_col, end_col)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Highlight {
+    pub row: u16,
+    pub start_col: u16,
+    pub end_col: u16,
+    pub kind: HighlightKind,
+}
+
+/// 🖱️ 鼠标选择动作，坐标为屏幕上的（行，列）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectionGesture {
+    Start { row: u16, col: u16, mode: SelectionMode },
+    Extend { row: u16, col: u16 },
+    Finish,
+    Clear,
+}
+
+/// 一帧的交互结果
+#[derive(Default)]
+pub struct TerminalViewOutput {
+    pub input: Vec<String>,                   // 待发送到PTY的数据，按事件顺序
+    pub scroll: isize,                        // 回滚的行数，正数表示向上翻看历史
+    pub selection: Option<SelectionGesture>,
+    pub copy: bool,                           // Ctrl+Shift+C 或右键菜单复制
+    pub paste: Option<String>,                // Ctrl+Shift+V / Shift+Insert 或右键菜单粘贴的剪贴板内容
+    pub mouse: Vec<Vec<u8>>,                  // 鼠标报告序列（X10编码可能不是合法UTF-8）
+    pub link_hover: Option<(u16, u16)>,       // 按住Ctrl时鼠标所在的单元格
+    pub link_click: Option<(u16, u16)>,       // Ctrl+单击的单元格
+    pub clipboard: Option<String>,            // 为OSC 52读取请求取得的剪贴板内容
+}
+
+impl TerminalView {
+    pub fn new(font_size: f32) -> Self {
+        Self {
+            font_size,
+            blink_epoch: 0.0,
+            scroll_remainder: 0.0,
+            menu_paste: false,
+            clipboard_query: false,
+            mouse_button: None,
+            mouse_cell: None,
+            rows: Vec::new(),
+            layout_key: None,
+        }
+    }
+
+    /// 📋 读取剪贴板（egui只能异步读取），内容在之后某一帧的 `TerminalViewOutput::clipboard` 中返回
+    pub fn request_clipboard(&mut self, ctx: &egui::Context) {
+        self.clipboard_query = true;
+        ctx.send_viewport_cmd(egui::ViewportCommand::RequestPaste);
+    }
+
+    /// 调整字号，下一帧按新的单元格尺寸重新计算行列数
+    pub fn set_font_size(&mut self, font_size: f32) {
+        self.font_size = font_size;
+    }
+
+    fn font_id(&self) -> egui::FontId {
+        egui::FontId::monospace(self.font_size)
+    }
+
+    /// 单元格尺寸：等宽字体中 'M' 的宽度 × 行高
+    pub fn cell_size(&self, ui: &egui::Ui) -> egui::Vec2 {
+        let font_id = self.font_id();
+        ui.fonts(|fonts| egui::vec2(fonts.glyph_width(&font_id, 'M'), fonts.row_height(&font_id)))
+    }
+
+    /// 📐 给定区域能容纳的行列数（行，列）
+    pub fn grid_size(&self, ui: &egui::Ui, size: egui::Vec2) -> (u16, u16) {
+        let cell = self.cell_size(ui);
+        let cols = (size.x / cell.x).floor().clamp(20.0, 1000.0) as u16;
+        let rows = (size.y / cell.y).floor().clamp(5.0, 500.0) as u16;
+        (rows, cols)
+    }
+
+    /// 绘制屏幕并收集输入和滚动
+    pub fn show(&mut self, ui: &mut egui::Ui, id: egui::Id, frame: &TerminalFrame) -> TerminalViewOutput {
+        let rect = ui.available_rect_before_wrap();
+        let response = ui.interact(rect, id, egui::Sense::click_and_drag());
+        ui.advance_cursor_after_rect(rect);
+
+        // 点击获取焦点；没有其他控件持有焦点时自动获取，打开Tab即可输入
+        if response.clicked() || ui.memory(|m| m.focused().is_none()) {
+            response.request_focus();
+        }
+        let focused = response.has_focus();
+
+        let cell = self.cell_size(ui);
+        let page = ((rect.height() / cell.y).floor() as isize - 1).max(1);
+        let mut output = if focused {
+            self.collect_input(ui, id, rect, frame.key_modes, page)
+        } else {
+            TerminalViewOutput::default()
+        };
+        let now = ui.input(|i| i.time);
+        // 输入或光标移动时重置闪烁相位，输出过程中光标保持常亮
+        if !output.input.is_empty() || frame.damage.cursor {
+            self.blink_epoch = now;
+        }
+
+        let cols = (rect.width() / cell.x).floor().max(1.0);
+        let rows = frame.lines.len().max(1) as f32;
+        // 屏幕坐标 → （行，列），拖出视图时取最近的单元格
+        let to_cell = |pos: egui::Pos2| {
+            let offset = pos - rect.min;
+            let row = (offset.y / cell.y).floor().clamp(0.0, rows - 1.0) as u16;
+            let col = (offset.x / cell.x).floor().clamp(0.0, cols - 1.0) as u16;
+            (row, col)
+        };
+
+        // 远端程序开启鼠标报告时鼠标事件都发给它，按住Shift仍可本地选择和回滚
+        let reporting = frame.mouse.filter(|_| !ui.input(|i| i.modifiers.shift));
+        if let Some(reporting) = reporting {
+            self.report_mouse(ui, &response, cell, to_cell, reporting, &mut output);
+        } else {
+            self.mouse_button = None;
+            // 🖱️ 鼠标滚轮翻看回滚历史
+            if response.hovered() {
+                self.scroll_remainder += ui.input(|i| i.raw_scroll_delta.y) / cell.y;
+                let lines = self.scroll_remainder.trunc();
+                self.scroll_remainder -= lines;
+                output.scroll += lines as isize;
+            }
+
+            // 🔗 Ctrl+单击打开链接（macOS上为Cmd）
+            if ui.input(|i| i.modifiers.command)
+                && let Some(pos) = response.hover_pos()
+            {
+                output.link_hover = Some(to_cell(pos));
+                if response.clicked_by(egui::PointerButton::Primary) {
+                    output.link_click = output.link_hover;
+                }
+            }
+
+            self.handle_selection(ui, &response, rect, to_cell, &mut output);
+            response.context_menu(|ui| {
+                if ui.add_enabled(frame.has_selection, egui::Button::new("复制 (Ctrl+Shift+C)")).clicked() {
+                    output.copy = true;
+                    ui.close();
+                }
+                if ui.button("粘贴 (Ctrl+Shift+V)").clicked() {
+                    self.menu_paste = true;
+                    ui.ctx().send_viewport_cmd(egui::ViewportCommand::RequestPaste);
+                    ui.close();
+                }
+            });
+        }
+
+        self.update_layout(ui, cell, frame);
+
+        let painter = ui.painter_at(rect);
+        let theme = frame.theme;
+        painter.rect_filled(rect, 0.0, theme.background);
+        let row_origin = |row: usize| rect.left_top() + egui::vec2(0.0, row as f32 * cell.y);
+        // 先画背景和搜索高亮，再画文字，保证高亮不遮挡字符
+        for (row, layout) in self.rows.iter().enumerate() {
+            for (background, color) in layout.iter().flat_map(|layout| &layout.backgrounds) {
+                painter.rect_filled(background.translate(row_origin(row).to_vec2()), 0.0, *color);
+            }
+        }
+        for highlight in frame.highlights {
+            let highlight_rect = egui::Rect::from_min_max(
+                rect.left_top() + egui::vec2(highlight.start_col as f32 * cell.x, highlight.row as f32 * cell.y),
+                rect.left_top() + egui::vec2(highlight.end_col as f32 * cell.x, (highlight.row + 1) as f32 * cell.y),
+            );
+            let color = match highlight.kind {
+                HighlightKind::Match => MATCH_BG,
+                HighlightKind::CurrentMatch => CURRENT_MATCH_BG,
+                HighlightKind::Selection => theme.selection,
+                HighlightKind::Link => {
+                    let y = highlight_rect.bottom() - 1.0;
+                    painter.hline(highlight_rect.x_range(), y, egui::Stroke::new(1.5, LINK_UNDERLINE));
+                    continue;
+                }
+            };
+            painter.rect_filled(highlight_rect, 0.0, color);
+        }
+        for (row, layout) in self.rows.iter().enumerate() {
+            for (offset, galley, color, bold) in layout.iter().flat_map(|layout| &layout.texts) {
+                let pos = row_origin(row) + *offset;
+                // 等宽字体没有粗体字形，错开0.6像素重绘一次模拟加粗
+                if *bold {
+                    painter.galley(pos + egui::vec2(0.6, 0.0), galley.clone(), *color);
+                }
+                painter.galley(pos, galley.clone(), *color);
+            }
+        }
+
+        self.paint_blocks(&painter, rect, cell, frame.blocks, theme);
+        if frame.flash {
+            painter.rect_filled(rect, 0.0, theme.foreground.gamma_multiply(0.2));
+        }
+
+        let style = frame.cursor_style;
+        if let Some((row, col)) = frame.cursor
+            && let Some(line) = frame.lines.get(row as usize)
+        {
+            let blink_on = !style.blinking || ((now - self.blink_epoch) / BLINK_INTERVAL) as i64 % 2 == 0;
+            if !focused || blink_on {
+                let cell_rect = egui::Rect::from_min_size(
+                    rect.left_top() + egui::vec2(col as f32 * cell.x, row as f32 * cell.y),
+                    cell,
+                );
+                self.paint_cursor(&painter, cell_rect, line, col, focused.then_some(style.shape), theme);
+            }
+            if focused && style.blinking {
+                let elapsed = (now - self.blink_epoch) % BLINK_INTERVAL;
+                ui.ctx().request_repaint_after(std::time::Duration::from_secs_f64(BLINK_INTERVAL - elapsed));
+            }
+        }
+
+        output
+    }
+
+    /// 🖱️ 左键拖动选择（Alt+拖动为矩形选择），双击选单词，三击选整行；拖出视图时自动滚动
+    fn handle_selection(
+        &self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        rect: egui::Rect,
+        to_cell: impl Fn(egui::Pos2) -> (u16, u16),
+        output: &mut TerminalViewOutput,
+    ) {
+        let primary = egui::PointerButton::Primary;
+
+        let gesture = if response.triple_clicked_by(primary) {
+            response.interact_pointer_pos().map(|pos| {
+                let (row, col) = to_cell(pos);
+                SelectionGesture::Start { row, col, mode: SelectionMode::Line }
+            })
+        } else if response.double_clicked_by(primary) {
+            response.interact_pointer_pos().map(|pos| {
+                let (row, col) = to_cell(pos);
+                SelectionGesture::Start { row, col, mode: SelectionMode::Word }
+            })
+        } else if response.drag_started_by(primary) {
+            let (origin, alt) = ui.input(|i| (i.pointer.press_origin(), i.modifiers.alt));
+            origin.map(|pos| {
+                let (row, col) = to_cell(pos);
+                let mode = if alt { SelectionMode::Block } else { SelectionMode::Normal };
+                SelectionGesture::Start { row, col, mode }
+            })
+        } else if response.dragged_by(primary) {
+            response.interact_pointer_pos().map(|pos| {
+                if pos.y < rect.top() {
+                    output.scroll += 1;
+                } else if pos.y > rect.bottom() {
+                    output.scroll -= 1;
+                }
+                let (row, col) = to_cell(pos);
+                SelectionGesture::Extend { row, col }
+            })
+        } else if response.drag_stopped_by(primary) {
+            Some(SelectionGesture::Finish)
+        } else if response.clicked_by(primary) {
+            Some(SelectionGesture::Clear)
+        } else {
+            None
+        };
+        output.selection = gesture;
+    }
+
+    /// 🖱️ 把按键、拖动、移动和滚轮事件编码为鼠标报告；在终端内按下的按键，移出终端后释放也会报告
+    fn report_mouse(
+        &mut self,
+        ui: &egui::Ui,
+        response: &egui::Response,
+        cell: egui::Vec2,
+        to_cell: impl Fn(egui::Pos2) -> (u16, u16),
+        reporting: MouseReporting,
+        output: &mut TerminalViewOutput,
+    ) {
+        let hovered = response.hovered();
+        let mut events = Vec::new();
+        ui.input(|i| {
+            let mods = KeyModifiers {
+                shift: i.modifiers.shift,
+                alt: i.modifiers.alt,
+                ctrl: i.modifiers.ctrl,
+            };
+            for event in &i.events {
+                match event {
+                    egui::Event::PointerButton { pos, button, pressed, .. } => {
+                        let button = match button {
+                            egui::PointerButton::Primary => MouseButton::Left,
+                            egui::PointerButton::Middle => MouseButton::Middle,
+                            egui::PointerButton::Secondary => MouseButton::Right,
+                            _ => continue,
+                        };
+                        if *pressed && hovered {
+                            self.mouse_button = Some(button);
+                            events.push((MouseEventKind::Press(button), *pos, mods));
+                        } else if !*pressed && self.mouse_button == Some(button) {
+                            self.mouse_button = None;
+                            events.push((MouseEventKind::Release(button), *pos, mods));
+                        }
+                    }
+                    egui::Event::PointerMoved(pos) if hovered || self.mouse_button.is_some() => {
+                        events.push((MouseEventKind::Motion(self.mouse_button), *pos, mods));
+                    }
+                    _ => {}
+                }
+            }
+
+            // 滚轮每滚动一行报告一次
+            if hovered && let Some(pos) = i.pointer.hover_pos() {
+                self.scroll_remainder += i.raw_scroll_delta.y / cell.y;
+                let lines = self.scroll_remainder.trunc();
+                self.scroll_remainder -= lines;
+                let button = if lines > 0.0 { MouseButton::WheelUp } else { MouseButton::WheelDown };
+                for _ in 0..lines.abs() as usize {
+                    events.push((MouseEventKind::Press(button), pos, mods));
+                }
+            }
+        });
+
+        for (kind, pos, mods) in events {
+            let (row, col) = to_cell(pos);
+            // 同一单元格内的移动不重复报告
+            if let MouseEventKind::Motion(_) = kind
+                && self.mouse_cell == Some((row, col))
+            {
+                continue;
+            }
+            self.mouse_cell = Some((row, col));
+            if let Some(sequence) = encode_mouse(MouseEvent { kind, row, col, mods }, reporting) {
+                crate::app_log!(debug, "UI", "🖱️ {:?} ({}, {}) -> {:?}", kind, row, col, sequence);
+                output.mouse.push(sequence);
+            }
+        }
+    }
+
+    /// 🧱 命令块：左侧状态色条，提示符上方的分隔线，提示符行右侧的退出码和耗时
+    fn paint_blocks(
+        &self,
+        painter: &egui::Painter,
+        rect: egui::Rect,
+        cell: egui::Vec2,
+        blocks: &[BlockMarker],
+        theme: &TerminalTheme,
+    ) {
+        let label_font = egui::FontId::proportional(self.font_size * 0.8);
+        for block in blocks {
+            let color = block.status.color();
+            let top = rect.top() + block.start_row as f32 * cell.y;
+            let bottom = rect.top() + block.end_row as f32 * cell.y;
+            let bar = egui::Rect::from_min_max(egui::pos2(rect.left(), top), egui::pos2(rect.left() + 2.0, bottom));
+            painter.rect_filled(bar, 0.0, color);
+            if !block.header {
+                continue;
+            }
+            painter.hline(rect.x_range(), top, egui::Stroke::new(1.0, theme.foreground.gamma_multiply(0.2)));
+            if !block.label.is_empty() {
+                let galley = painter.layout_no_wrap(block.label.clone(), label_font.clone(), color);
+                let pos = egui::pos2(rect.right() - galley.size().x - 6.0, top + (cell.y - galley.size().y) / 2.0);
+                painter.rect_filled(egui::Rect::from_min_size(pos, galley.size()).expand(2.0), 3.0, theme.background);
+                painter.galley(pos, galley, color);
+            }
+        }
+    }
+
+    /// 实际显示的前景/背景色（处理反显）
+    fn segment_colors(segment: &TerminalSegment, theme: &TerminalTheme) -> (egui::Color32, Option<egui::Color32>) {
+        let fg = segment.color.map_or(theme.foreground, |c| theme.color(c));
+        let bg = segment.background_color.map(|c| theme.color(c));
+        if segment.inverse {
+            (bg.unwrap_or(theme.background), Some(fg))
+        } else {
+            (fg, bg)
+        }
+    }
+
+    /// 🩹 按damage丢弃变化行的排版，再排版所有缺少排版的行；单元格尺寸、配色或字体变化时全部重排
+    fn update_layout(&mut self, ui: &egui::Ui, cell: egui::Vec2, frame: &TerminalFrame) {
+        let atlas = ui.fonts(|fonts| fonts.texture_atlas());
+        let valid = self.rows.len() == frame.lines.len()
+            && self
+                .layout_key
+                .as_ref()
+                .is_some_and(|key| key.cell == cell && key.theme == *frame.theme && Arc::ptr_eq(&key.atlas, &atlas));
+        if !valid {
+            self.rows.clear();
+            self.rows.resize_with(frame.lines.len(), || None);
+            self.layout_key = Some(LayoutKey {
+                cell,
+                theme: frame.theme.clone(),
+                atlas,
+            });
+        } else if !frame.damage.is_empty() {
+            for &row in &frame.damage.rows {
+                if let Some(layout) = self.rows.get_mut(row as usize) {
+                    *layout = None;
+                }
+            }
+        }
+
+        for (row, line) in frame.lines.iter().enumerate() {
+            if self.rows[row].is_none() {
+                let layout = self.layout_row(ui, line, cell, frame.theme);
+                self.rows[row] = Some(layout);
+            }
+        }
+    }
+
+    /// 排版一行：背景色块和文字
+    fn layout_row(&self, ui: &egui::Ui, line: &TerminalLine, cell: egui::Vec2, theme: &TerminalTheme) -> RowLayout {
+        let mut layout = RowLayout::default();
+        for segment in &line.segments {
+            let segment_rect = egui::Rect::from_min_size(
+                egui::pos2(segment.col as f32 * cell.x, 0.0),
+                egui::vec2(segment.width as f32 * cell.x, cell.y),
+            );
+            let (fg, bg) = Self::segment_colors(segment, theme);
+            if let Some(bg) = bg {
+                layout.backgrounds.push((segment_rect, bg));
+            }
+            if segment.text.trim().is_empty() && !segment.underline && segment.link.is_none() {
+                continue;
+            }
+
+            let galley = self.layout_text(ui.ctx(), &segment.text, fg, segment);
+            let offset = Self::text_pos(segment_rect, &segment.text, &galley).to_vec2();
+            layout.texts.push((offset, galley, fg, segment.bold));
+        }
+        layout
+    }
+
+    /// 文字排版，OSC 8超链接加下划线
+    fn layout_text(&self, ctx: &egui::Context, text: &str, color: egui::Color32, segment: &TerminalSegment) -> Arc<egui::Galley> {
+        let underline = segment.underline || segment.link.is_some();
+        let format = egui::TextFormat {
+            font_id: self.font_id(),
+            color,
+            italics: segment.italic,
+            underline: if underline { egui::Stroke::new(1.0, color) } else { egui::Stroke::NONE },
+            ..Default::default()
+        };
+        ctx.fonts(|fonts| fonts.layout_job(egui::text::LayoutJob::single_section(text.to_string(), format)))
+    }
+
+    /// 文字在rect中的位置；宽字符/非ASCII字符单独成段，在其单元格内居中
+    fn text_pos(rect: egui::Rect, text: &str, galley: &egui::Galley) -> egui::Pos2 {
+        let mut pos = rect.left_top();
+        if !text.is_ascii() {
+            pos.x += (rect.width() - galley.size().x) / 2.0;
+        }
+        pos
+    }
+
+    /// 直接绘制文字（光标块上的字符）
+    fn paint_text(&self, painter: &egui::Painter, rect: egui::Rect, text: &str, color: egui::Color32, segment: &TerminalSegment) {
+        let galley = self.layout_text(painter.ctx(), text, color, segment);
+        let pos = Self::text_pos(rect, text, &galley);
+        if segment.bold {
+            painter.galley(pos + egui::vec2(0.6, 0.0), galley.clone(), color);
+        }
+        painter.galley(pos, galley, color);
+    }
+
+    fn paint_cursor(
+        &self,
+        painter: &egui::Painter,
+        cell_rect: egui::Rect,
+        line: &TerminalLine,
+        col: u16,
+        shape: Option<CursorShape>, // None：失去焦点，画空心框
+        theme: &TerminalTheme,
+    ) {
+        // 找到光标所在的字符，宽字符上的光标占两格
+        let under = line
+            .segments
+            .iter()
+            .find(|s| s.col <= col && col < s.col + s.width)
+            .map(|s| {
+                if s.text.is_ascii() {
+                    let ch = s.text.chars().nth((col - s.col) as usize).unwrap_or(' ');
+                    (ch.to_string(), 1, s)
+                } else {
+                    (s.text.clone(), s.width, s)
+                }
+            });
+        let width = under.as_ref().map_or(1, |(_, width, _)| *width);
+        // 默认颜色的字符上用主题的光标色，有颜色的字符上用其前景色
+        let (fg, bg) = match &under {
+            Some((_, _, s)) if s.color.is_some() || s.inverse => Self::segment_colors(s, theme),
+            Some((_, _, s)) => (theme.cursor, s.background_color.map(|c| theme.color(c))),
+            None => (theme.cursor, None),
+        };
+        let rect = egui::Rect::from_min_size(cell_rect.min, egui::vec2(width as f32 * cell_rect.width(), cell_rect.height()));
+
+        let Some(shape) = shape else {
+            painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, fg), egui::StrokeKind::Inside);
+            return;
+        };
+        match shape {
+            CursorShape::Block => {
+                painter.rect_filled(rect, 0.0, fg);
+                // 在光标块上用背景色重绘字符
+                if let Some((text, _, segment)) = &under
+                    && !text.trim().is_empty()
+                {
+                    self.paint_text(painter, rect, text, bg.unwrap_or(theme.background), segment);
+                }
+            }
+            CursorShape::Underline => {
+                let bar = egui::Rect::from_min_max(egui::pos2(rect.left(), rect.bottom() - 2.0), rect.max);
+                painter.rect_filled(bar, 0.0, fg);
+            }
+            CursorShape::Bar => {
+                let bar = egui::Rect::from_min_max(rect.min, egui::pos2(rect.left() + 2.0, rect.bottom()));
+                painter.rect_filled(bar, 0.0, fg);
+            }
+        }
+    }
+
+    /// ⌨️ 把本帧的键盘/输入法/剪贴板事件转换为发往PTY的数据；Shift+PageUp/PageDown按page行回滚
+    fn collect_input(&mut self, ui: &egui::Ui, id: egui::Id, rect: egui::Rect, modes: KeyModes, page: isize) -> TerminalViewOutput {
+        // Tab、方向键、Esc都交给终端，不用于切换焦点
+        ui.memory_mut(|m| {
+            m.set_focus_lock_filter(
+                id,
+                egui::EventFilter {
+                    tab: true,
+                    horizontal_arrows: true,
+                    vertical_arrows: true,
+                    escape: true,
+                },
+            )
+        });
+        // 输入法候选框跟随终端区域
+        ui.ctx().output_mut(|o| {
+            o.ime = Some(egui::output::IMEOutput { rect, cursor_rect: rect });
+        });
+
+        let menu_paste = std::mem::take(&mut self.menu_paste);
+        let clipboard_query = self.clipboard_query;
+        let output = ui.input(|i| {
+            let mut output = TerminalViewOutput::default();
+            let input = &mut output.input;
+            let mut scroll = 0;
+            // Ctrl+Shift+C/V（macOS上为Cmd+C/V）是复制粘贴，不带Shift的Ctrl+C/V是控制字符
+            let clipboard_shortcut = i.modifiers.shift || i.modifiers.mac_cmd;
+            let mut skip_text = None; // 已按小键盘应用模式编码的按键，丢弃随后的同名Text事件
+            for event in &i.events {
+                match event {
+                    egui::Event::Text(text) if skip_text.take() == Some(text.as_str()) => {}
+                    // Alt作为Meta键：ESC前缀
+                    egui::Event::Text(text) if i.modifiers.alt => {
+                        let mods = KeyModifiers { alt: true, ..Default::default() };
+                        input.extend(text.chars().filter_map(|ch| encode_key(TermKey::Char(ch), mods, modes)));
+                    }
+                    egui::Event::Text(text) => input.push(text.clone()),
+                    egui::Event::Ime(egui::ImeEvent::Commit(text)) if !text.is_empty() => input.push(text.clone()),
+                    egui::Event::Paste(text) if clipboard_query && output.clipboard.is_none() => {
+                        output.clipboard = Some(text.clone());
+                    }
+                    egui::Event::Paste(text) if clipboard_shortcut || menu_paste => output.paste = Some(text.clone()),
+                    egui::Event::Copy if clipboard_shortcut => output.copy = true,
+                    // egui把Ctrl+C/X/V转换成了复制/剪切/粘贴事件，终端里它们是控制字符
+                    egui::Event::Paste(_) => input.push("\x16".to_string()),
+                    egui::Event::Copy => input.push("\x03".to_string()),
+                    egui::Event::Cut => input.push("\x18".to_string()),
+                    egui::Event::Key { key: egui::Key::PageUp, pressed: true, modifiers, .. } if modifiers.shift_only() => {
+                        scroll += page;
+                    }
+                    egui::Event::Key { key: egui::Key::PageDown, pressed: true, modifiers, .. } if modifiers.shift_only() => {
+                        scroll -= page;
+                    }
+                    egui::Event::Key { key, physical_key, pressed: true, modifiers, .. } => {
+                        let Some(term_key) = term_key(*key, *physical_key, *modifiers, modes) else {
+                            continue;
+                        };
+                        let mods = KeyModifiers {
+                            shift: modifiers.shift,
+                            alt: modifiers.alt,
+                            ctrl: modifiers.ctrl,
+                        };
+                        if let Some(sequence) = encode_key(term_key, mods, modes) {
+                            crate::app_log!(debug, "UI", "⌨️ {:?} {:?} -> {:?}", term_key, mods, sequence);
+                            if let TermKey::Keypad('+') = term_key {
+                                skip_text = Some("+");
+                            }
+                            input.push(sequence);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            output.scroll = scroll;
+            output
+        });
+        if output.clipboard.is_some() {
+            self.clipboard_query = false;
+        }
+        output
+    }
+}
+
+/// egui按键 → 终端按键。字母、数字和符号只在按住Ctrl时编码，其余情况由Text事件发送
+fn term_key(key: egui::Key, physical_key: Option<egui::Key>, modifiers: egui::Modifiers, modes: KeyModes) -> Option<TermKey> {
+    use egui::Key;
+
+    let term_key = match key {
+        Key::Enter => TermKey::Enter,
+        Key::Backspace => TermKey::Backspace,
+        Key::Tab => TermKey::Tab,
+        Key::Escape => TermKey::Escape,
+        Key::ArrowUp => TermKey::Up,
+        Key::ArrowDown => TermKey::Down,
+        Key::ArrowRight => TermKey::Right,
+        Key::ArrowLeft => TermKey::Left,
+        Key::Home => TermKey::Home,
+        Key::End => TermKey::End,
+        Key::Insert => TermKey::Insert,
+        Key::Delete => TermKey::Delete,
+        Key::PageUp => TermKey::PageUp,
+        Key::PageDown => TermKey::PageDown,
+        // egui把小键盘数字、回车等合并到了主键盘，只有小键盘+号的物理键码是独立的
+        _ if physical_key == Some(Key::Plus) && modes.application_keypad => TermKey::Keypad('+'),
+        Key::Space if modifiers.ctrl => TermKey::Char(' '),
+        _ => {
+            let name = key.name();
+            if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse().ok()) {
+                TermKey::F(n)
+            } else if modifiers.ctrl && let Some(ch) = single_char(key.symbol_or_name()) {
+                TermKey::Char(ch)
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(term_key)
+}
+
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    let ch = chars.next()?;
+    chars.next().is_none().then_some(ch)
+}
@@ -0,0 +1,42 @@
+use eframe::egui;
+
+use std::time::Duration;
+
+/// 提示显示的时长（秒）
+const TOAST_SECONDS: f64 = 3.0;
+
+/// 🍞 轻量提示 - 在窗口右下角短暂显示，不抢焦点、不拦截鼠标
+#[derive(Debug, Default)]
+pub struct Toasts {
+    items: Vec<(String, f64)>, // (消息, 消失的时间)
+}
+
+impl Toasts {
+    pub fn push(&mut self, ctx: &egui::Context, text: impl Into<String>) {
+        let expires = ctx.input(|i| i.time) + TOAST_SECONDS;
+        self.items.push((text.into(), expires));
+        ctx.request_repaint();
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, id: egui::Id) {
+        let now = ctx.input(|i| i.time);
+        self.items.retain(|(_, expires)| *expires > now);
+        let Some(next) = self.items.iter().map(|(_, expires)| *expires).reduce(f64::min) else {
+            return;
+        };
+        // 到时间后重绘一次，让提示消失
+        ctx.request_repaint_after(Duration::from_secs_f64(next - now));
+
+        egui::Area::new(id)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -16.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                for (text, _) in &self.items {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(text);
+                    });
+                }
+            });
+    }
+}
@@ -77,6 +77,11 @@ impl Logger {
         }
     }
 
+    /// 设置最低输出级别（性能测试时关闭逐帧的调试日志）
+    pub fn set_min_level(&mut self, level: LogLevel) {
+        self.min_level = level;
+    }
+
     fn should_log(&self, level: &LogLevel) -> bool {
         match (&self.min_level, level) {
             (LogLevel::Debug, _) => true,
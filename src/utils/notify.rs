@@ -0,0 +1,65 @@
+// 桌面通知 - 调用各平台自带的通知命令，标题和正文作为参数传入，不经过shell拼接
+
+use std::process::Command;
+
+/// 🔔 在后台线程发送桌面通知，失败时只记录日志
+pub fn desktop_notification(title: &str, body: &str) {
+    let title = title.to_string();
+    let body = body.to_string();
+    std::thread::spawn(move || match notification_command(&title, &body).output() {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => crate::app_log!(
+            warn,
+            "Notify",
+            "桌面通知失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => crate::app_log!(warn, "Notify", "无法发送桌面通知: {}", e),
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn notification_command(title: &str, body: &str) -> Command {
+    // Windows没有命令行通知工具，通过PowerShell调用Toast API；内容经环境变量传入，避免转义问题
+    const SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+$template = [Windows.UI.Notifications.ToastTemplateType]::ToastText02
+$xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent($template)
+$texts = $xml.GetElementsByTagName('text')
+$texts.Item(0).AppendChild($xml.CreateTextNode($env:AY_NOTIFY_TITLE)) | Out-Null
+$texts.Item(1).AppendChild($xml.CreateTextNode($env:AY_NOTIFY_BODY)) | Out-Null
+$toast = [Windows.UI.Notifications.ToastNotification]::new($xml)
+$appId = '{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe'
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($appId).Show($toast)
+"#;
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-WindowStyle", "Hidden", "-Command", SCRIPT])
+        .env("AY_NOTIFY_TITLE", title)
+        .env("AY_NOTIFY_BODY", body);
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(title: &str, body: &str) -> Command {
+    let mut command = Command::new("osascript");
+    command.args([
+        "-e",
+        "on run argv",
+        "-e",
+        "display notification (item 2 of argv) with title (item 1 of argv)",
+        "-e",
+        "end run",
+        title,
+        body,
+    ]);
+    command
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn notification_command(title: &str, body: &str) -> Command {
+    // freedesktop通知（libnotify的notify-send）
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name=AY Dev Tool", "--", title, body]);
+    command
+}